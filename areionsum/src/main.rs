@@ -0,0 +1,562 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use areion::digest::{Digest, Update, VariableOutput};
+use areion::{
+    Areion256Sponge, Areion512Md, Areion512MdF, Areion512Mmo, Areion512MmoF, AreionHaifaVar,
+};
+use clap::{Parser, ValueEnum};
+
+/// Print or check Areion checksums.
+///
+/// With no FILE, or when FILE is -, read standard input.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// The hash algorithm to use.
+    #[arg(short, long, value_enum, default_value_t = Algorithm::Md)]
+    algorithm: Algorithm,
+
+    /// The digest length in bits for Areion512-HAIFA, a multiple of 8 no greater than 512.
+    #[arg(short, long, value_parser = parse_length)]
+    length: Option<usize>,
+
+    /// Read checksums from the FILEs and check them.
+    #[arg(short, long)]
+    check: bool,
+
+    /// Create a BSD-style checksum.
+    #[arg(long)]
+    tag: bool,
+
+    /// Read in binary mode.
+    #[arg(short, long, overrides_with = "text")]
+    binary: bool,
+
+    /// Read in text mode (default).
+    #[arg(short, long, conflicts_with = "tag")]
+    text: bool,
+
+    /// Hash the contents of directories recursively.
+    #[arg(short, long, conflicts_with = "check")]
+    recursive: bool,
+
+    /// Don't fail or report status for missing files.
+    #[arg(long, requires = "check")]
+    ignore_missing: bool,
+
+    /// Don't print OK for each successfully verified file.
+    #[arg(long, requires = "check")]
+    quiet: bool,
+
+    /// Don't output anything, status code shows success.
+    #[arg(long, requires = "check")]
+    status: bool,
+
+    /// Exit non-zero for improperly formatted checksum lines.
+    #[arg(long, requires = "check")]
+    strict: bool,
+
+    /// Warn about improperly formatted checksum lines.
+    #[arg(short, long, requires = "check")]
+    warn: bool,
+
+    /// The files to hash or check.
+    files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Algorithm {
+    /// Areion512-MD
+    Md,
+    /// Areion512-MD-F
+    MdF,
+    /// Areion512-MMO
+    Mmo,
+    /// Areion512-MMO-F
+    MmoF,
+    /// Areion512-256-Sponge
+    Sponge,
+    /// Areion512-HAIFA
+    Haifa,
+}
+
+/// An algorithm and its output length in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Spec {
+    algorithm: Algorithm,
+    len: usize,
+}
+
+impl Spec {
+    const HAIFA_TAG: &'static str = "Areion512-HAIFA-";
+
+    fn new(algorithm: Algorithm, len: Option<usize>) -> Spec {
+        let len = match algorithm {
+            Algorithm::Mmo | Algorithm::MmoF => 64,
+            Algorithm::Haifa => len.unwrap_or(64),
+            _ => 32,
+        };
+        Spec { algorithm, len }
+    }
+
+    fn from_tag(tag: &str) -> Option<Spec> {
+        if let Some(bits) = tag.strip_prefix(Self::HAIFA_TAG) {
+            let len = parse_length(bits).ok()?;
+            return Some(Spec::new(Algorithm::Haifa, Some(len)));
+        }
+        let algorithm = match tag {
+            "Areion512-MD" => Algorithm::Md,
+            "Areion512-MD-F" => Algorithm::MdF,
+            "Areion512-MMO" => Algorithm::Mmo,
+            "Areion512-MMO-F" => Algorithm::MmoF,
+            "Areion512-256-Sponge" => Algorithm::Sponge,
+            _ => return None,
+        };
+        Some(Spec::new(algorithm, None))
+    }
+
+    fn tag(&self) -> String {
+        match self.algorithm {
+            Algorithm::Md => "Areion512-MD".into(),
+            Algorithm::MdF => "Areion512-MD-F".into(),
+            Algorithm::Mmo => "Areion512-MMO".into(),
+            Algorithm::MmoF => "Areion512-MMO-F".into(),
+            Algorithm::Sponge => "Areion512-256-Sponge".into(),
+            Algorithm::Haifa => format!("{}{}", Self::HAIFA_TAG, self.len * 8),
+        }
+    }
+
+    fn hasher(&self) -> Hasher {
+        match self.algorithm {
+            Algorithm::Md => Hasher::Md(Areion512Md::new()),
+            Algorithm::MdF => Hasher::MdF(Areion512MdF::new()),
+            Algorithm::Mmo => Hasher::Mmo(Areion512Mmo::new()),
+            Algorithm::MmoF => Hasher::MmoF(Areion512MmoF::new()),
+            Algorithm::Sponge => Hasher::Sponge(Areion256Sponge::new()),
+            Algorithm::Haifa => Hasher::Haifa(
+                AreionHaifaVar::new(self.len).expect("length should have been validated"),
+            ),
+        }
+    }
+
+    fn hash_path(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut hasher = self.hasher();
+        if path == Path::new("-") {
+            areion::update_reader(&mut hasher, io::stdin().lock())?;
+        } else {
+            areion::update_file(&mut hasher, path)?;
+        }
+        Ok(hasher.finalize())
+    }
+}
+
+enum Hasher {
+    Md(Areion512Md),
+    MdF(Areion512MdF),
+    Mmo(Areion512Mmo),
+    MmoF(Areion512MmoF),
+    Sponge(Areion256Sponge),
+    Haifa(AreionHaifaVar),
+}
+
+impl Update for Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md(h) => Digest::update(h, data),
+            Hasher::MdF(h) => Digest::update(h, data),
+            Hasher::Mmo(h) => Digest::update(h, data),
+            Hasher::MmoF(h) => Digest::update(h, data),
+            Hasher::Sponge(h) => Digest::update(h, data),
+            Hasher::Haifa(h) => Update::update(h, data),
+        }
+    }
+}
+
+impl Hasher {
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Md(h) => h.finalize().to_vec(),
+            Hasher::MdF(h) => h.finalize().to_vec(),
+            Hasher::Mmo(h) => h.finalize().to_vec(),
+            Hasher::MmoF(h) => h.finalize().to_vec(),
+            Hasher::Sponge(h) => h.finalize().to_vec(),
+            Hasher::Haifa(h) => {
+                let mut out = vec![0u8; h.output_size()];
+                h.finalize_variable(&mut out).expect("buffer should be the output size");
+                out
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    if args.length.is_some() && args.algorithm != Algorithm::Haifa {
+        eprintln!("areionsum: --length is only supported with --algorithm haifa");
+        return ExitCode::FAILURE;
+    }
+
+    let files = if args.files.is_empty() { vec![PathBuf::from("-")] } else { args.files.clone() };
+    let ok = if args.check { check(&args, &files) } else { hash(&args, &files) };
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Hashes the given files, printing a checksum line for each. Returns `false` if any file couldn't
+/// be read.
+fn hash(args: &Args, files: &[PathBuf]) -> bool {
+    let spec = Spec::new(args.algorithm, args.length);
+    let mut ok = true;
+    for path in files {
+        let paths = if args.recursive {
+            match walk(path) {
+                Ok(paths) => paths,
+                Err(e) => {
+                    error(path, &e);
+                    ok = false;
+                    continue;
+                }
+            }
+        } else {
+            vec![path.clone()]
+        };
+
+        for path in paths {
+            match spec.hash_path(&path) {
+                Ok(digest) => {
+                    let name = path.to_string_lossy();
+                    let (escaped, name) = escape(&name);
+                    let prefix = if escaped { "\\" } else { "" };
+                    if args.tag {
+                        println!("{prefix}{} ({name}) = {}", spec.tag(), to_hex(&digest));
+                    } else {
+                        let mode = if args.binary { '*' } else { ' ' };
+                        println!("{prefix}{} {mode}{name}", to_hex(&digest));
+                    }
+                }
+                Err(e) => {
+                    error(&path, &e);
+                    ok = false;
+                }
+            }
+        }
+    }
+    ok
+}
+
+/// Recursively lists the files in `path` in sorted order. Symbolic links to directories are not
+/// followed.
+fn walk(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if path == Path::new("-") || !fs::symlink_metadata(path)?.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut entries =
+        fs::read_dir(path)?.map(|e| e.map(|e| e.path())).collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        paths.extend(walk(&entry)?);
+    }
+    Ok(paths)
+}
+
+/// Verifies the checksum lines in the given manifests. Returns `false` if any file failed to
+/// verify, couldn't be read, or (in strict mode) if any line was improperly formatted.
+fn check(args: &Args, manifests: &[PathBuf]) -> bool {
+    let default = Spec::new(args.algorithm, args.length);
+    let mut ok = true;
+    for manifest in manifests {
+        let reader: Box<dyn BufRead> = if manifest == Path::new("-") {
+            Box::new(io::stdin().lock())
+        } else {
+            match File::open(manifest) {
+                Ok(f) => Box::new(BufReader::new(f)),
+                Err(e) => {
+                    error(manifest, &e);
+                    ok = false;
+                    continue;
+                }
+            }
+        };
+
+        let (mut formatted, mut verified) = (0usize, 0usize);
+        let (mut improper, mut unreadable, mut mismatched) = (0usize, 0usize, 0usize);
+        for (i, line) in reader.lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    error(manifest, &e);
+                    ok = false;
+                    break;
+                }
+            };
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let Some(entry) = parse_line(&line, default, args.length.is_some()) else {
+                improper += 1;
+                if args.warn {
+                    eprintln!(
+                        "areionsum: {}: {}: improperly formatted checksum line",
+                        manifest.display(),
+                        i + 1
+                    );
+                }
+                continue;
+            };
+            formatted += 1;
+
+            let (escaped, name) = escape(&entry.name);
+            let prefix = if escaped { "\\" } else { "" };
+            match entry.spec.hash_path(Path::new(&entry.name)) {
+                Ok(digest) if digest == entry.digest => {
+                    verified += 1;
+                    if !args.quiet && !args.status {
+                        println!("{prefix}{name}: OK");
+                    }
+                }
+                Ok(_) => {
+                    verified += 1;
+                    mismatched += 1;
+                    if !args.status {
+                        println!("{prefix}{name}: FAILED");
+                    }
+                }
+                Err(e) if args.ignore_missing && e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    unreadable += 1;
+                    if !args.status {
+                        error(Path::new(&entry.name), &e);
+                        println!("{prefix}{name}: FAILED open or read");
+                    }
+                }
+            }
+        }
+
+        if formatted == 0 {
+            eprintln!(
+                "areionsum: {}: no properly formatted checksum lines found",
+                manifest.display()
+            );
+            ok = false;
+            continue;
+        }
+
+        if !args.status {
+            warn(improper, "line is", "lines are", "improperly formatted");
+            warn(unreadable, "listed file", "listed files", "could not be read");
+            warn(mismatched, "computed checksum", "computed checksums", "did NOT match");
+        }
+
+        if args.ignore_missing && verified == 0 && unreadable == 0 {
+            if !args.status {
+                eprintln!("areionsum: {}: no file was verified", manifest.display());
+            }
+            ok = false;
+        }
+
+        if unreadable > 0 || mismatched > 0 || (args.strict && improper > 0) {
+            ok = false;
+        }
+    }
+    ok
+}
+
+/// A parsed checksum line.
+#[derive(Debug, PartialEq, Eq)]
+struct Entry {
+    spec: Spec,
+    digest: Vec<u8>,
+    name: String,
+}
+
+/// Parses either a GNU-style (`HEX  NAME`) or BSD-style (`TAG (NAME) = HEX`) checksum line.
+/// Untagged lines use the `default` algorithm; if that's Areion512-HAIFA and no length was given
+/// explicitly, the length is inferred from the digest.
+fn parse_line(line: &str, default: Spec, explicit_len: bool) -> Option<Entry> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+
+    let tagged = line.split_once(" (").and_then(|(tag, rest)| {
+        let (name, hex) = rest.rsplit_once(") = ")?;
+        Some((Spec::from_tag(tag)?, name, hex))
+    });
+
+    let (spec, name, hex) = match tagged {
+        Some(tagged) => tagged,
+        None => {
+            let (hex, rest) = line.split_once(' ')?;
+            let name = rest.strip_prefix([' ', '*'])?;
+            let mut spec = default;
+            if spec.algorithm == Algorithm::Haifa && !explicit_len {
+                spec.len = hex.len() / 2;
+            }
+            (spec, name, hex)
+        }
+    };
+
+    let digest = from_hex(hex)?;
+    if name.is_empty() || digest.len() != spec.len || !(1..=64).contains(&spec.len) {
+        return None;
+    }
+    let name = if escaped { unescape(name)? } else { name.to_string() };
+    Some(Entry { spec, digest, name })
+}
+
+/// Escapes backslashes, newlines, and carriage returns in file names, GNU-style. Returns whether
+/// any escaping was needed, in which case the line must be prefixed with a backslash.
+fn escape(name: &str) -> (bool, String) {
+    if !name.contains(['\\', '\n', '\r']) {
+        return (false, name.to_string());
+    }
+    let escaped = name.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+    (true, escaped)
+}
+
+fn unescape(name: &str) -> Option<String> {
+    let mut out = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => out.push('\\'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+fn parse_length(s: &str) -> Result<usize, String> {
+    let bits: usize = s.parse().map_err(|e| format!("invalid length: {e}"))?;
+    if bits == 0 || bits > 512 || !bits.is_multiple_of(8) {
+        return Err(format!("invalid length: {bits} (must be a multiple of 8 from 8 to 512)"));
+    }
+    Ok(bits / 8)
+}
+
+fn to_hex(b: &[u8]) -> String {
+    b.iter().map(|v| format!("{:02x}", v)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect()
+}
+
+fn error(path: &Path, e: &io::Error) {
+    // Match coreutils by omitting the " (os error N)" suffix.
+    let msg = e.to_string();
+    let msg = msg.split(" (os error").next().unwrap_or(&msg);
+    eprintln!("areionsum: {}: {}", path.display(), msg);
+}
+
+fn warn(n: usize, one: &str, many: &str, what: &str) {
+    match n {
+        0 => {}
+        1 => eprintln!("areionsum: WARNING: 1 {one} {what}"),
+        n => eprintln!("areionsum: WARNING: {n} {many} {what}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gnu_line() {
+        let default = Spec::new(Algorithm::Md, None);
+        let line = format!("{}  file.txt", "ab".repeat(32));
+        assert_eq!(
+            parse_line(&line, default, false),
+            Some(Entry { spec: default, digest: vec![0xab; 32], name: "file.txt".into() })
+        );
+
+        let line = format!("{} *file.txt", "ab".repeat(32));
+        assert_eq!(parse_line(&line, default, false).map(|e| e.name), Some("file.txt".into()));
+
+        let line = format!("{}  file.txt", "ab".repeat(31));
+        assert_eq!(parse_line(&line, default, false), None, "wrong digest length");
+
+        assert_eq!(parse_line("zz  file.txt", default, false), None, "invalid hex");
+    }
+
+    #[test]
+    fn parse_bsd_line() {
+        let default = Spec::new(Algorithm::Md, None);
+        let line = format!("Areion512-HAIFA-128 (a (b) = c) = {}", "01".repeat(16));
+        assert_eq!(
+            parse_line(&line, default, false),
+            Some(Entry {
+                spec: Spec::new(Algorithm::Haifa, Some(16)),
+                digest: vec![1; 16],
+                name: "a (b) = c".into(),
+            })
+        );
+
+        let line = format!("Areion512-MMO (file) = {}", "01".repeat(32));
+        assert_eq!(parse_line(&line, default, false), None, "wrong digest length");
+
+        let line = format!("SHA256 (file) = {}", "01".repeat(32));
+        assert_eq!(parse_line(&line, default, false), None, "unknown tag");
+    }
+
+    #[test]
+    fn infer_haifa_length() {
+        let default = Spec::new(Algorithm::Haifa, None);
+        let line = format!("{}  file", "01".repeat(20));
+        assert_eq!(parse_line(&line, default, false).map(|e| e.spec.len), Some(20));
+
+        let default = Spec::new(Algorithm::Haifa, Some(32));
+        assert_eq!(parse_line(&line, default, true), None);
+    }
+
+    #[test]
+    fn escaping() {
+        let (escaped, name) = escape("a\\b\nc");
+        assert!(escaped);
+        assert_eq!(name, "a\\\\b\\nc");
+        assert_eq!(unescape(&name), Some("a\\b\nc".into()));
+
+        let default = Spec::new(Algorithm::Md, None);
+        let line = format!("\\{}  {}", "ab".repeat(32), name);
+        assert_eq!(parse_line(&line, default, false).map(|e| e.name), Some("a\\b\nc".into()));
+
+        assert_eq!(escape("plain"), (false, "plain".into()));
+        assert_eq!(unescape("bad\\x"), None);
+    }
+
+    #[test]
+    fn tags_round_trip() {
+        for algorithm in Algorithm::value_variants() {
+            let spec = Spec::new(*algorithm, Some(24));
+            assert_eq!(Spec::from_tag(&spec.tag()), Some(spec));
+        }
+    }
+
+    #[test]
+    fn lengths() {
+        assert_eq!(parse_length("256"), Ok(32));
+        assert!(parse_length("0").is_err());
+        assert!(parse_length("12").is_err());
+        assert!(parse_length("520").is_err());
+    }
+}
@@ -1,7 +1,22 @@
+use areion::aead::{AeadInPlace, KeyInit};
+use areion::cipher::{KeyIvInit, StreamCipher};
+use areion::digest::Mac;
+use areion::lms::{LmotsType, LmsType};
+use areion::merkle::MerkleLog;
+use areion::pow::Challenge;
+use areion::rand_core::{RngCore, SeedableRng};
+use areion::signature::{Keypair, Signer, Verifier};
+use areion::smt::SparseMerkleTree;
+use areion::sphincs::{ParameterSet, SigningKey, Sphincs128f, Sphincs128s};
 use areion::{digest::Digest, Areion512Md, Areion512Mmo};
-use areion::{Areion256Sponge, AreionHaifa512};
+use areion::{
+    Areion256Sponge, Areion512Ctr, Areion512Opp, Areion512Pmac, AreionBuildHasher, AreionHaifa512,
+    AreionRng,
+};
 use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
 use sha2::{Sha256, Sha512};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
 
 fn areion256(c: &mut Criterion) {
     c.bench_function("areion256", |b| {
@@ -108,6 +123,162 @@ fn areion512_haifa(c: &mut Criterion) {
     g.finish();
 }
 
+fn areion512_pmac(c: &mut Criterion) {
+    let mac = <Areion512Pmac as Mac>::new(&[0u8; 64].into());
+    let mut g = c.benchmark_group("areion512_pmac");
+    for &(len, id) in LENS {
+        let input = vec![0u8; len];
+        g.throughput(Throughput::Bytes(len as u64));
+        g.bench_function(id, |b| {
+            b.iter(|| mac.clone().chain_update(&input).finalize());
+        });
+    }
+    g.finish();
+}
+
+fn areion512_opp(c: &mut Criterion) {
+    let cipher = Areion512Opp::<_, _>::new(&[0u8; 32].into());
+    let nonce = [0u8; 16].into();
+    let mut g = c.benchmark_group("areion512_opp");
+    for &(len, id) in LENS {
+        let mut input = vec![0u8; len];
+        g.throughput(Throughput::Bytes(len as u64));
+        g.bench_function(id, |b| {
+            b.iter(|| cipher.encrypt_in_place_detached(&nonce, b"", &mut input));
+        });
+    }
+    g.finish();
+}
+
+fn areion512_ctr(c: &mut Criterion) {
+    let mut cipher = Areion512Ctr::new(&[0u8; 32].into(), &[0u8; 16].into());
+    let mut g = c.benchmark_group("areion512_ctr");
+    for &(len, id) in LENS {
+        let mut input = vec![0u8; len];
+        g.throughput(Throughput::Bytes(len as u64));
+        g.bench_function(id, |b| {
+            b.iter(|| cipher.apply_keystream(&mut input));
+        });
+    }
+    g.finish();
+}
+
+fn aes256_ctr(c: &mut Criterion) {
+    let mut cipher = ctr::Ctr64BE::<aes::Aes256>::new(&[0u8; 32].into(), &[0u8; 16].into());
+    let mut g = c.benchmark_group("aes256_ctr");
+    for &(len, id) in LENS {
+        let mut input = vec![0u8; len];
+        g.throughput(Throughput::Bytes(len as u64));
+        g.bench_function(id, |b| {
+            b.iter(|| cipher.apply_keystream(&mut input));
+        });
+    }
+    g.finish();
+}
+
+fn areion_rng(c: &mut Criterion) {
+    let mut rng = AreionRng::from_seed([0u8; 32]);
+    let mut g = c.benchmark_group("areion_rng");
+    for &(len, id) in LENS {
+        let mut output = vec![0u8; len];
+        g.throughput(Throughput::Bytes(len as u64));
+        g.bench_function(id, |b| {
+            b.iter(|| rng.fill_bytes(&mut output));
+        });
+    }
+    g.finish();
+}
+
+fn areion_hasher(c: &mut Criterion) {
+    let mut g = c.benchmark_group("areion_hasher");
+    let areion = AreionBuildHasher::new_with_key(&[7; 32]);
+    let siphash = RandomState::new();
+    g.bench_function("areion/u64", |b| b.iter(|| areion.hash_one(0xdeadbeefu64)));
+    g.bench_function("siphash/u64", |b| b.iter(|| siphash.hash_one(0xdeadbeefu64)));
+    g.bench_function("areion/str", |b| b.iter(|| areion.hash_one("a short string key")));
+    g.bench_function("siphash/str", |b| b.iter(|| siphash.hash_one("a short string key")));
+    g.finish();
+}
+
+fn sphincs<P: ParameterSet>(c: &mut Criterion) {
+    let sk = SigningKey::<P>::generate(AreionRng::from_seed([0u8; 32]));
+    let vk = sk.verifying_key();
+    let sig = sk.sign(b"message");
+    let mut g = c.benchmark_group(P::NAME);
+    g.sample_size(10);
+    g.bench_function("sign", |b| b.iter(|| sk.sign(b"message")));
+    g.bench_function("verify", |b| b.iter(|| vk.verify(b"message", &sig)));
+    g.finish();
+}
+
+fn lms(c: &mut Criterion) {
+    let params = [(LmsType::AreionM32H10, LmotsType::AreionN32W4)];
+    let mut sk = areion::lms::SigningKey::generate(&params, AreionRng::from_seed([0u8; 32]))
+        .expect("should be valid parameters");
+    let vk = sk.verifying_key();
+    let mut store = |_: &[u8]| Ok(());
+    let sig = sk.sign(b"message", &mut store).expect("should sign");
+    let state = sk.to_bytes();
+    let mut g = c.benchmark_group("lms_h10_w4");
+    g.bench_function("sign", |b| {
+        b.iter_batched(
+            || areion::lms::SigningKey::from_bytes(&state).expect("should be a valid state"),
+            |mut sk| sk.sign(b"message", &mut store),
+            BatchSize::LargeInput,
+        )
+    });
+    g.bench_function("verify", |b| b.iter(|| vk.verify(b"message", &sig)));
+    g.finish();
+}
+
+fn merkle_log(c: &mut Criterion) {
+    let mut log = MerkleLog::new();
+    for i in 0..(1u64 << 16) {
+        log.append(&i.to_be_bytes());
+    }
+    let mut g = c.benchmark_group("merkle_log");
+    let mut growing = MerkleLog::new();
+    g.bench_function("append", |b| b.iter(|| growing.append(b"entry")));
+    g.bench_function("inclusion_proof", |b| b.iter(|| log.inclusion_proof(12345, 50000)));
+    g.bench_function("consistency_proof", |b| b.iter(|| log.consistency_proof(12345, 50000)));
+    g.finish();
+}
+
+fn sparse_merkle_tree(c: &mut Criterion) {
+    let mut rng = AreionRng::from_seed([0u8; 32]);
+    let mut key = || {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        key
+    };
+    let mut tree = SparseMerkleTree::new();
+    tree.update((0..10_000).map(|_| (key(), Some(b"value".to_vec()))));
+    let batch = (0..100).map(|_| (key(), Some(b"value".to_vec()))).collect::<Vec<_>>();
+    let present = batch[0].0;
+    let mut g = c.benchmark_group("sparse_merkle_tree");
+    g.bench_function("insert", |b| b.iter(|| tree.insert(present, *b"other")));
+    g.bench_function("update/100", |b| b.iter(|| tree.update(batch.iter().cloned())));
+    g.bench_function("prove", |b| b.iter(|| tree.prove(&present)));
+    let proof = tree.prove(&present);
+    let root = tree.root();
+    g.bench_function("verify", |b| b.iter(|| proof.verify(&root, &present, Some(b"value"))));
+    g.finish();
+}
+
+fn pow(c: &mut Criterion) {
+    let challenge = Challenge::new(0, [7; 32]);
+    let nonces = 1 << 16;
+    let stamp = challenge.solve();
+    let mut g = c.benchmark_group("pow");
+    g.throughput(Throughput::Elements(nonces as u64));
+    g.bench_function("search/64Ki", |b| {
+        b.iter(|| Challenge::new(255, [7; 32]).solve_range(0..nonces))
+    });
+    g.throughput(Throughput::Elements(1));
+    g.bench_function("verify", |b| b.iter(|| challenge.verify(&stamp)));
+    g.finish();
+}
+
 fn sha256(c: &mut Criterion) {
     let mut g = c.benchmark_group("sha256");
     for &(len, id) in LENS {
@@ -154,6 +325,18 @@ criterion_group!(
     areion512_mmo,
     areion256_sponge,
     areion512_haifa,
+    areion512_pmac,
+    areion512_opp,
+    areion512_ctr,
+    aes256_ctr,
+    areion_rng,
+    areion_hasher,
+    sphincs::<Sphincs128s>,
+    sphincs::<Sphincs128f>,
+    lms,
+    merkle_log,
+    sparse_merkle_tree,
+    pow,
     sha256,
     sha512,
     blake3,
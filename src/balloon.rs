@@ -0,0 +1,436 @@
+//! A memory-hard password hashing function in the style of
+//! [Balloon hashing](https://eprint.iacr.org/2016/027.pdf) built on Areion512.
+//!
+//! Each lane seeds a buffer of 32-byte blocks from the password and salt with Areion512-HAIFA,
+//! fills the rest of it sequentially, then mixes it for a number of passes. Every block is mixed
+//! with its predecessor and with three other blocks whose indexes are derived from the salt, making
+//! the access pattern independent of the password. Blocks are hashed with a single Areion512-DM
+//! compression `H(cnt, a, b) = DM(a || b ^ LE64(cnt))` with a strictly increasing counter per lane,
+//! so each block costs five permutation calls per pass: one for its predecessor, one for its
+//! indexes, and three for the other blocks.
+//!
+//! The memory cost `m` is the total number of kibibytes used, split evenly among the `p` lanes,
+//! which are computed in parallel. The time cost `t` is the number of mixing passes. The final
+//! blocks of the lanes are XORed together and hashed with Areion512-HAIFA and the output length to
+//! produce the password hash.
+//!
+//! [`AreionBalloon`] implements [`PasswordHasher`], producing hashes in the
+//! [PHC string format](https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md) with
+//! the `areion-balloon` algorithm identifier, and
+//! [`PasswordVerifier`](password_hash::PasswordVerifier) for verifying them:
+//!
+//! ```text
+//! $areion-balloon$m=19456,t=2,p=1$<salt>$<hash>
+//! ```
+
+use std::thread;
+
+use crate::intrinsics::*;
+use crate::{AreionHaifa512, AreionHaifaVar};
+
+use digest::{Digest, Update, VariableOutput};
+use password_hash::errors::InvalidValue;
+use password_hash::{
+    Decimal, Error, Ident, Output, ParamsString, PasswordHash, PasswordHasher, Result, Salt,
+};
+
+/// The algorithm identifier used in PHC strings.
+pub const ALG_ID: Ident<'static> = Ident::new_unwrap("areion-balloon");
+
+/// The number of bytes in a block.
+const BLOCK_LEN: usize = 32;
+
+/// The number of pseudorandom blocks mixed into each block per pass.
+const DELTA: u64 = 3;
+
+type Block = [u8; BLOCK_LEN];
+
+/// The cost parameters for [`AreionBalloon`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Params {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    output_len: usize,
+}
+
+impl Params {
+    /// The default memory cost, in kibibytes.
+    pub const DEFAULT_M_COST: u32 = 19 * 1024;
+
+    /// The default time cost.
+    pub const DEFAULT_T_COST: u32 = 2;
+
+    /// The default parallelism cost.
+    pub const DEFAULT_P_COST: u32 = 1;
+
+    /// The maximum memory cost, in kibibytes.
+    pub const MAX_M_COST: u32 = 4 * 1024 * 1024;
+
+    /// The maximum parallelism cost.
+    pub const MAX_P_COST: u32 = 256;
+
+    /// The default output length, in bytes.
+    pub const DEFAULT_OUTPUT_LEN: usize = 32;
+
+    /// The minimum output length, in bytes.
+    pub const MIN_OUTPUT_LEN: usize = 16;
+
+    /// The maximum output length, in bytes.
+    pub const MAX_OUTPUT_LEN: usize = 64;
+
+    /// Creates a set of parameters with the given memory cost in kibibytes, time cost, parallelism
+    /// cost, and output length in bytes.
+    ///
+    /// Each lane must have at least one kibibyte of memory, and the time and parallelism costs must
+    /// be at least one. The memory and parallelism costs are limited to [`Params::MAX_M_COST`] and
+    /// [`Params::MAX_P_COST`], so that untrusted PHC strings can't exhaust memory or threads.
+    pub fn new(m_cost: u32, t_cost: u32, p_cost: u32, output_len: usize) -> Result<Params> {
+        if t_cost < 1 || p_cost < 1 || m_cost < p_cost {
+            return Err(InvalidValue::TooShort.param_error());
+        }
+        if m_cost > Self::MAX_M_COST || p_cost > Self::MAX_P_COST {
+            return Err(InvalidValue::TooLong.param_error());
+        }
+        if output_len < Self::MIN_OUTPUT_LEN {
+            return Err(Error::OutputSize {
+                provided: core::cmp::Ordering::Less,
+                expected: Self::MIN_OUTPUT_LEN,
+            });
+        }
+        if output_len > Self::MAX_OUTPUT_LEN {
+            return Err(Error::OutputSize {
+                provided: core::cmp::Ordering::Greater,
+                expected: Self::MAX_OUTPUT_LEN,
+            });
+        }
+        Ok(Params { m_cost, t_cost, p_cost, output_len })
+    }
+
+    /// The memory cost, in kibibytes.
+    pub fn m_cost(&self) -> u32 {
+        self.m_cost
+    }
+
+    /// The time cost.
+    pub fn t_cost(&self) -> u32 {
+        self.t_cost
+    }
+
+    /// The parallelism cost.
+    pub fn p_cost(&self) -> u32 {
+        self.p_cost
+    }
+
+    /// The output length, in bytes.
+    pub fn output_len(&self) -> usize {
+        self.output_len
+    }
+
+    /// The number of blocks in each lane.
+    fn lane_len(&self) -> u64 {
+        self.m_cost as u64 / self.p_cost as u64 * (1024 / BLOCK_LEN as u64)
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            m_cost: Self::DEFAULT_M_COST,
+            t_cost: Self::DEFAULT_T_COST,
+            p_cost: Self::DEFAULT_P_COST,
+            output_len: Self::DEFAULT_OUTPUT_LEN,
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a PasswordHash<'a>> for Params {
+    type Error = Error;
+
+    fn try_from(hash: &'a PasswordHash<'a>) -> Result<Self> {
+        let (mut m_cost, mut t_cost, mut p_cost) =
+            (Self::DEFAULT_M_COST, Self::DEFAULT_T_COST, Self::DEFAULT_P_COST);
+        for (ident, value) in hash.params.iter() {
+            match ident.as_str() {
+                "m" => m_cost = value.decimal()?,
+                "t" => t_cost = value.decimal()?,
+                "p" => p_cost = value.decimal()?,
+                _ => return Err(Error::ParamNameInvalid),
+            }
+        }
+        let output_len = hash.hash.map_or(Self::DEFAULT_OUTPUT_LEN, |h| h.len());
+        Params::new(m_cost, t_cost, p_cost, output_len)
+    }
+}
+
+impl TryFrom<Params> for ParamsString {
+    type Error = Error;
+
+    fn try_from(params: Params) -> Result<Self> {
+        let mut s = ParamsString::new();
+        s.add_decimal("m", params.m_cost)?;
+        s.add_decimal("t", params.t_cost)?;
+        s.add_decimal("p", params.p_cost)?;
+        Ok(s)
+    }
+}
+
+/// AreionBalloon, a memory-hard password hashing function. See the [module](self) documentation for
+/// details.
+#[derive(Clone, Debug, Default)]
+pub struct AreionBalloon {
+    params: Params,
+}
+
+impl AreionBalloon {
+    /// Creates a password hasher with the given parameters.
+    pub fn new(params: Params) -> AreionBalloon {
+        AreionBalloon { params }
+    }
+
+    /// Returns the hasher's parameters.
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Hashes the given password and salt, filling `out` with the raw output. The length of `out`
+    /// overrides the output length of the hasher's parameters.
+    pub fn hash_password_into(&self, password: &[u8], salt: &[u8], out: &mut [u8]) -> Result<()> {
+        let params =
+            Params::new(self.params.m_cost, self.params.t_cost, self.params.p_cost, out.len())?;
+
+        let lanes = if params.p_cost == 1 {
+            vec![lane(&params, password, salt, 0)]
+        } else {
+            thread::scope(|s| {
+                let lanes = (0..params.p_cost)
+                    .map(|l| s.spawn(move || lane(&params, password, salt, l)))
+                    .collect::<Vec<_>>();
+                lanes.into_iter().map(|l| l.join().expect("lane should not panic")).collect()
+            })
+        };
+
+        let mut acc = [0u8; BLOCK_LEN];
+        for block in lanes {
+            acc.iter_mut().zip(block).for_each(|(a, b)| *a ^= b);
+        }
+
+        let mut h = AreionHaifaVar::new(out.len()).expect("should be a valid output length");
+        h.update(&acc);
+        h.finalize_variable(out).expect("should be a valid output length");
+        Ok(())
+    }
+}
+
+impl PasswordHasher for AreionBalloon {
+    type Params = Params;
+
+    /// Hashes the password with the hasher's parameters, rather than the defaults.
+    fn hash_password<'a>(
+        &self,
+        password: &[u8],
+        salt: impl Into<Salt<'a>>,
+    ) -> Result<PasswordHash<'a>> {
+        self.hash_password_customized(password, None, None, self.params, salt)
+    }
+
+    fn hash_password_customized<'a>(
+        &self,
+        password: &[u8],
+        algorithm: Option<Ident<'a>>,
+        version: Option<Decimal>,
+        params: Params,
+        salt: impl Into<Salt<'a>>,
+    ) -> Result<PasswordHash<'a>> {
+        if algorithm.is_some_and(|a| a != ALG_ID) {
+            return Err(Error::Algorithm);
+        }
+        if version.is_some() {
+            return Err(Error::Version);
+        }
+
+        let salt = salt.into();
+        let mut salt_buf = [0u8; Salt::MAX_LENGTH];
+        let salt_bytes = salt.decode_b64(&mut salt_buf)?;
+
+        let mut out = [0u8; Params::MAX_OUTPUT_LEN];
+        let out = &mut out[..params.output_len];
+        AreionBalloon::new(params).hash_password_into(password, salt_bytes, out)?;
+
+        Ok(PasswordHash {
+            algorithm: ALG_ID,
+            version: None,
+            params: params.try_into()?,
+            salt: Some(salt),
+            hash: Some(Output::new(out)?),
+        })
+    }
+}
+
+/// Fills and mixes a single lane, returning its last block.
+fn lane(params: &Params, password: &[u8], salt: &[u8], lane: u32) -> Block {
+    let s = params.lane_len();
+    let mut cnt = 0u64;
+    let mut h = |a: &Block, b: &Block| {
+        let mut b = *b;
+        b[..8].iter_mut().zip(cnt.to_le_bytes()).for_each(|(b, c)| *b ^= c);
+        cnt += 1;
+        dm(a, &b)
+    };
+
+    // Expand the password and salt into the first two blocks of the buffer.
+    let seed = AreionHaifa512::new()
+        .chain_update(params.m_cost.to_le_bytes())
+        .chain_update(params.t_cost.to_le_bytes())
+        .chain_update(params.p_cost.to_le_bytes())
+        .chain_update((params.output_len as u32).to_le_bytes())
+        .chain_update(lane.to_le_bytes())
+        .chain_update((password.len() as u64).to_le_bytes())
+        .chain_update(password)
+        .chain_update(salt)
+        .finalize();
+    let mut buf = vec![[0u8; BLOCK_LEN]; s as usize];
+    buf[0].copy_from_slice(&seed[..BLOCK_LEN]);
+    buf[1].copy_from_slice(&seed[BLOCK_LEN..]);
+
+    // Fill the rest of the buffer.
+    for m in 2..buf.len() {
+        buf[m] = h(&buf[m - 2], &buf[m - 1]);
+    }
+
+    // Derive a key for the block indexes from the salt alone, so the access pattern doesn't depend
+    // on the password.
+    let mut idx_key = [0u8; BLOCK_LEN];
+    let mut idx_h = AreionHaifaVar::new(BLOCK_LEN).expect("should be a valid output length");
+    idx_h.update(&lane.to_le_bytes());
+    idx_h.update(salt);
+    idx_h.finalize_variable(&mut idx_key).expect("should be a valid output length");
+
+    // Mix the buffer.
+    for t in 0..params.t_cost as u64 {
+        for m in 0..s {
+            let prev = buf[((m + s - 1) % s) as usize];
+            buf[m as usize] = h(&prev, &buf[m as usize]);
+
+            let mut ctr = [0u8; BLOCK_LEN];
+            ctr[..8].copy_from_slice(&t.to_le_bytes());
+            ctr[8..16].copy_from_slice(&m.to_le_bytes());
+            let idx = dm(&idx_key, &ctr);
+            for other in idx.chunks_exact(8).take(DELTA as usize) {
+                let other = u64::from_le_bytes(other.try_into().expect("should be 8 bytes")) % s;
+                buf[m as usize] = h(&buf[m as usize], &buf[other as usize]);
+            }
+        }
+    }
+
+    buf[s as usize - 1]
+}
+
+/// Compresses two blocks into one with Areion512-DM.
+#[inline]
+fn dm(a: &Block, b: &Block) -> Block {
+    let (h0, h1) =
+        crate::areion512_dm(load(&a[..16]), load(&a[16..]), load(&b[..16]), load(&b[16..]));
+    let mut out = [0u8; BLOCK_LEN];
+    store(&mut out[..16], h0);
+    store(&mut out[16..], h1);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+    use password_hash::{PasswordVerifier, SaltString};
+
+    fn hash(m_cost: u32, t_cost: u32, p_cost: u32, password: &[u8], salt: &[u8]) -> Vec<u8> {
+        let params = Params::new(m_cost, t_cost, p_cost, 32).expect("should be valid params");
+        let mut out = vec![0u8; 32];
+        AreionBalloon::new(params)
+            .hash_password_into(password, salt, &mut out)
+            .expect("should hash password");
+        out
+    }
+
+    #[test]
+    fn test_vector() {
+        expect![[r#"
+            64 91 8d be 40 bb fb b7 fe 47 a6 71 8d 5c db a8
+            a3 f2 83 18 99 12 32 6b 0b ad 1a 5e 10 c4 59 34"#]]
+        .assert_eq(&hex_fmt(&hash(16, 2, 1, b"password", b"somesalt")));
+        expect![[r#"
+            4c 5f b5 58 f8 75 ed c3 06 7b e1 0f 2d 5d 37 7b
+            42 3b cc 82 8d 8d ec 72 63 04 b1 70 37 81 62 47"#]]
+        .assert_eq(&hex_fmt(&hash(16, 1, 4, b"password", b"somesalt")));
+    }
+
+    #[test]
+    fn parameters_change_output() {
+        let base = hash(16, 1, 1, b"password", b"somesalt");
+        assert_ne!(base, hash(32, 1, 1, b"password", b"somesalt"), "m");
+        assert_ne!(base, hash(16, 2, 1, b"password", b"somesalt"), "t");
+        assert_ne!(base, hash(16, 1, 2, b"password", b"somesalt"), "p");
+        assert_ne!(base, hash(16, 1, 1, b"passwore", b"somesalt"), "password");
+        assert_ne!(base, hash(16, 1, 1, b"password", b"somesalu"), "salt");
+        assert_ne!(base, hash(16, 1, 1, b"passwordsomesalt", b""), "boundary");
+
+        // Truncating the output doesn't produce a prefix of a longer output.
+        let mut short = [0u8; 16];
+        AreionBalloon::new(Params::new(16, 1, 1, 16).expect("should be valid params"))
+            .hash_password_into(b"password", b"somesalt", &mut short)
+            .expect("should hash password");
+        assert_ne!(base[..16], short);
+    }
+
+    #[test]
+    fn invalid_params() {
+        assert!(Params::new(16, 0, 1, 32).is_err(), "t=0");
+        assert!(Params::new(16, 1, 0, 32).is_err(), "p=0");
+        assert!(Params::new(3, 1, 4, 32).is_err(), "m<p");
+        assert!(Params::new(Params::MAX_M_COST + 1, 1, 1, 32).is_err(), "large m");
+        assert!(Params::new(1024, 1, Params::MAX_P_COST + 1, 32).is_err(), "large p");
+        assert!(Params::new(Params::MAX_M_COST, 1, Params::MAX_P_COST, 32).is_ok());
+        assert!(Params::new(16, 1, 1, 15).is_err(), "short output");
+        assert!(Params::new(16, 1, 1, 65).is_err(), "long output");
+        assert!(Params::new(16, 1, 1, 16).is_ok());
+    }
+
+    #[test]
+    fn phc_strings() {
+        let hasher = AreionBalloon::new(Params::new(64, 1, 2, 32).expect("should be valid params"));
+        let salt = SaltString::encode_b64(b"somesalt").expect("should be a valid salt");
+        let hash = hasher.hash_password(b"password", &salt).expect("should hash password");
+        let hash = hash.to_string();
+        expect![
+            "$areion-balloon$m=64,t=1,p=2$c29tZXNhbHQ$xWYFXES5WXY+GcIji2I4g/vRjNIY5quGtfmK3Rl5yyk"
+        ]
+        .assert_eq(&hash);
+
+        // The encoded parameters are used for verification, regardless of the verifier's.
+        let hash = PasswordHash::new(&hash).expect("should parse");
+        assert!(AreionBalloon::default().verify_password(b"password", &hash).is_ok());
+        assert_eq!(
+            AreionBalloon::default().verify_password(b"passwore", &hash),
+            Err(Error::Password)
+        );
+    }
+
+    #[test]
+    fn foreign_phc_strings() {
+        let hasher = AreionBalloon::default();
+        for s in [
+            "$argon2id$v=19$m=64,t=1,p=2$c29tZXNhbHQ$Jx/9KUn1ZMCtrspPdlgVjA",
+            "$areion-balloon$v=1$m=64,t=1,p=2$c29tZXNhbHQ$Jx/9KUn1ZMCtrspPdlgVjA",
+            "$areion-balloon$m=64,t=1,p=2,x=1$c29tZXNhbHQ$Jx/9KUn1ZMCtrspPdlgVjA",
+            "$areion-balloon$m=64,t=0,p=2$c29tZXNhbHQ$Jx/9KUn1ZMCtrspPdlgVjA",
+            "$areion-balloon$m=4294967295,t=1,p=2$c29tZXNhbHQ$Jx/9KUn1ZMCtrspPdlgVjA",
+            "$areion-balloon$m=65536,t=1,p=65536$c29tZXNhbHQ$Jx/9KUn1ZMCtrspPdlgVjA",
+        ] {
+            let hash = PasswordHash::new(s).expect("should parse");
+            assert!(hasher.verify_password(b"password", &hash).is_err(), "{s}");
+        }
+    }
+}
@@ -0,0 +1,550 @@
+//! A Bao-style verified streaming encoding built on the Areion512-DM compression function.
+//!
+//! Content is split into 1 KiB chunks, which are hashed into a binary Merkle tree. The tree is
+//! stored separately from the content as an *outboard* encoding: an 8-byte little-endian content
+//! length followed by the tree's parent nodes in pre-order. Given the root hash, a [`Decoder`]
+//! verifies each chunk as it's read, and returns an error as soon as a chunk or parent node fails
+//! to verify. [`extract_slice`] and [`decode_slice`] allow a byte range to be sent and verified
+//! independently of the rest of the content.
+//!
+//! All nodes are compressed with `C(H, M, T) = DM(M, H ^ T)`, where the 128-bit tweak `T` contains
+//! the chunk counter, the number of bytes in the block, and domain separation flags.
+//!
+//! * Chunks are hashed in 32-byte blocks (the final block is zero-padded), starting with a fixed
+//!   IV. The first and last blocks of each chunk are flagged, as is the last block of a chunk
+//!   which is the root of the tree.
+//! * Parent nodes compress the left child's hash as the message block and the right child's hash
+//!   as the chaining value, flagged as parents and, if applicable, as the root.
+
+use std::io::{self, Read};
+
+use crate::intrinsics::*;
+
+use hex_literal::hex;
+
+/// The number of bytes in a chunk.
+pub const CHUNK_LEN: usize = 1024;
+
+/// The number of bytes in a hash.
+pub const HASH_LEN: usize = 32;
+
+/// The number of bytes in an encoded parent node.
+pub const PARENT_LEN: usize = 2 * HASH_LEN;
+
+/// The number of bytes in the encoded content length header.
+pub const HEADER_LEN: usize = 8;
+
+/// A root or subtree hash.
+pub type Hash = [u8; HASH_LEN];
+
+const BLOCK_LEN: usize = 32;
+
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+
+/// SHA-256 IV constants
+const IV: [u8; 32] = hex!("6a09e667bb67ae853c6ef372a54ff53a510e527f9b05688c1f83d9ab5be0cd19");
+
+#[inline]
+fn compress(
+    h: (AesBlock, AesBlock),
+    block: &[u8; BLOCK_LEN],
+    counter: u64,
+    block_len: usize,
+    flags: u32,
+) -> (AesBlock, AesBlock) {
+    let mut t = [0u8; 16];
+    t[..8].copy_from_slice(&counter.to_be_bytes());
+    t[8..12].copy_from_slice(&(block_len as u32).to_be_bytes());
+    t[12..].copy_from_slice(&flags.to_be_bytes());
+    crate::areion512_dm(load(&block[..16]), load(&block[16..]), h.0, xor(h.1, load(&t)))
+}
+
+fn to_hash((h0, h1): (AesBlock, AesBlock)) -> Hash {
+    let mut out = [0u8; HASH_LEN];
+    store(&mut out[..16], h0);
+    store(&mut out[16..], h1);
+    out
+}
+
+fn chunk_hash(chunk: &[u8], counter: u64, root: bool) -> Hash {
+    debug_assert!(chunk.len() <= CHUNK_LEN);
+    let mut h = (load(&IV[..16]), load(&IV[16..]));
+    let n = chunk.len().div_ceil(BLOCK_LEN).max(1);
+    let blocks = chunk.chunks(BLOCK_LEN).chain(chunk.is_empty().then_some(&[][..]));
+    for (i, block) in blocks.enumerate() {
+        let mut buf = [0u8; BLOCK_LEN];
+        buf[..block.len()].copy_from_slice(block);
+
+        let mut flags = 0;
+        if i == 0 {
+            flags |= CHUNK_START;
+        }
+        if i == n - 1 {
+            flags |= CHUNK_END;
+            if root {
+                flags |= ROOT;
+            }
+        }
+        h = compress(h, &buf, counter, block.len(), flags);
+    }
+    to_hash(h)
+}
+
+fn parent_hash(left: &Hash, right: &Hash, root: bool) -> Hash {
+    let h = (load(&right[..16]), load(&right[16..]));
+    to_hash(compress(h, left, 0, BLOCK_LEN, if root { PARENT | ROOT } else { PARENT }))
+}
+
+/// Returns the number of bytes in the left subtree of a tree with `len` bytes of content: the
+/// largest power-of-two number of chunks which leaves at least one byte for the right subtree.
+fn left_len(len: u64) -> u64 {
+    debug_assert!(len > CHUNK_LEN as u64);
+    let full_chunks = (len - 1) / CHUNK_LEN as u64;
+    (1 << full_chunks.ilog2()) * CHUNK_LEN as u64
+}
+
+/// Returns the number of bytes in the outboard encoding of `len` bytes of content.
+pub fn outboard_len(len: u64) -> u64 {
+    let chunks = len.div_ceil(CHUNK_LEN as u64).max(1);
+    HEADER_LEN as u64 + (chunks - 1) * PARENT_LEN as u64
+}
+
+/// Returns the root hash of `data`.
+pub fn root_hash(data: &[u8]) -> Hash {
+    fn hash(data: &[u8], start: u64, root: bool) -> Hash {
+        if data.len() <= CHUNK_LEN {
+            return chunk_hash(data, start / CHUNK_LEN as u64, root);
+        }
+        let mid = left_len(data.len() as u64);
+        let left = hash(&data[..mid as usize], start, false);
+        let right = hash(&data[mid as usize..], start + mid, false);
+        parent_hash(&left, &right, root)
+    }
+    hash(data, 0, true)
+}
+
+/// Returns the root hash and the outboard encoding of `data`.
+pub fn encode_outboard(data: &[u8]) -> (Hash, Vec<u8>) {
+    fn encode(data: &[u8], start: u64, root: bool, outboard: &mut Vec<u8>) -> Hash {
+        if data.len() <= CHUNK_LEN {
+            return chunk_hash(data, start / CHUNK_LEN as u64, root);
+        }
+
+        // Reserve space for the parent node, which precedes its children.
+        let pos = outboard.len();
+        outboard.extend_from_slice(&[0u8; PARENT_LEN]);
+
+        let mid = left_len(data.len() as u64);
+        let left = encode(&data[..mid as usize], start, false, outboard);
+        let right = encode(&data[mid as usize..], start + mid, false, outboard);
+        outboard[pos..pos + HASH_LEN].copy_from_slice(&left);
+        outboard[pos + HASH_LEN..pos + PARENT_LEN].copy_from_slice(&right);
+        parent_hash(&left, &right, root)
+    }
+
+    let mut outboard = Vec::with_capacity(outboard_len(data.len() as u64) as usize);
+    outboard.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    let hash = encode(data, 0, true, &mut outboard);
+    (hash, outboard)
+}
+
+/// A subtree which has yet to be verified.
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    hash: Hash,
+    start: u64,
+    len: u64,
+    root: bool,
+}
+
+impl Node {
+    fn is_chunk(&self) -> bool {
+        self.len <= CHUNK_LEN as u64
+    }
+
+    fn chunk_range(&self) -> (u64, u64) {
+        chunk_range(self.start, self.len)
+    }
+}
+
+/// Returns the range of chunks in a subtree of `len` bytes at `start`.
+fn chunk_range(start: u64, len: u64) -> (u64, u64) {
+    let first = start / CHUNK_LEN as u64;
+    (first, first + len.div_ceil(CHUNK_LEN as u64).max(1))
+}
+
+/// A pre-order traversal of the subtrees which intersect a range of chunks, verifying each parent
+/// node and chunk against the hash of the subtree it claims to be.
+#[derive(Debug)]
+struct Verifier {
+    stack: Vec<Node>,
+    chunks: (u64, u64),
+}
+
+impl Verifier {
+    fn new(root: &Hash, len: u64, chunks: (u64, u64)) -> Verifier {
+        Verifier { stack: vec![Node { hash: *root, start: 0, len, root: true }], chunks }
+    }
+
+    /// Returns the next node in the traversal which intersects the range.
+    fn next(&mut self) -> Option<Node> {
+        while let Some(node) = self.stack.pop() {
+            let (first, last) = node.chunk_range();
+            if first < self.chunks.1 && self.chunks.0 < last {
+                return Some(node);
+            }
+        }
+        None
+    }
+
+    /// Verifies a parent node and queues its children.
+    fn parent(&mut self, node: Node, parent: &[u8; PARENT_LEN]) -> io::Result<()> {
+        let (left, right) = parent.split_at(HASH_LEN);
+        let (left, right) = (left.try_into().unwrap(), right.try_into().unwrap());
+        if parent_hash(left, right, node.root) != node.hash {
+            return Err(corrupt("parent node", node.start));
+        }
+
+        let mid = left_len(node.len);
+        self.stack.push(Node {
+            hash: *right,
+            start: node.start + mid,
+            len: node.len - mid,
+            root: false,
+        });
+        self.stack.push(Node { hash: *left, start: node.start, len: mid, root: false });
+        Ok(())
+    }
+
+    /// Verifies a chunk.
+    fn chunk(&self, node: Node, chunk: &[u8]) -> io::Result<()> {
+        if chunk_hash(chunk, node.start / CHUNK_LEN as u64, node.root) != node.hash {
+            return Err(corrupt("chunk", node.start));
+        }
+        Ok(())
+    }
+}
+
+fn corrupt(what: &str, offset: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{what} at offset {offset} failed to verify"),
+    )
+}
+
+fn read_header(r: &mut impl Read) -> io::Result<u64> {
+    let mut header = [0u8; HEADER_LEN];
+    r.read_exact(&mut header)?;
+    Ok(u64::from_le_bytes(header))
+}
+
+/// A reader which verifies content against its outboard encoding and root hash as it's read.
+///
+/// Each chunk is verified before any of its bytes are returned, so a reader never sees unverified
+/// content. Verification failures are returned as [`io::ErrorKind::InvalidData`] errors.
+#[derive(Debug)]
+pub struct Decoder<D, O> {
+    data: D,
+    outboard: O,
+    root: Hash,
+    verifier: Option<Verifier>,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<D: Read, O: Read> Decoder<D, O> {
+    /// Creates a new decoder for the given content, outboard encoding, and trusted root hash.
+    pub fn new(data: D, outboard: O, root: &Hash) -> Decoder<D, O> {
+        Decoder {
+            data,
+            outboard,
+            root: *root,
+            verifier: None,
+            buf: Vec::with_capacity(CHUNK_LEN),
+            pos: 0,
+        }
+    }
+
+    /// Reads and verifies the next chunk into the buffer. Returns `false` if no chunks remain.
+    fn fill(&mut self) -> io::Result<bool> {
+        let verifier = match &mut self.verifier {
+            Some(verifier) => verifier,
+            None => {
+                let len = read_header(&mut self.outboard)?;
+                let chunks = len.div_ceil(CHUNK_LEN as u64).max(1);
+                self.verifier.insert(Verifier::new(&self.root, len, (0, chunks)))
+            }
+        };
+
+        while let Some(node) = verifier.next() {
+            if node.is_chunk() {
+                self.buf.resize(node.len as usize, 0);
+                self.data.read_exact(&mut self.buf)?;
+                verifier.chunk(node, &self.buf)?;
+                self.pos = 0;
+                return Ok(true);
+            }
+
+            let mut parent = [0u8; PARENT_LEN];
+            self.outboard.read_exact(&mut parent)?;
+            verifier.parent(node, &parent)?;
+        }
+        Ok(false)
+    }
+}
+
+impl<D: Read, O: Read> Read for Decoder<D, O> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buf.len() && !self.fill()? {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.buf.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Returns the range of chunks which must be included in a slice of `len` bytes at `start`. A
+/// slice always includes at least one chunk, so empty slices and slices past the end of the
+/// content include the final chunk.
+fn slice_chunks(content_len: u64, start: u64, len: u64) -> (u64, u64) {
+    let chunks = content_len.div_ceil(CHUNK_LEN as u64).max(1);
+    let first = (start / CHUNK_LEN as u64).min(chunks - 1);
+    let last = start.saturating_add(len).div_ceil(CHUNK_LEN as u64).clamp(first + 1, chunks);
+    (first, last)
+}
+
+/// Extracts a slice of `len` bytes at `start` from content and its outboard encoding.
+///
+/// The slice contains the content length header followed, in pre-order, by every parent node and
+/// chunk needed to verify the requested range. Returns an error if the outboard encoding is
+/// malformed or doesn't match the content's length.
+pub fn extract_slice(data: &[u8], outboard: &[u8], start: u64, len: u64) -> io::Result<Vec<u8>> {
+    let mut outboard = outboard;
+    let content_len = read_header(&mut outboard)?;
+    if content_len != data.len() as u64 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "content length mismatch"));
+    }
+
+    let mut slice = content_len.to_le_bytes().to_vec();
+    let chunks = slice_chunks(content_len, start, len);
+    let mut stack = vec![(0u64, content_len, outboard)];
+    while let Some((start, len, outboard)) = stack.pop() {
+        let (first, last) = chunk_range(start, len);
+        if last <= chunks.0 || chunks.1 <= first {
+            continue;
+        }
+
+        if len <= CHUNK_LEN as u64 {
+            slice.extend_from_slice(&data[start as usize..(start + len) as usize]);
+            continue;
+        }
+
+        // A subtree of N chunks has N-1 parent nodes, which follow its own parent node.
+        let mid = left_len(len);
+        let left_parents = (mid / CHUNK_LEN as u64 - 1) as usize;
+        let parent = outboard.get(..PARENT_LEN).ok_or(io::ErrorKind::UnexpectedEof)?;
+        let (left, right) = outboard[PARENT_LEN..]
+            .split_at((left_parents * PARENT_LEN).min(outboard.len() - PARENT_LEN));
+        slice.extend_from_slice(parent);
+        stack.push((start + mid, len - mid, right));
+        stack.push((start, mid, left));
+    }
+    Ok(slice)
+}
+
+/// Verifies a slice against the trusted root hash, returning the content in the range of `len`
+/// bytes at `start`. Returns an [`io::ErrorKind::InvalidData`] error if any part of the slice
+/// fails to verify.
+pub fn decode_slice(slice: &[u8], root: &Hash, start: u64, len: u64) -> io::Result<Vec<u8>> {
+    let mut slice = slice;
+    let content_len = read_header(&mut slice)?;
+    let mut verifier = Verifier::new(root, content_len, slice_chunks(content_len, start, len));
+
+    let end = start.saturating_add(len).min(content_len);
+    // The header is unverified until the first chunk is, so size the output from the slice itself.
+    let mut out = Vec::with_capacity((end.saturating_sub(start) as usize).min(slice.len()));
+    let mut chunk = Vec::with_capacity(CHUNK_LEN);
+    while let Some(node) = verifier.next() {
+        if node.is_chunk() {
+            chunk.resize(node.len as usize, 0);
+            slice.read_exact(&mut chunk)?;
+            verifier.chunk(node, &chunk)?;
+
+            let lo = start.clamp(node.start, node.start + node.len) - node.start;
+            let hi = end.clamp(node.start, node.start + node.len) - node.start;
+            out.extend_from_slice(&chunk[lo as usize..hi as usize]);
+            continue;
+        }
+
+        let mut parent = [0u8; PARENT_LEN];
+        slice.read_exact(&mut parent)?;
+        verifier.parent(node, &parent)?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    const LENS: &[usize] = &[
+        0,
+        1,
+        BLOCK_LEN,
+        CHUNK_LEN - 1,
+        CHUNK_LEN,
+        CHUNK_LEN + 1,
+        2 * CHUNK_LEN,
+        3 * CHUNK_LEN + 7,
+        8 * CHUNK_LEN,
+        13 * CHUNK_LEN + 100,
+    ];
+
+    fn input(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn decode(data: &[u8], outboard: &[u8], root: &Hash) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        Decoder::new(data, outboard, root).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn root_hash_test_vector() {
+        expect![[r#"
+            02 27 62 2d 7e ae 3f 34 70 4a cc 17 85 f7 76 2e
+            61 c7 ad 6e 74 cb 5d 80 92 ec a6 5d 49 d9 21 cd"#]]
+        .assert_eq(&hex_fmt(&root_hash(&input(3 * CHUNK_LEN + 7))));
+    }
+
+    #[test]
+    fn round_trip() {
+        for &len in LENS {
+            let data = input(len);
+            let (root, outboard) = encode_outboard(&data);
+            assert_eq!(root, root_hash(&data), "len={len}");
+            assert_eq!(outboard.len() as u64, outboard_len(len as u64), "len={len}");
+            assert_eq!(decode(&data, &outboard, &root).expect("should verify"), data, "len={len}");
+        }
+    }
+
+    #[test]
+    fn distinct_lengths_yield_distinct_roots() {
+        let roots = LENS.iter().map(|&len| root_hash(&input(len))).collect::<Vec<_>>();
+        for (i, a) in roots.iter().enumerate() {
+            assert!(!roots[i + 1..].contains(a));
+        }
+        assert_ne!(root_hash(&[0; 32]), root_hash(&[0; 31]));
+    }
+
+    #[test]
+    fn wrong_root() {
+        let data = input(3 * CHUNK_LEN);
+        let (mut root, outboard) = encode_outboard(&data);
+        root[0] ^= 1;
+        let err = decode(&data, &outboard, &root).expect_err("should fail");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn corrupt_chunks_are_rejected_early() {
+        let mut data = input(8 * CHUNK_LEN);
+        let (root, outboard) = encode_outboard(&data);
+        data[5 * CHUNK_LEN + 3] ^= 1;
+
+        let mut decoder = Decoder::new(data.as_slice(), outboard.as_slice(), &root);
+        let mut buf = [0u8; CHUNK_LEN];
+        for i in 0..5 {
+            decoder.read_exact(&mut buf).expect("prior chunks should verify");
+            assert_eq!(&buf[..], &data[i * CHUNK_LEN..(i + 1) * CHUNK_LEN]);
+        }
+        let err = decoder.read_exact(&mut buf).expect_err("corrupt chunk should fail");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn corrupt_outboard() {
+        let data = input(8 * CHUNK_LEN);
+        let (root, outboard) = encode_outboard(&data);
+        for i in 0..outboard.len() {
+            let mut outboard = outboard.clone();
+            outboard[i] ^= 1;
+            assert!(decode(&data, &outboard, &root).is_err(), "byte {i}");
+        }
+    }
+
+    #[test]
+    fn truncated_data() {
+        let data = input(3 * CHUNK_LEN + 7);
+        let (root, outboard) = encode_outboard(&data);
+        let err = decode(&data[..data.len() - 1], &outboard, &root).expect_err("should fail");
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn slices() {
+        for &len in LENS {
+            let data = input(len);
+            let (root, outboard) = encode_outboard(&data);
+            for (start, n) in
+                [(0, 0), (0, 1), (1000, 100), (2048, 1024), (3000, 5000), (0, 1 << 20)]
+            {
+                let slice = extract_slice(&data, &outboard, start, n).expect("should extract");
+                let decoded = decode_slice(&slice, &root, start, n).expect("should verify");
+                let (lo, hi) = ((start as usize).min(len), ((start + n) as usize).min(len));
+                assert_eq!(decoded, &data[lo..hi], "len={len} start={start} n={n}");
+            }
+        }
+    }
+
+    #[test]
+    fn slices_are_smaller_than_content() {
+        let data = input(64 * CHUNK_LEN);
+        let (_, outboard) = encode_outboard(&data);
+        let slice = extract_slice(&data, &outboard, 10 * CHUNK_LEN as u64, 10).expect("extract");
+        assert_eq!(slice.len(), HEADER_LEN + 6 * PARENT_LEN + CHUNK_LEN);
+    }
+
+    #[test]
+    fn corrupt_slices() {
+        let data = input(8 * CHUNK_LEN);
+        let (root, outboard) = encode_outboard(&data);
+        let slice = extract_slice(&data, &outboard, 3000, 2000).expect("should extract");
+        for i in 0..slice.len() {
+            let mut slice = slice.clone();
+            slice[i] ^= 1;
+            assert!(decode_slice(&slice, &root, 3000, 2000).is_err(), "byte {i}");
+        }
+    }
+
+    #[test]
+    fn forged_slice_length() {
+        let slice = u64::MAX.to_le_bytes();
+        assert!(decode_slice(&slice, &[0; HASH_LEN], 0, u64::MAX).is_err());
+    }
+
+    #[quickcheck]
+    fn decoding_round_trip(data: Vec<u8>) -> bool {
+        let (root, outboard) = encode_outboard(&data);
+        decode(&data, &outboard, &root).ok() == Some(data)
+    }
+
+    #[test]
+    fn fuzz() {
+        bolero::check!().with_type::<(Vec<u8>, Vec<u8>)>().for_each(|(data, outboard)| {
+            let _ = decode(data, outboard, &[0; HASH_LEN]);
+            let _ = decode_slice(outboard, &[0; HASH_LEN], 0, data.len() as u64);
+        });
+    }
+}
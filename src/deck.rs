@@ -0,0 +1,264 @@
+use core::fmt;
+
+use crate::intrinsics::*;
+use crate::mask::Mask512;
+
+use cipher::consts::U32;
+use cipher::{AlgorithmName, Key, KeyInit, KeySizeUser};
+
+const BLOCK_LEN: usize = 64;
+
+const LANES: usize = 4;
+
+/// Areion512-Deck, a doubly-extendable cryptographic keyed function in the
+/// [Farfalle](https://eprint.iacr.org/2016/1188.pdf) style with 256-bit keys.
+///
+/// A deck function maps a sequence of strings to an arbitrarily long output, and allows both the
+/// input sequence and the output to be extended incrementally. The key is used to derive a base
+/// mask `k = P(K || 10*)`, which is rolled with the OPP mask function `φ` to produce an input mask
+/// for each block.
+///
+/// The compression layer pads each string with `10*` and sums `P(m_i ^ δ_i)` for each block `m_i`,
+/// where `δ_i = φ^i(k)` for all but the last block of each string, which uses `(φ ^ 1)(δ_i)` to
+/// mark the string boundary. The expansion layer computes `y = P(x) ^ (φ ^ 1)(φ^2 ^ φ ^ 1)(k)`
+/// from the accumulator `x` and produces output blocks `z_j = P(φ^j(y)) ^ (φ^2 ^ φ ^ 1)(k)`.
+/// Masking `y` with the key keeps the expansion layer keyed even when the accumulator is public,
+/// as it is for the empty sequence.
+///
+/// Both layers process independent blocks, and four permutation calls are made at a time.
+#[derive(Clone)]
+pub struct Areion512Deck {
+    k: Mask512,
+    delta: Mask512,
+    acc: AesBlock4,
+}
+
+impl Areion512Deck {
+    /// Appends the given string to the input sequence.
+    pub fn absorb(&mut self, string: &[u8]) {
+        let (blocks, tail) = string.split_at(string.len() / BLOCK_LEN * BLOCK_LEN);
+
+        let mut lanes = blocks.chunks_exact(LANES * BLOCK_LEN);
+        for lanes in lanes.by_ref() {
+            let d0 = self.delta;
+            let d1 = d0.phi();
+            let d2 = d1.phi();
+            let d3 = d2.phi();
+            let y0 = p(xor4(load4(&lanes[..64]), d0.blocks()));
+            let y1 = p(xor4(load4(&lanes[64..128]), d1.blocks()));
+            let y2 = p(xor4(load4(&lanes[128..192]), d2.blocks()));
+            let y3 = p(xor4(load4(&lanes[192..]), d3.blocks()));
+            self.acc = xor4(self.acc, xor4(xor4(y0, y1), xor4(y2, y3)));
+            self.delta = d3.phi();
+        }
+
+        for block in lanes.remainder().chunks_exact(BLOCK_LEN) {
+            self.acc = xor4(self.acc, p(xor4(load4(block), self.delta.blocks())));
+            self.delta = self.delta.phi();
+        }
+
+        let mut padded = [0u8; BLOCK_LEN];
+        padded[..tail.len()].copy_from_slice(tail);
+        padded[tail.len()] = 0x80;
+        self.acc = xor4(self.acc, p(xor4(load4(&padded), self.delta.phi_1().blocks())));
+        self.delta = self.delta.phi();
+    }
+
+    /// Returns `len` bytes of output for the input sequence, starting at `offset`.
+    pub fn squeeze(&self, offset: u64, len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        self.squeeze_into(offset, &mut out);
+        out
+    }
+
+    /// Fills `out` with output for the input sequence, starting at `offset`.
+    ///
+    /// Seeking to an offset takes time logarithmic in `offset`, as `φ^j(y)` is computed directly
+    /// rather than by evaluating `φ` `j` times.
+    pub fn squeeze_into(&self, offset: u64, out: &mut [u8]) {
+        let mut y = [0u8; BLOCK_LEN];
+        store4(&mut y, p(self.acc));
+        let mut y =
+            Mask512::from_bytes(&y).xor(self.k.phi_2().phi_1()).phi_n(offset / BLOCK_LEN as u64);
+        let k = self.k.phi_2().blocks();
+
+        let mut skip = (offset % BLOCK_LEN as u64) as usize;
+        let mut buf = [0u8; LANES * BLOCK_LEN];
+        let mut out = out;
+        while !out.is_empty() {
+            let n = (skip + out.len()).div_ceil(BLOCK_LEN).min(LANES);
+            for block in buf.chunks_exact_mut(BLOCK_LEN).take(n) {
+                store4(block, xor4(p(y.blocks()), k));
+                y = y.phi();
+            }
+
+            let len = (n * BLOCK_LEN - skip).min(out.len());
+            out[..len].copy_from_slice(&buf[skip..skip + len]);
+            out = &mut out[len..];
+            skip = 0;
+        }
+    }
+}
+
+impl KeySizeUser for Areion512Deck {
+    type KeySize = U32;
+}
+
+impl KeyInit for Areion512Deck {
+    fn new(key: &Key<Self>) -> Self {
+        let mut k = [0u8; BLOCK_LEN];
+        k[..32].copy_from_slice(key);
+        k[32] = 0x80;
+        let x = p(load4(&k));
+        store4(&mut k, x);
+        let k = Mask512::from_bytes(&k);
+        Areion512Deck { k, delta: k, acc: (zero(), zero(), zero(), zero()) }
+    }
+}
+
+impl AlgorithmName for Areion512Deck {
+    fn write_alg_name(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-Deck")
+    }
+}
+
+impl fmt::Debug for Areion512Deck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-Deck { ... }")
+    }
+}
+
+#[inline]
+fn p(x: AesBlock4) -> AesBlock4 {
+    crate::areion512(x.0, x.1, x.2, x.3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    fn deck(strings: &[&[u8]]) -> Areion512Deck {
+        let mut deck = Areion512Deck::new(&[0x0f; 32].into());
+        for s in strings {
+            deck.absorb(s);
+        }
+        deck
+    }
+
+    #[test]
+    fn test_vector() {
+        let deck = deck(&[b"this is one string", b"and this is another"]);
+        expect![[r#"
+            dc af 0b d2 74 36 db e6 32 6b 14 56 74 46 63 c8
+            43 0b b5 7c 22 4b fa 9d 38 e7 e1 ec f5 e7 d8 5f
+            18 72 d5 9f eb e8 ab d2 18 ee dd 42 62 a0 c4 9b
+            24 1d 71 af 1a 9d 2c 7d be 85 66 47 26 4c 5f 12
+            fb a4 3e 50 b4 fb dc ad ab 42 3b 0b f8 08 2a 41
+            a4 37 05 60 cc 06 69 bf 05 3d 21 5f 45 af 3d 19
+            33 4b 57 ea"#]]
+        .assert_eq(&hex_fmt(&deck.squeeze(0, 100)));
+    }
+
+    #[test]
+    fn long_strings() {
+        let deck = deck(&[&[0xaa; 1000], &[0xbb; 256]]);
+        expect![[r#"
+            0c d5 97 22 8a 8e 3f b5 23 cd 3f 75 6e 9a 77 1f
+            2e b9 e2 12 29 27 d8 4a cf bd 28 09 ff 3a 39 8c"#]]
+        .assert_eq(&hex_fmt(&deck.squeeze(0, 32)));
+    }
+
+    #[test]
+    fn string_boundaries() {
+        let outputs = [
+            deck(&[]),
+            deck(&[b""]),
+            deck(&[b"", b""]),
+            deck(&[b"abc"]),
+            deck(&[b"ab", b"c"]),
+            deck(&[b"a", b"bc"]),
+            deck(&[b"abc", b""]),
+            deck(&[b"", b"abc"]),
+            deck(&[&[0; 64]]),
+            deck(&[&[0; 63]]),
+            deck(&[&[0; 64], b""]),
+        ]
+        .map(|d| d.squeeze(0, 32));
+        for (i, a) in outputs.iter().enumerate() {
+            assert!(!outputs[i + 1..].contains(a), "{i}");
+        }
+    }
+
+    #[test]
+    fn empty_sequence_does_not_reveal_key() {
+        // Without a keyed expansion input, the empty sequence's output would be `P(P(0)) ^ k'`,
+        // revealing the output mask `k'`, and `deck([""])` would then reveal the input mask.
+        let mut pp0 = [0u8; BLOCK_LEN];
+        store4(&mut pp0, p(p((zero(), zero(), zero(), zero()))));
+        let k_out = xor_bytes(&deck(&[]).squeeze(0, 64), &pp0);
+
+        let mut acc = [0u8; BLOCK_LEN];
+        let z = xor_bytes(&deck(&[b""]).squeeze(0, 64), &k_out);
+        let x = load4(&z);
+        let x = crate::inv_areion512(x.0, x.1, x.2, x.3);
+        let x = crate::inv_areion512(x.0, x.1, x.2, x.3);
+        let x = crate::inv_areion512(x.0, x.1, x.2, x.3);
+        store4(&mut acc, x);
+        let mut pad = [0u8; BLOCK_LEN];
+        pad[0] = 0x80;
+        let k_in = xor_bytes(&acc, &pad);
+
+        let mut m = [0u8; BLOCK_LEN];
+        m[..3].copy_from_slice(b"abc");
+        m[3] = 0x80;
+        let mut forged = [0u8; BLOCK_LEN];
+        store4(&mut forged, p(p(p(load4(&xor_bytes(&m, &k_in))))));
+        let forged = xor_bytes(&forged, &k_out);
+
+        assert_ne!(forged, deck(&[b"abc"]).squeeze(0, 64));
+    }
+
+    fn xor_bytes(a: &[u8], b: &[u8]) -> Vec<u8> {
+        a.iter().zip(b).map(|(a, b)| a ^ b).collect()
+    }
+
+    #[test]
+    fn incremental_absorb() {
+        let mut d = deck(&[b"first"]);
+        let a = d.squeeze(0, 32);
+        d.absorb(b"second");
+        let b = d.squeeze(0, 32);
+        assert_ne!(a, b);
+        assert_eq!(b, deck(&[b"first", b"second"]).squeeze(0, 32));
+    }
+
+    #[quickcheck]
+    fn squeezing_is_seekable(offset: u16, len: u16) -> bool {
+        let (offset, len) = (offset as usize % 1000, len as usize % 1000);
+        let deck = deck(&[b"seek"]);
+        let all = deck.squeeze(0, 2000);
+        deck.squeeze(offset as u64, len) == all[offset..offset + len]
+    }
+
+    #[test]
+    fn distant_offsets() {
+        let deck = deck(&[b"seek"]);
+        let all = deck.squeeze(0, 40_000);
+        assert_eq!(deck.squeeze(35_005, 100), all[35_005..35_105]);
+
+        let end = deck.squeeze(u64::MAX - 200, 200);
+        assert_eq!(deck.squeeze(u64::MAX - 100, 100), end[100..]);
+    }
+
+    #[quickcheck]
+    fn different_sequences_yield_different_outputs(a: Vec<Vec<u8>>, b: Vec<Vec<u8>>) -> bool {
+        let aa = deck(&a.iter().map(Vec::as_slice).collect::<Vec<_>>()).squeeze(0, 32);
+        let bb = deck(&b.iter().map(Vec::as_slice).collect::<Vec<_>>()).squeeze(0, 32);
+        (aa != bb) == (a != b)
+    }
+}
@@ -0,0 +1,372 @@
+use core::fmt;
+
+use crate::AreionHaifaVar;
+
+use digest::{Update, VariableOutput};
+
+/// The output length of the hash function, in bytes.
+const OUT_LEN: usize = 64;
+
+/// The length of the `V` and `C` state values, in bytes. This is the `seedlen` of 888 bits used by
+/// SP 800-90A for hash functions with 512-bit outputs.
+const SEED_LEN: usize = 111;
+
+/// The minimum length of entropy input, in bytes, for a security strength of 256 bits.
+const MIN_ENTROPY_LEN: usize = 32;
+
+/// The minimum length of the nonce, in bytes, which is half the security strength.
+const MIN_NONCE_LEN: usize = 16;
+
+/// An error returned by [`AreionHashDrbg`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrbgError {
+    /// The entropy input or nonce was shorter than required for the security strength.
+    InsufficientEntropy,
+    /// More than [`AreionHashDrbg::MAX_REQUEST_LEN`] bytes were requested.
+    RequestTooLarge,
+    /// The reseed interval has elapsed and the DRBG must be reseeded before generating more output.
+    ReseedRequired,
+}
+
+impl fmt::Display for DrbgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DrbgError::InsufficientEntropy => "insufficient entropy input",
+            DrbgError::RequestTooLarge => "requested output is too large",
+            DrbgError::ReseedRequired => "reseed required",
+        })
+    }
+}
+
+impl std::error::Error for DrbgError {}
+
+/// AreionHashDrbg, a deterministic random bit generator in the style of the
+/// [NIST SP 800-90A](https://csrc.nist.gov/pubs/sp/800/90/a/r1/final) Hash_DRBG, using
+/// Areion512-HAIFA with 512-bit outputs as the hash function and a security strength of 256 bits.
+///
+/// The internal state is a pair of 888-bit values `V` and `C` and a reseed counter. Seed material
+/// is condensed into `V` and `C` with the `Hash_df` derivation function. Output is generated by
+/// hashing successive values of `V`, after which `V` is advanced by `Hash(0x03 || V)`, `C`, and the
+/// reseed counter. Requests are limited to [`AreionHashDrbg::MAX_REQUEST_LEN`] bytes and the DRBG
+/// must be reseeded after [`AreionHashDrbg::RESEED_INTERVAL`] requests.
+///
+/// Entropy inputs must be at least 32 bytes and nonces at least 16 bytes.
+pub struct AreionHashDrbg {
+    v: [u8; SEED_LEN],
+    c: [u8; SEED_LEN],
+    reseed_counter: u64,
+}
+
+impl AreionHashDrbg {
+    /// The maximum number of bytes which can be returned by a single call to `generate`.
+    pub const MAX_REQUEST_LEN: usize = 1 << 16;
+
+    /// The maximum number of calls to `generate` between reseeds.
+    pub const RESEED_INTERVAL: u64 = 1 << 48;
+
+    /// Instantiates a DRBG with the given entropy input, nonce, and personalization string.
+    pub fn instantiate(
+        entropy: &[u8],
+        nonce: &[u8],
+        personalization: &[u8],
+    ) -> Result<AreionHashDrbg, DrbgError> {
+        if entropy.len() < MIN_ENTROPY_LEN || nonce.len() < MIN_NONCE_LEN {
+            return Err(DrbgError::InsufficientEntropy);
+        }
+
+        let v = hash_df(&[entropy, nonce, personalization]);
+        let c = hash_df(&[&[0x00], &v]);
+        Ok(AreionHashDrbg { v, c, reseed_counter: 1 })
+    }
+
+    /// Reseeds the DRBG with the given entropy input and additional input.
+    pub fn reseed(&mut self, entropy: &[u8], additional: &[u8]) -> Result<(), DrbgError> {
+        if entropy.len() < MIN_ENTROPY_LEN {
+            return Err(DrbgError::InsufficientEntropy);
+        }
+
+        self.v = hash_df(&[&[0x01], &self.v, entropy, additional]);
+        self.c = hash_df(&[&[0x00], &self.v]);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Fills `out` with pseudorandom bytes, mixing in the given additional input.
+    ///
+    /// Returns [`DrbgError::ReseedRequired`] once the reseed interval has elapsed, in which case
+    /// [`AreionHashDrbg::reseed`] must be called before generating more output.
+    pub fn generate(&mut self, out: &mut [u8], additional: &[u8]) -> Result<(), DrbgError> {
+        if out.len() > Self::MAX_REQUEST_LEN {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        if self.reseed_counter > Self::RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+
+        if !additional.is_empty() {
+            let w = hash(&[&[0x02], &self.v, additional]);
+            add(&mut self.v, &w);
+        }
+
+        // Hashgen: hash successive values of V.
+        let mut data = self.v;
+        for chunk in out.chunks_mut(OUT_LEN) {
+            chunk.copy_from_slice(&hash(&[&data])[..chunk.len()]);
+            add(&mut data, &[1]);
+        }
+
+        let h = hash(&[&[0x03], &self.v]);
+        let c = self.c;
+        add(&mut self.v, &h);
+        add(&mut self.v, &c);
+        add(&mut self.v, &self.reseed_counter.to_be_bytes());
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// Reseeds the DRBG with the given entropy input and additional input, then fills `out` with
+    /// pseudorandom bytes. This provides prediction resistance: the output is unpredictable even
+    /// to an attacker who has compromised the DRBG's prior state.
+    pub fn generate_with_prediction_resistance(
+        &mut self,
+        out: &mut [u8],
+        entropy: &[u8],
+        additional: &[u8],
+    ) -> Result<(), DrbgError> {
+        if out.len() > Self::MAX_REQUEST_LEN {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        self.reseed(entropy, additional)?;
+        self.generate(out, b"")
+    }
+}
+
+impl fmt::Debug for AreionHashDrbg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AreionHashDrbg { ... }")
+    }
+}
+
+/// Hashes the concatenation of the given inputs.
+fn hash(inputs: &[&[u8]]) -> [u8; OUT_LEN] {
+    let mut h = AreionHaifaVar::new(OUT_LEN).expect("should be a valid output size");
+    for input in inputs {
+        h.update(input);
+    }
+    let mut out = [0u8; OUT_LEN];
+    h.finalize_variable(&mut out).expect("should be a valid output size");
+    out
+}
+
+/// The `Hash_df` derivation function, which condenses the concatenation of the given inputs into
+/// `seedlen` bits.
+fn hash_df(inputs: &[&[u8]]) -> [u8; SEED_LEN] {
+    let bits = ((SEED_LEN * 8) as u32).to_be_bytes();
+    let mut out = [0u8; SEED_LEN];
+    for (i, chunk) in out.chunks_mut(OUT_LEN).enumerate() {
+        let counter = [i as u8 + 1];
+        let mut parts = vec![&counter[..], &bits[..]];
+        parts.extend_from_slice(inputs);
+        chunk.copy_from_slice(&hash(&parts)[..chunk.len()]);
+    }
+    out
+}
+
+/// Adds `x` to `v` modulo `2^seedlen`, treating both as big-endian integers.
+fn add(v: &mut [u8; SEED_LEN], x: &[u8]) {
+    let mut carry = 0u16;
+    let mut x = x.iter().rev();
+    for b in v.iter_mut().rev() {
+        let sum = *b as u16 + *x.next().unwrap_or(&0) as u16 + carry;
+        *b = sum as u8;
+        carry = sum >> 8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+
+    /// A known-answer test parsed from a `.rsp` file.
+    #[derive(Debug, Default)]
+    struct Kat {
+        count: usize,
+        prediction_resistance: bool,
+        entropy: Vec<u8>,
+        nonce: Vec<u8>,
+        personalization: Vec<u8>,
+        entropy_reseed: Vec<u8>,
+        additional_reseed: Vec<u8>,
+        entropy_pr: Vec<Vec<u8>>,
+        additional: Vec<Vec<u8>>,
+        returned: Vec<u8>,
+    }
+
+    impl Kat {
+        fn run(&self) -> Vec<u8> {
+            let mut drbg =
+                AreionHashDrbg::instantiate(&self.entropy, &self.nonce, &self.personalization)
+                    .expect("should instantiate");
+            let mut out = vec![0u8; self.returned.len()];
+            if self.prediction_resistance {
+                for (entropy, additional) in self.entropy_pr.iter().zip(&self.additional) {
+                    drbg.generate_with_prediction_resistance(&mut out, entropy, additional)
+                        .expect("should generate");
+                }
+            } else {
+                drbg.reseed(&self.entropy_reseed, &self.additional_reseed).expect("should reseed");
+                for additional in &self.additional {
+                    drbg.generate(&mut out, additional).expect("should generate");
+                }
+            }
+            out
+        }
+    }
+
+    fn parse_rsp(rsp: &str) -> Vec<Kat> {
+        let mut kats = Vec::new();
+        let mut prediction_resistance = false;
+        let mut kat = Kat::default();
+        for line in rsp.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if let Some(pr) = header.strip_prefix("PredictionResistance = ") {
+                    prediction_resistance = pr == "True";
+                }
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').expect("should be a key-value pair");
+            let (key, value) = (key.trim(), value.trim());
+            let bytes = || hex::decode(value).expect("should be hex");
+            match key {
+                "COUNT" => {
+                    kat = Kat {
+                        count: value.parse().expect("should be a number"),
+                        prediction_resistance,
+                        ..Kat::default()
+                    }
+                }
+                "EntropyInput" => kat.entropy = bytes(),
+                "Nonce" => kat.nonce = bytes(),
+                "PersonalizationString" => kat.personalization = bytes(),
+                "EntropyInputReseed" => kat.entropy_reseed = bytes(),
+                "AdditionalInputReseed" => kat.additional_reseed = bytes(),
+                "EntropyInputPR" => kat.entropy_pr.push(bytes()),
+                "AdditionalInput" => kat.additional.push(bytes()),
+                "ReturnedBits" => {
+                    kat.returned = bytes();
+                    kats.push(core::mem::take(&mut kat));
+                }
+                _ => panic!("unknown key: {key}"),
+            }
+        }
+        kats
+    }
+
+    #[test]
+    fn known_answers() {
+        let kats = parse_rsp(include_str!("../testdata/areion_hash_drbg.rsp"));
+        assert_eq!(kats.len(), 8);
+        for kat in kats {
+            assert_eq!(
+                hex_fmt(&kat.run()),
+                hex_fmt(&kat.returned),
+                "COUNT = {}, PR = {}",
+                kat.count,
+                kat.prediction_resistance
+            );
+        }
+    }
+
+    #[test]
+    fn test_vector() {
+        let mut drbg = AreionHashDrbg::instantiate(&[0x0f; 32], &[0xaa; 16], b"personalization")
+            .expect("should instantiate");
+        let mut out = [0u8; 100];
+        drbg.generate(&mut out, b"").expect("should generate");
+        expect![[r#"
+            be 7f 1d ae 54 01 eb 28 e3 5f 2a aa 54 0d ab a3
+            5a 93 6f 88 de 29 c7 42 6e 9e 95 f3 56 4d 34 07
+            3c 7d cb e9 44 bb d6 7a 80 71 26 c4 53 49 a5 44
+            6a 3a 83 64 40 ce 02 7d da 83 9f f2 96 1f 0c bb
+            9c f7 d4 31 39 52 f9 ce 5a 08 67 a9 02 3a c1 81
+            e1 34 ce ec 32 77 8a 22 4e 9c 4b 92 a8 79 1e 01
+            83 56 dc 03"#]]
+        .assert_eq(&hex_fmt(&out));
+    }
+
+    #[test]
+    fn additional_input() {
+        let drbg = || {
+            AreionHashDrbg::instantiate(&[0x0f; 32], &[0xaa; 16], b"").expect("should instantiate")
+        };
+        let (mut a, mut b) = (drbg(), drbg());
+        let (mut x, mut y) = ([0u8; 32], [0u8; 32]);
+        a.generate(&mut x, b"").expect("should generate");
+        b.generate(&mut y, b"additional").expect("should generate");
+        assert_ne!(x, y);
+
+        // The additional input is mixed into the state, not just the output.
+        a.generate(&mut x, b"").expect("should generate");
+        b.generate(&mut y, b"").expect("should generate");
+        assert_ne!(x, y);
+    }
+
+    #[test]
+    fn insufficient_entropy() {
+        assert_eq!(
+            AreionHashDrbg::instantiate(&[0; 31], &[0; 16], b"").map(|_| ()),
+            Err(DrbgError::InsufficientEntropy)
+        );
+        assert_eq!(
+            AreionHashDrbg::instantiate(&[0; 32], &[0; 15], b"").map(|_| ()),
+            Err(DrbgError::InsufficientEntropy)
+        );
+
+        let mut drbg =
+            AreionHashDrbg::instantiate(&[0; 32], &[0; 16], b"").expect("should instantiate");
+        assert_eq!(drbg.reseed(&[0; 31], b""), Err(DrbgError::InsufficientEntropy));
+    }
+
+    #[test]
+    fn request_limits() {
+        let mut drbg =
+            AreionHashDrbg::instantiate(&[0; 32], &[0; 16], b"").expect("should instantiate");
+        assert!(drbg.generate(&mut vec![0; AreionHashDrbg::MAX_REQUEST_LEN], b"").is_ok());
+        assert_eq!(
+            drbg.generate(&mut vec![0; AreionHashDrbg::MAX_REQUEST_LEN + 1], b""),
+            Err(DrbgError::RequestTooLarge)
+        );
+    }
+
+    #[test]
+    fn reseed_required() {
+        let mut drbg =
+            AreionHashDrbg::instantiate(&[0; 32], &[0; 16], b"").expect("should instantiate");
+        drbg.reseed_counter = AreionHashDrbg::RESEED_INTERVAL;
+        assert!(drbg.generate(&mut [0; 32], b"").is_ok());
+        assert_eq!(drbg.generate(&mut [0; 32], b""), Err(DrbgError::ReseedRequired));
+
+        drbg.reseed(&[1; 32], b"").expect("should reseed");
+        assert!(drbg.generate(&mut [0; 32], b"").is_ok());
+    }
+
+    #[test]
+    fn addition_carries() {
+        let mut v = [0xff; SEED_LEN];
+        add(&mut v, &[1]);
+        assert_eq!(v, [0; SEED_LEN]);
+
+        let mut v = [0; SEED_LEN];
+        v[SEED_LEN - 1] = 0xff;
+        add(&mut v, &[0x01, 0x01]);
+        assert_eq!(v[SEED_LEN - 3..], [0x00, 0x02, 0x00]);
+    }
+}
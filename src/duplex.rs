@@ -0,0 +1,273 @@
+use crate::intrinsics::*;
+
+use aead::consts::{U0, U16, U32};
+use aead::{AeadCore, AeadInPlace, Key, KeyInit, KeySizeUser, Nonce, Tag};
+use hex_literal::hex;
+use subtle::ConstantTimeEq;
+
+/// The rate of the duplex, in bytes.
+const RATE: usize = 32;
+
+/// The initialization vector, encoding the version, key size, rate, and tag size in bytes.
+const IV: [u8; 16] = hex!("01 20 20 10 00 00 00 00 00 00 00 00 00 00 00 00");
+
+/// Areion512-Duplex, an authenticated cipher in the style of
+/// [Ascon](https://ascon.iaik.tugraz.at) using a keyed duplex over the Areion512 permutation, with
+/// 256-bit keys, 128-bit nonces, and 128-bit tags.
+///
+/// The 512-bit state is split into a 256-bit rate `(x_0, x_1)` and a 256-bit capacity `(x_2, x_3)`,
+/// for a security level of 128 bits. The state is initialized with `IV || N || K`, permuted, and
+/// the key is XORed into the capacity. Associated data and plaintext are absorbed through the rate
+/// in 32-byte blocks padded with `10*`, with a domain separation bit XORed into the capacity
+/// between them. Finally, the key is XORed into the capacity again, the state is permuted, and the
+/// tag is the last 128 bits of the capacity XORed with the last 128 bits of the key.
+///
+/// Encryption and decryption are single-pass and require only the 64-byte state.
+#[derive(Clone)]
+pub struct Areion512Duplex {
+    k: (AesBlock, AesBlock),
+}
+
+type State = (AesBlock, AesBlock, AesBlock, AesBlock);
+
+impl Areion512Duplex {
+    fn init(&self, nonce: &Nonce<Self>) -> State {
+        let (k0, k1) = self.k;
+        let (x0, x1, x2, x3) = crate::areion512(load(&IV), load(nonce), k0, k1);
+        (x0, x1, xor(x2, k0), xor(x3, k1))
+    }
+
+    fn absorb(s: State, ad: &[u8]) -> State {
+        let (mut x0, mut x1, mut x2, mut x3) = s;
+
+        if !ad.is_empty() {
+            let mut blocks = ad.chunks_exact(RATE);
+            for block in blocks.by_ref() {
+                (x0, x1, x2, x3) = crate::areion512(
+                    xor(x0, load(&block[..16])),
+                    xor(x1, load(&block[16..])),
+                    x2,
+                    x3,
+                );
+            }
+            let padded = pad(blocks.remainder());
+            (x0, x1, x2, x3) = crate::areion512(
+                xor(x0, load(&padded[..16])),
+                xor(x1, load(&padded[16..])),
+                x2,
+                x3,
+            );
+        }
+
+        // Separate the associated data from the message.
+        let mut sep = [0u8; 16];
+        sep[15] = 1;
+        (x0, x1, x2, xor(x3, load(&sep)))
+    }
+
+    fn crypt(s: State, buffer: &mut [u8], encrypt: bool) -> State {
+        let (mut x0, mut x1, mut x2, mut x3) = s;
+
+        let mut blocks = buffer.chunks_exact_mut(RATE);
+        for block in blocks.by_ref() {
+            let (b0, b1) = (load(&block[..16]), load(&block[16..]));
+            let (y0, y1) = (xor(x0, b0), xor(x1, b1));
+            store(&mut block[..16], y0);
+            store(&mut block[16..], y1);
+
+            // The ciphertext block replaces the rate.
+            let (c0, c1) = if encrypt { (y0, y1) } else { (b0, b1) };
+            (x0, x1, x2, x3) = crate::areion512(c0, c1, x2, x3);
+        }
+
+        // Process the final, possibly empty, partial block.
+        let tail = blocks.into_remainder();
+        let mut rate = [0u8; RATE];
+        store(&mut rate[..16], x0);
+        store(&mut rate[16..], x1);
+        for (r, b) in rate.iter_mut().zip(tail.iter_mut()) {
+            let c = if encrypt { *r ^ *b } else { *b };
+            *b ^= *r;
+            *r = c;
+        }
+        rate[tail.len()] ^= 0x80;
+
+        (load(&rate[..16]), load(&rate[16..]), x2, x3)
+    }
+
+    fn tag(&self, s: State) -> Tag<Self> {
+        let (k0, k1) = self.k;
+        let (x0, x1, x2, x3) = s;
+        let (_, _, _, x3) = crate::areion512(x0, x1, xor(x2, k0), xor(x3, k1));
+        let mut tag = Tag::<Self>::default();
+        store(&mut tag, xor(x3, k1));
+        tag
+    }
+}
+
+/// Pads a partial block with a single set bit followed by zeros.
+fn pad(partial: &[u8]) -> [u8; RATE] {
+    let mut block = [0u8; RATE];
+    block[..partial.len()].copy_from_slice(partial);
+    block[partial.len()] = 0x80;
+    block
+}
+
+impl KeySizeUser for Areion512Duplex {
+    type KeySize = U32;
+}
+
+impl KeyInit for Areion512Duplex {
+    fn new(key: &Key<Self>) -> Self {
+        Areion512Duplex { k: (load(&key[..16]), load(&key[16..])) }
+    }
+}
+
+impl AeadCore for Areion512Duplex {
+    type NonceSize = U16;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+impl AeadInPlace for Areion512Duplex {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> aead::Result<Tag<Self>> {
+        let s = Self::absorb(self.init(nonce), associated_data);
+        let s = Self::crypt(s, buffer, true);
+        Ok(self.tag(s))
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> aead::Result<()> {
+        let s = Self::absorb(self.init(nonce), associated_data);
+        let s = Self::crypt(s, buffer, false);
+        if self.tag(s).ct_eq(tag).into() {
+            Ok(())
+        } else {
+            // Don't release unverified plaintext.
+            buffer.fill(0);
+            Err(aead::Error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use aead::generic_array::GenericArray;
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    fn seal(key: &[u8], nonce: &[u8], ad: &[u8], pt: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let cipher = Areion512Duplex::new_from_slice(key).expect("should be a valid key");
+        let mut ct = pt.to_vec();
+        let tag = cipher
+            .encrypt_in_place_detached(GenericArray::from_slice(nonce), ad, &mut ct)
+            .expect("should encrypt");
+        (ct, tag.to_vec())
+    }
+
+    fn open(key: &[u8], nonce: &[u8], ad: &[u8], ct: &[u8], tag: &[u8]) -> Option<Vec<u8>> {
+        let cipher = Areion512Duplex::new_from_slice(key).expect("should be a valid key");
+        let mut pt = ct.to_vec();
+        cipher
+            .decrypt_in_place_detached(
+                GenericArray::from_slice(nonce),
+                ad,
+                &mut pt,
+                GenericArray::from_slice(tag),
+            )
+            .ok()
+            .map(|_| pt)
+    }
+
+    #[test]
+    fn test_vector() {
+        let key = (0..32).collect::<Vec<u8>>();
+        let nonce = (32..48).collect::<Vec<u8>>();
+        let pt = (0..100).collect::<Vec<u8>>();
+        let (ct, tag) = seal(&key, &nonce, b"header", &pt);
+        expect![[r#"
+            1a cf bb 0c f0 f2 1d 67 c1 6c f2 5a 4c 36 21 7d
+            83 5f c3 e6 9d 32 9d 04 c7 0e c7 91 f9 43 55 c4
+            bc 21 72 7a 39 89 08 3a 5c e9 96 0c db 26 ed cf
+            f4 93 1f eb 84 9c 09 23 53 14 55 9b b2 6a aa 8c
+            cb 97 d7 df 7a d7 68 86 96 01 1b 16 e8 01 d1 9e
+            4f 97 26 5e cc 06 a3 bd 0a a5 4b 39 aa 03 a6 59
+            81 aa 02 38"#]]
+        .assert_eq(&hex_fmt(&ct));
+        expect!["35 9c 34 4d 92 65 1d ae bc d8 75 83 74 c8 4e f6"].assert_eq(&hex_fmt(&tag));
+    }
+
+    #[test]
+    fn empty_inputs() {
+        let (ct, tag) = seal(&[0; 32], &[0; 16], b"", b"");
+        assert!(ct.is_empty());
+        expect!["4c 68 f9 ae 1c ed ae 77 4b f7 ec 78 b8 cd 84 80"].assert_eq(&hex_fmt(&tag));
+
+        // Empty associated data is distinct from a single zero byte.
+        assert_ne!(seal(&[0; 32], &[0; 16], &[0], b"").1, tag);
+    }
+
+    #[test]
+    fn tampering() {
+        let (key, nonce) = ([7u8; 32], [9u8; 16]);
+        for len in [0, 1, 31, 32, 33, 200] {
+            let pt = vec![0xcc; len];
+            let (ct, tag) = seal(&key, &nonce, b"ad", &pt);
+            assert_eq!(open(&key, &nonce, b"ad", &ct, &tag), Some(pt));
+
+            assert_eq!(open(&key, &nonce, b"AD", &ct, &tag), None, "ad, len={len}");
+            assert_eq!(open(&key, &[8; 16], b"ad", &ct, &tag), None, "nonce");
+            assert_eq!(open(&[6; 32], &nonce, b"ad", &ct, &tag), None, "key");
+            for i in 0..ct.len() {
+                let mut ct = ct.clone();
+                ct[i] ^= 1;
+                assert_eq!(open(&key, &nonce, b"ad", &ct, &tag), None, "ct[{i}]");
+            }
+            for i in 0..tag.len() {
+                let mut tag = tag.clone();
+                tag[i] ^= 1;
+                assert_eq!(open(&key, &nonce, b"ad", &ct, &tag), None, "tag[{i}]");
+            }
+        }
+    }
+
+    #[test]
+    fn failed_decryption_clears_buffer() {
+        let cipher = Areion512Duplex::new(&[7u8; 32].into());
+        let nonce = [9u8; 16].into();
+        let mut buf = vec![0xcc; 100];
+        let tag = cipher.encrypt_in_place_detached(&nonce, b"", &mut buf).expect("should encrypt");
+        assert!(cipher.decrypt_in_place_detached(&nonce, b"x", &mut buf, &tag).is_err());
+        assert_eq!(buf, vec![0; 100]);
+    }
+
+    #[quickcheck]
+    fn round_trip(key: Vec<u8>, nonce: Vec<u8>, ad: Vec<u8>, pt: Vec<u8>) -> bool {
+        let (mut key, mut nonce) = (key, nonce);
+        key.resize(32, 0);
+        nonce.resize(16, 0);
+        let (ct, tag) = seal(&key, &nonce, &ad, &pt);
+        open(&key, &nonce, &ad, &ct, &tag) == Some(pt)
+    }
+
+    #[test]
+    fn fuzz() {
+        bolero::check!().with_type::<(Vec<u8>, Vec<u8>, [u8; 16])>().for_each(|(ad, ct, tag)| {
+            assert_eq!(open(&[0; 32], &[0; 16], ad, ct, tag), None);
+        });
+    }
+}
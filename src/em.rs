@@ -0,0 +1,267 @@
+use core::fmt;
+
+use crate::intrinsics::*;
+
+use cipher::consts::{U32, U64};
+use cipher::{AlgorithmName, BlockCipher, Key, KeyInit, KeySizeUser};
+
+/// Areion256-EM, a single-key Even-Mansour block cipher with 256-bit blocks and keys:
+/// `E(K, M) = P(M ^ K) ^ K`.
+#[derive(Clone)]
+pub struct Areion256Em {
+    k: (AesBlock, AesBlock),
+}
+
+impl Areion256Em {
+    #[inline]
+    pub(crate) fn encrypt_words(&self, x0: AesBlock, x1: AesBlock) -> (AesBlock, AesBlock) {
+        let (k0, k1) = self.k;
+        let (y0, y1) = crate::areion256(xor(x0, k0), xor(x1, k1));
+        (xor(y0, k0), xor(y1, k1))
+    }
+
+    #[inline]
+    pub(crate) fn decrypt_words(&self, x0: AesBlock, x1: AesBlock) -> (AesBlock, AesBlock) {
+        let (k0, k1) = self.k;
+        let (y0, y1) = crate::inv_areion256(xor(x0, k0), xor(x1, k1));
+        (xor(y0, k0), xor(y1, k1))
+    }
+}
+
+impl KeySizeUser for Areion256Em {
+    type KeySize = U32;
+}
+
+impl KeyInit for Areion256Em {
+    fn new(key: &Key<Self>) -> Self {
+        Areion256Em { k: (load(&key[..16]), load(&key[16..])) }
+    }
+}
+
+impl BlockCipher for Areion256Em {}
+
+cipher::impl_simple_block_encdec!(
+    Areion256Em, U32, cipher, block,
+    encrypt: {
+        let b = block.get_in();
+        let (y0, y1) = cipher.encrypt_words(load(&b[..16]), load(&b[16..]));
+        let b = block.get_out();
+        store(&mut b[..16], y0);
+        store(&mut b[16..], y1);
+    }
+    decrypt: {
+        let b = block.get_in();
+        let (y0, y1) = cipher.decrypt_words(load(&b[..16]), load(&b[16..]));
+        let b = block.get_out();
+        store(&mut b[..16], y0);
+        store(&mut b[16..], y1);
+    }
+);
+
+impl AlgorithmName for Areion256Em {
+    fn write_alg_name(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion256-EM")
+    }
+}
+
+impl fmt::Debug for Areion256Em {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion256-EM { ... }")
+    }
+}
+
+/// Areion512-EM, a single-key Even-Mansour block cipher with 512-bit blocks and keys:
+/// `E(K, M) = P(M ^ K) ^ K`.
+#[derive(Clone)]
+pub struct Areion512Em {
+    k: (AesBlock, AesBlock, AesBlock, AesBlock),
+}
+
+impl Areion512Em {
+    #[inline]
+    pub(crate) fn encrypt_words(
+        &self,
+        x0: AesBlock,
+        x1: AesBlock,
+        x2: AesBlock,
+        x3: AesBlock,
+    ) -> (AesBlock, AesBlock, AesBlock, AesBlock) {
+        let (k0, k1, k2, k3) = self.k;
+        let (y0, y1, y2, y3) = crate::areion512(xor(x0, k0), xor(x1, k1), xor(x2, k2), xor(x3, k3));
+        (xor(y0, k0), xor(y1, k1), xor(y2, k2), xor(y3, k3))
+    }
+
+    #[inline]
+    pub(crate) fn decrypt_words(
+        &self,
+        x0: AesBlock,
+        x1: AesBlock,
+        x2: AesBlock,
+        x3: AesBlock,
+    ) -> (AesBlock, AesBlock, AesBlock, AesBlock) {
+        let (k0, k1, k2, k3) = self.k;
+        let (y0, y1, y2, y3) =
+            crate::inv_areion512(xor(x0, k0), xor(x1, k1), xor(x2, k2), xor(x3, k3));
+        (xor(y0, k0), xor(y1, k1), xor(y2, k2), xor(y3, k3))
+    }
+}
+
+impl KeySizeUser for Areion512Em {
+    type KeySize = U64;
+}
+
+impl KeyInit for Areion512Em {
+    fn new(key: &Key<Self>) -> Self {
+        Areion512Em {
+            k: (load(&key[..16]), load(&key[16..32]), load(&key[32..48]), load(&key[48..])),
+        }
+    }
+}
+
+impl BlockCipher for Areion512Em {}
+
+cipher::impl_simple_block_encdec!(
+    Areion512Em, U64, cipher, block,
+    encrypt: {
+        let b = block.get_in();
+        let (y0, y1, y2, y3) = cipher.encrypt_words(
+            load(&b[..16]),
+            load(&b[16..32]),
+            load(&b[32..48]),
+            load(&b[48..]),
+        );
+        let b = block.get_out();
+        store(&mut b[..16], y0);
+        store(&mut b[16..32], y1);
+        store(&mut b[32..48], y2);
+        store(&mut b[48..], y3);
+    }
+    decrypt: {
+        let b = block.get_in();
+        let (y0, y1, y2, y3) = cipher.decrypt_words(
+            load(&b[..16]),
+            load(&b[16..32]),
+            load(&b[32..48]),
+            load(&b[48..]),
+        );
+        let b = block.get_out();
+        store(&mut b[..16], y0);
+        store(&mut b[16..32], y1);
+        store(&mut b[32..48], y2);
+        store(&mut b[48..], y3);
+    }
+);
+
+impl AlgorithmName for Areion512Em {
+    fn write_alg_name(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-EM")
+    }
+}
+
+impl fmt::Debug for Areion512Em {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-EM { ... }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use cipher::generic_array::GenericArray;
+    use cipher::{BlockDecrypt, BlockEncrypt};
+    use expect_test::expect;
+    use hex_literal::hex;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn zero_key_is_the_permutation() {
+        let mut block = GenericArray::from(hex!(
+            "
+            00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f
+            10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f"
+        ));
+        Areion256Em::new(&Default::default()).encrypt_block(&mut block);
+        expect![[r#"
+                68 84 5f 13 2e e4 61 60 66 c7 02 d9 42 a3 b2 c3
+                a3 77 f6 5b 13 bb 05 c7 cd 1f b2 9c 89 af a1 85"#]]
+        .assert_eq(&hex_fmt(&block));
+    }
+
+    #[test]
+    fn areion256_em_test_vector() {
+        let cipher = Areion256Em::new(&GenericArray::from([0x0f; 32]));
+        let mut block = GenericArray::from([0xf0; 32]);
+        cipher.encrypt_block(&mut block);
+        expect![[r#"
+            74 68 7b 2f 66 cd 11 3b 81 b7 d9 6b 32 41 8c 7e
+            5a b7 e7 47 49 c4 1d e3 da 58 59 ce 73 1f 67 3f"#]]
+        .assert_eq(&hex_fmt(&block));
+    }
+
+    #[test]
+    fn areion512_em_test_vector() {
+        let cipher = Areion512Em::new(&GenericArray::from([0x0f; 64]));
+        let mut block = GenericArray::from([0xf0; 64]);
+        cipher.encrypt_block(&mut block);
+        expect![[r#"
+            8e 1e 10 e4 13 28 60 0e 30 b7 c6 a5 54 77 74 29
+            f3 7a b1 17 5e d0 bf 7d c9 f4 52 bf d5 ee 55 b5
+            e5 8f 7d 32 e3 61 f5 4d ff 31 5a 1a 48 41 95 ff
+            98 27 2b ca 03 4b fe 8d eb 60 0e ea e8 a7 45 2f"#]]
+        .assert_eq(&hex_fmt(&block));
+    }
+
+    #[test]
+    fn multiple_blocks() {
+        let cipher = Areion512Em::new(&GenericArray::from([0x0f; 64]));
+        let mut blocks = [GenericArray::from([1; 64]), GenericArray::from([2; 64])];
+        cipher.encrypt_blocks(&mut blocks);
+        let mut single = GenericArray::from([2; 64]);
+        cipher.encrypt_block(&mut single);
+        assert_eq!(blocks[1], single);
+
+        cipher.decrypt_blocks(&mut blocks);
+        assert_eq!(blocks, [GenericArray::from([1; 64]), GenericArray::from([2; 64])]);
+    }
+
+    #[quickcheck]
+    fn areion256_em_round_trip(key: Vec<u8>, block: Vec<u8>) -> bool {
+        let (mut key, mut block) = (key, block);
+        key.resize(32, 0);
+        block.resize(32, 0);
+        let cipher = Areion256Em::new_from_slice(&key).expect("should be a valid key");
+        let mut x = GenericArray::clone_from_slice(&block);
+        cipher.encrypt_block(&mut x);
+        let encrypted = x;
+        cipher.decrypt_block(&mut x);
+        encrypted.as_slice() != block && x.as_slice() == block
+    }
+
+    #[quickcheck]
+    fn areion512_em_round_trip(key: Vec<u8>, block: Vec<u8>) -> bool {
+        let (mut key, mut block) = (key, block);
+        key.resize(64, 0);
+        block.resize(64, 0);
+        let cipher = Areion512Em::new_from_slice(&key).expect("should be a valid key");
+        let mut x = GenericArray::clone_from_slice(&block);
+        cipher.encrypt_block(&mut x);
+        let encrypted = x;
+        cipher.decrypt_block(&mut x);
+        encrypted.as_slice() != block && x.as_slice() == block
+    }
+
+    #[quickcheck]
+    fn different_keys_yield_different_ciphertexts(a: Vec<u8>, b: Vec<u8>) -> bool {
+        let (mut a, mut b) = (a, b);
+        a.resize(64, 0);
+        b.resize(64, 0);
+        let mut x = GenericArray::from([0u8; 64]);
+        let mut y = GenericArray::from([0u8; 64]);
+        Areion512Em::new_from_slice(&a).expect("should be a valid key").encrypt_block(&mut x);
+        Areion512Em::new_from_slice(&b).expect("should be a valid key").encrypt_block(&mut y);
+        x == y || a != b
+    }
+}
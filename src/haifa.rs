@@ -5,14 +5,17 @@ use crate::intrinsics::*;
 use digest::block_buffer::Lazy;
 use digest::core_api::{
     Block, BlockSizeUser, Buffer, BufferKindUser, CoreWrapper, CtVariableCoreWrapper,
-    RtVariableCoreWrapper, TruncSide, UpdateCore, VariableOutputCore,
+    FixedOutputCore, RtVariableCoreWrapper, TruncSide, UpdateCore, VariableOutputCore,
 };
-use digest::crypto_common::AlgorithmName;
+use digest::crypto_common::{AlgorithmName, InvalidLength, Key, KeyInit, KeySizeUser};
 use digest::generic_array::GenericArray;
-use digest::typenum::{Unsigned, U64};
-use digest::{HashMarker, Output, OutputSizeUser, Reset};
+use digest::typenum::{Unsigned, U32, U64};
+use digest::{HashMarker, MacMarker, Output, OutputSizeUser, Reset};
 use hex_literal::hex;
 
+/// The flag XORed into the tweak for the keyed output transform.
+const FINAL: u128 = 0x80 << 120;
+
 #[derive(Debug, Clone)]
 struct State {
     /// The 512-bit hash state.
@@ -44,9 +47,58 @@ impl State {
             m_len: 0,
         }
     }
+
+    /// Creates a keyed state by XORing the key and its length into the tweak. Because the tweak is
+    /// an input to every compression, the key is bound to every block of the message.
+    ///
+    /// The key length occupies the low bytes of `t_2`, leaving the high bytes free for `domain`.
+    fn new_keyed(output_size: usize, key: &[u8], domain: u128) -> State {
+        debug_assert!(key.len() <= 32);
+        let mut k = [0u8; 32];
+        k[..key.len()].copy_from_slice(key);
+
+        let mut state = State::new(output_size);
+        let (t0, t1, t2, t3) = state.t;
+        state.t = (
+            xor(t0, load(&k[..16])),
+            xor(t1, load(&k[16..])),
+            xor(t2, load(&(domain | key.len() as u128).to_be_bytes())),
+            t3,
+        );
+        state
+    }
+
+    fn finalize(&mut self, buffer: &mut Buffer<Core>, out: &mut [u8]) {
+        // Update the state with the compression function, using the length of the remaining data
+        // in bits to update the counter.
+        let bit_len = buffer.get_pos() as u64 * 8;
+        self.compress(&[*buffer.pad_with_zeros()], bit_len);
+
+        // Use the hash state as the digest, truncating as needed.
+        let mut tmp = [0u8; 64];
+        store(&mut tmp[..16], self.h.0);
+        store(&mut tmp[16..32], self.h.1);
+        store(&mut tmp[32..48], self.h.2);
+        store(&mut tmp[48..], self.h.3);
+        let n = out.len();
+        out.copy_from_slice(&tmp[..n]);
+    }
 }
 
 impl State {
+    /// Writes `P(h ^ t ^ f) ^ h` to `out`, where `h` is the final chaining value of a keyed state
+    /// and `f` is a final-output flag in the high byte of `t_3`.
+    ///
+    /// Without this, a tag would be a raw chaining value, and the tags of related messages would
+    /// satisfy the linear relation `tag(m1' || m2 ^ h1 ^ h1') = tag(m1 || m2) ^ h1 ^ h1'`.
+    fn output_transform(&self, out: &mut [u8]) {
+        let (h0, h1, h2, h3) = self.h;
+        let (t0, t1, t2, t3) = self.t;
+        let t3 = xor(t3, load(&FINAL.to_be_bytes()));
+        let (y0, y1, y2, y3) = crate::areion512(xor(h0, t0), xor(h1, t1), xor(h2, t2), xor(h3, t3));
+        store4(out, (xor(y0, h0), xor(y1, h1), xor(y2, h2), xor(y3, h3)));
+    }
+
     fn compress(&mut self, blocks: &[GenericArray<u8, U64>], bit_len: u64) {
         let Self { h: (mut h0, mut h1, mut h2, mut h3), t: (t0, t1, t2, t3), mut m_len } = *self;
 
@@ -124,19 +176,7 @@ impl VariableOutputCore for Core {
     }
 
     fn finalize_variable_core(&mut self, buffer: &mut Buffer<Self>, out: &mut Output<Self>) {
-        // Update the state with the compression function, using the length of the remaining data
-        // in bits to update the counter.
-        let bit_len = buffer.get_pos() as u64 * 8;
-        self.state.compress(&[*buffer.pad_with_zeros()], bit_len);
-
-        // Use the hash state as the digest, truncating as needed.
-        let mut tmp = [0u8; 64];
-        store(&mut tmp[..16], self.state.h.0);
-        store(&mut tmp[16..32], self.state.h.1);
-        store(&mut tmp[32..48], self.state.h.2);
-        store(&mut tmp[48..], self.state.h.3);
-        let n = out.len();
-        out.copy_from_slice(&tmp[..n]);
+        self.state.finalize(buffer, out);
     }
 }
 
@@ -154,17 +194,112 @@ impl AlgorithmName for Core {
     }
 }
 
+/// The core of [`AreionHaifaMac`].
+#[derive(Clone)]
+pub struct MacCore {
+    state: State,
+    init: State,
+}
+
+impl MacCore {
+    /// Creates a keyed core with a domain separation value XORed into the high bytes of the key
+    /// length field of the tweak.
+    pub(crate) fn new_with_domain(key: &[u8], domain: u128) -> MacCore {
+        debug_assert!(key.len() <= 32 && domain as u8 == 0);
+        let state = State::new_keyed(U64::USIZE, key, domain);
+        MacCore { init: state.clone(), state }
+    }
+}
+
+impl MacMarker for MacCore {}
+
+impl BlockSizeUser for MacCore {
+    type BlockSize = U64;
+}
+
+impl BufferKindUser for MacCore {
+    type BufferKind = Lazy;
+}
+
+impl OutputSizeUser for MacCore {
+    type OutputSize = U64;
+}
+
+impl KeySizeUser for MacCore {
+    type KeySize = U32;
+}
+
+impl KeyInit for MacCore {
+    fn new(key: &Key<Self>) -> Self {
+        let state = State::new_keyed(U64::USIZE, key, 0);
+        MacCore { init: state.clone(), state }
+    }
+
+    /// Accepts keys of up to 32 bytes. Keys of different lengths produce unrelated outputs.
+    fn new_from_slice(key: &[u8]) -> Result<Self, InvalidLength> {
+        if key.len() > Self::KeySize::USIZE {
+            return Err(InvalidLength);
+        }
+        let state = State::new_keyed(U64::USIZE, key, 0);
+        Ok(MacCore { init: state.clone(), state })
+    }
+}
+
+impl UpdateCore for MacCore {
+    #[inline]
+    fn update_blocks(&mut self, blocks: &[Block<Self>]) {
+        self.state.compress(blocks, Self::BlockSize::U64 * 8);
+    }
+}
+
+impl FixedOutputCore for MacCore {
+    fn finalize_fixed_core(&mut self, buffer: &mut Buffer<Self>, out: &mut Output<Self>) {
+        self.state.finalize(buffer, out);
+        self.state.output_transform(out);
+    }
+}
+
+impl Reset for MacCore {
+    #[inline]
+    fn reset(&mut self) {
+        self.state = self.init.clone();
+    }
+}
+
+impl AlgorithmName for MacCore {
+    #[inline]
+    fn write_alg_name(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-HAIFA-MAC")
+    }
+}
+
+impl fmt::Debug for MacCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-HAIFA-MAC { ... }")
+    }
+}
+
 pub type AreionHaifaVar = RtVariableCoreWrapper<Core>;
 pub type AreionHaifaCore<OutSize> = CtVariableCoreWrapper<Core, OutSize>;
 pub type AreionHaifa<OutSize> = CoreWrapper<AreionHaifaCore<OutSize>>;
 pub type AreionHaifa512 = AreionHaifa<U64>;
 
+/// A keyed Areion512-HAIFA PRF with keys of up to 256 bits and 512-bit outputs.
+///
+/// The key is XORed into the HAIFA tweak, which is an input to every compression function call.
+/// The final chaining value `h` isn't released directly: the tag is `P(h ^ t ^ f) ^ h`, where `t`
+/// is the keyed tweak and `f` is a final-output flag, so tags can't be extended or combined
+/// linearly with the chaining values of other messages.
+pub type AreionHaifaMac = CoreWrapper<MacCore>;
+
 #[cfg(test)]
 mod tests {
-    use digest::Digest;
+    use digest::{Digest, Mac};
+    use expect_test::expect;
     use quickcheck_macros::quickcheck;
 
     use super::*;
+    use crate::tests::hex_fmt;
 
     #[test]
     fn round_trip() {
@@ -178,6 +313,58 @@ mod tests {
         aa == bb || a != b
     }
 
+    fn mac(key: &[u8]) -> AreionHaifaMac {
+        <AreionHaifaMac as Mac>::new_from_slice(key).expect("should be a valid key")
+    }
+
+    #[test]
+    fn mac_test_vector() {
+        let mac = mac(b"this is a key").chain_update(b"this is a message").finalize();
+        expect![[r#"
+            fb d9 9b 57 b4 c2 bc 8d 60 7d 10 48 b3 7b 30 3f
+            fa 72 b5 13 b3 23 aa 7e 97 46 61 3e fe 8a e3 1b
+            cf e8 dc 9c 37 d2 17 0b a1 db ac 96 52 46 89 04
+            bd 5e 13 bc 1c ce 99 c4 89 73 21 96 6a d1 9c c0"#]]
+        .assert_eq(&hex_fmt(&mac.into_bytes()));
+    }
+
+    #[test]
+    fn mac_keys() {
+        let tag = |key: &[u8]| mac(key).chain_update(b"message").finalize().into_bytes();
+        let unkeyed = AreionHaifa512::new().chain_update(b"message").finalize();
+        assert_ne!(tag(b""), unkeyed);
+        assert_ne!(tag(b"a"), tag(b"a\0"));
+        assert_ne!(tag(b"a"), tag(b"b"));
+        assert!(<AreionHaifaMac as Mac>::new_from_slice(&[0; 33]).is_err());
+    }
+
+    #[test]
+    fn no_linear_forgery() {
+        // With raw chaining values as tags, tag(m1' || m2 ^ h1 ^ h1') = tag(m1 || m2) ^ h1 ^ h1'
+        // where h1 = tag(m1) and h1' = tag(m1') for 64-byte blocks m1 and m1'.
+        let tag = |m: &[u8]| mac(b"key").chain_update(m).finalize().into_bytes();
+        let (m1, m1_p, m2) = ([1u8; 64], [2u8; 64], [3u8; 64]);
+        let (h1, h1_p) = (tag(&m1), tag(&m1_p));
+        let diff = h1.iter().zip(h1_p).map(|(a, b)| a ^ b).collect::<Vec<u8>>();
+
+        let mut m = m1.to_vec();
+        m.extend_from_slice(&m2);
+        let mut forged = m1_p.to_vec();
+        forged.extend(m2.iter().zip(&diff).map(|(a, b)| a ^ b));
+
+        let predicted = tag(&m).iter().zip(&diff).map(|(a, b)| a ^ b).collect::<Vec<u8>>();
+        assert_ne!(tag(&forged).as_slice(), predicted);
+    }
+
+    #[quickcheck]
+    fn mac_verifies(key: Vec<u8>, msg: Vec<u8>) -> bool {
+        let mut key = key;
+        key.truncate(32);
+        let mac = mac(&key);
+        let tag = mac.clone().chain_update(&msg).finalize().into_bytes();
+        mac.chain_update(&msg).verify_slice(&tag).is_ok()
+    }
+
     #[test]
     fn fuzz() {
         bolero::check!().with_type::<Vec<u8>>().for_each(|input| {
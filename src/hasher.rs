@@ -0,0 +1,361 @@
+use core::fmt;
+use core::hash::{BuildHasher, Hasher};
+
+use crate::intrinsics::*;
+use crate::mask::Mask256;
+
+use rand_core::CryptoRngCore;
+
+const BLOCK_LEN: usize = 32;
+
+/// AreionHasher, a keyed hash function for hash tables built on the Areion256 Even-Mansour cipher
+/// `E_K(X) = P(X ^ K) ^ K` with 256-bit keys.
+///
+/// Input is processed in 32-byte blocks with CBC-MAC, and the final block is masked in the style of
+/// [CMAC](https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-38b.pdf) with
+/// `L = E_K(0)`:
+///
+/// ```text
+/// A_i = E_K(A_{i-1} ^ M_i)
+/// T   = E_K(A_{m-1} ^ M_m ^ φ(L))          if |M_m| = 256
+/// T   = E_K(A_{m-1} ^ M_m || 10* ^ φ^2(L)) otherwise
+/// ```
+///
+/// The first 64 bits of `T` are the hash. Inputs of up to 32 bytes, including any single integer,
+/// require only a single permutation call. Because the hash is a PRF of the input, an attacker who
+/// doesn't know the key can't construct colliding inputs to flood a hash table.
+#[derive(Clone)]
+pub struct AreionHasher {
+    k: (AesBlock, AesBlock),
+    masks: [(AesBlock, AesBlock); 2],
+    acc: (AesBlock, AesBlock),
+    // The buffered block is kept as two little-endian integers rather than bytes, so it can stay in
+    // registers instead of stalling on store forwarding when a few bytes are written and the block
+    // is then loaded. The buffer is always zero past the last byte written.
+    buf: (u128, u128),
+    pos: usize,
+}
+
+impl AreionHasher {
+    /// Creates a hasher with the given key.
+    pub fn new_with_key(key: &[u8; 32]) -> AreionHasher {
+        AreionBuildHasher::new_with_key(key).build_hasher()
+    }
+
+    #[inline]
+    fn e(&self, x0: AesBlock, x1: AesBlock) -> (AesBlock, AesBlock) {
+        let (k0, k1) = self.k;
+        let (y0, y1) = crate::areion256(xor(x0, k0), xor(x1, k1));
+        (xor(y0, k0), xor(y1, k1))
+    }
+
+    /// Appends the low `n` bytes of `x` to the buffered block, which must have room for them.
+    #[inline]
+    fn push(&mut self, x: u64, n: usize) {
+        debug_assert!(n <= 8 && self.pos + n <= BLOCK_LEN);
+        or_at(&mut self.buf, x, self.pos);
+        self.pos += n;
+    }
+
+    /// Appends up to 32 bytes to the buffered block, which must have room for them.
+    #[inline]
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in chunks.by_ref() {
+            self.push(u64::from_le_bytes(chunk.try_into().expect("should be 8 bytes")), 8);
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            self.push(read_small(rest), rest.len());
+        }
+    }
+
+    #[inline(never)]
+    fn write_blocks(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            // Only compress a full block once more input arrives, so the final block is always
+            // processed by finish.
+            if self.pos == BLOCK_LEN {
+                self.compress();
+            }
+            let n = bytes.len().min(BLOCK_LEN - self.pos);
+            self.push_bytes(&bytes[..n]);
+            bytes = &bytes[n..];
+        }
+    }
+
+    #[inline]
+    fn compress(&mut self) {
+        let (a0, a1) = self.acc;
+        let (b0, b1) = self.buf;
+        self.acc = self.e(xor(a0, from_u128(b0)), xor(a1, from_u128(b1)));
+        self.buf = (0, 0);
+        self.pos = 0;
+    }
+}
+
+impl Hasher for AreionHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        let mut buf = self.buf;
+        let (m0, m1) = if self.pos == BLOCK_LEN {
+            self.masks[0]
+        } else {
+            or_at(&mut buf, 0x80, self.pos);
+            self.masks[1]
+        };
+
+        let (a0, a1) = self.acc;
+        let (t0, _) = self.e(xor3(a0, from_u128(buf.0), m0), xor3(a1, from_u128(buf.1), m1));
+        low_u64(t0)
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        if self.pos + bytes.len() <= BLOCK_LEN {
+            self.push_bytes(bytes);
+        } else {
+            self.write_blocks(bytes);
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        if self.pos < BLOCK_LEN {
+            self.push(i.into(), 1);
+        } else {
+            self.write(&[i]);
+        }
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        if self.pos + 4 <= BLOCK_LEN {
+            self.push(i.into(), 4);
+        } else {
+            self.write(&i.to_le_bytes());
+        }
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        if self.pos + 8 <= BLOCK_LEN {
+            self.push(i, 8);
+        } else {
+            self.write(&i.to_le_bytes());
+        }
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// ORs the word `x` into the 256-bit little-endian integer `buf` at byte offset `pos`.
+#[inline]
+fn or_at(buf: &mut (u128, u128), x: u64, pos: usize) {
+    let (x, shift) = (u128::from(x), pos * 8);
+    if shift < 128 {
+        buf.0 |= x << shift;
+        if shift > 64 {
+            buf.1 |= x >> (128 - shift);
+        }
+    } else {
+        buf.1 |= x << (shift - 128);
+    }
+}
+
+#[inline]
+fn from_u128(x: u128) -> AesBlock {
+    from_u64(x as u64, (x >> 64) as u64)
+}
+
+/// Reads between 1 and 7 bytes as a little-endian word with overlapping loads.
+#[inline]
+fn read_small(b: &[u8]) -> u64 {
+    let n = b.len();
+    debug_assert!(0 < n && n < 8);
+    if n >= 4 {
+        let lo = u32::from_le_bytes(b[..4].try_into().expect("should be 4 bytes"));
+        let hi = u32::from_le_bytes(b[n - 4..].try_into().expect("should be 4 bytes"));
+        u64::from(lo) | u64::from(hi) << ((n - 4) * 8)
+    } else {
+        u64::from(b[0]) | u64::from(b[n / 2]) << (n / 2 * 8) | u64::from(b[n - 1]) << ((n - 1) * 8)
+    }
+}
+
+impl fmt::Debug for AreionHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AreionHasher { ... }")
+    }
+}
+
+/// A [`BuildHasher`] for [`AreionHasher`].
+///
+/// With the `std` feature, [`AreionBuildHasher::new`] generates a random key for each instance from
+/// a thread-local [`AreionRng`](crate::AreionRng) seeded by the operating system, so each hash
+/// table can have its own key without a system call.
+#[derive(Clone)]
+pub struct AreionBuildHasher {
+    k: (AesBlock, AesBlock),
+    masks: [(AesBlock, AesBlock); 2],
+}
+
+impl AreionBuildHasher {
+    /// Creates a hasher builder with a random key.
+    #[cfg(feature = "std")]
+    pub fn new() -> AreionBuildHasher {
+        use std::cell::RefCell;
+
+        use rand_core::{OsRng, RngCore, SeedableRng};
+
+        use crate::AreionRng;
+
+        thread_local! {
+            static RNG: RefCell<AreionRng> =
+                RefCell::new(AreionRng::from_rng(OsRng).expect("should seed from the OS"));
+        }
+
+        let mut key = [0u8; 32];
+        RNG.with(|rng| rng.borrow_mut().fill_bytes(&mut key));
+        AreionBuildHasher::new_with_key(&key)
+    }
+
+    /// Creates a hasher builder with a key generated by the given RNG.
+    pub fn from_rng(mut rng: impl CryptoRngCore) -> AreionBuildHasher {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        AreionBuildHasher::new_with_key(&key)
+    }
+
+    /// Creates a hasher builder with the given key.
+    pub fn new_with_key(key: &[u8; 32]) -> AreionBuildHasher {
+        let (k0, k1) = (load(&key[..16]), load(&key[16..]));
+        let (l0, l1) = crate::areion256(k0, k1);
+        let mut l = [0u8; 32];
+        store(&mut l[..16], xor(l0, k0));
+        store(&mut l[16..], xor(l1, k1));
+        let l = Mask256::from_bytes(&l).phi();
+        let l2 = l.phi();
+        AreionBuildHasher {
+            k: (k0, k1),
+            masks: [(l.block(0), l.block(1)), (l2.block(0), l2.block(1))],
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for AreionBuildHasher {
+    fn default() -> Self {
+        AreionBuildHasher::new()
+    }
+}
+
+impl BuildHasher for AreionBuildHasher {
+    type Hasher = AreionHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> AreionHasher {
+        AreionHasher { k: self.k, masks: self.masks, acc: (zero(), zero()), buf: (0, 0), pos: 0 }
+    }
+}
+
+impl fmt::Debug for AreionBuildHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AreionBuildHasher { ... }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    fn hash(bytes: &[u8]) -> u64 {
+        let mut h = AreionHasher::new_with_key(&[7; 32]);
+        h.write(bytes);
+        h.finish()
+    }
+
+    #[test]
+    fn test_vector() {
+        let mut h = AreionHasher::new_with_key(&core::array::from_fn(|i| i as u8));
+        h.write(b"this is a key");
+        h.write_u64(0xdeadbeef);
+        expect!["0xd98cd4aa31d82df3"].assert_eq(&format!("{:#018x}", h.finish()));
+    }
+
+    #[test]
+    fn padding() {
+        let outputs = [
+            hash(b""),
+            hash(&[0x80]),
+            hash(&[0; 31]),
+            hash(&[0; 32]),
+            hash(&[0; 33]),
+            hash(&[[0; 31].as_slice(), &[0x80]].concat()),
+            hash(&[0; 64]),
+        ];
+        for (i, a) in outputs.iter().enumerate() {
+            assert!(!outputs[i + 1..].contains(a), "{i}");
+        }
+    }
+
+    #[quickcheck]
+    fn integer_fast_paths(prefix: Vec<u8>, a: u8, b: u32, c: u64, d: usize) -> bool {
+        let mut fast = AreionHasher::new_with_key(&[7; 32]);
+        fast.write(&prefix);
+        fast.write_u8(a);
+        fast.write_u32(b);
+        fast.write_u64(c);
+        fast.write_usize(d);
+
+        let mut slow = AreionHasher::new_with_key(&[7; 32]);
+        slow.write(&prefix);
+        slow.write(&[a]);
+        slow.write(&b.to_le_bytes());
+        slow.write(&c.to_le_bytes());
+        slow.write(&(d as u64).to_le_bytes());
+
+        fast.finish() == slow.finish()
+    }
+
+    #[quickcheck]
+    fn split_writes(a: Vec<u8>, b: Vec<u8>) -> bool {
+        let mut h = AreionHasher::new_with_key(&[7; 32]);
+        h.write(&a);
+        h.write(&b);
+        h.finish() == hash(&[a, b].concat())
+    }
+
+    #[quickcheck]
+    fn different_keys_yield_different_hashes(a: u64, b: u64) -> bool {
+        let hash_with = |k: u64| {
+            let mut key = [0u8; 32];
+            key[..8].copy_from_slice(&k.to_le_bytes());
+            AreionBuildHasher::new_with_key(&key).hash_one(b"input")
+        };
+        (hash_with(a) != hash_with(b)) == (a != b)
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn random_keys() {
+        let (a, b) = (AreionBuildHasher::new(), AreionBuildHasher::new());
+        assert_ne!(a.hash_one(1u64), b.hash_one(1u64));
+        assert_eq!(a.hash_one(1u64), a.hash_one(1u64));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hash_map() {
+        let mut map = std::collections::HashMap::with_hasher(AreionBuildHasher::new());
+        for i in 0..1000u64 {
+            map.insert(i, i * 2);
+        }
+        assert!((0..1000u64).all(|i| map.get(&i) == Some(&(i * 2))));
+    }
+}
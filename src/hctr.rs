@@ -0,0 +1,264 @@
+use core::fmt;
+
+use crate::intrinsics::*;
+use crate::{Areion512Em, Areion512Tem};
+
+use cipher::consts::U32;
+use cipher::{AlgorithmName, InvalidLength, Key, KeyInit, KeySizeUser};
+
+const BLOCK_LEN: usize = 64;
+
+/// Areion512-HCTR, a tweakable wide-block cipher in the style of
+/// [HCTR2](https://eprint.iacr.org/2021/1441.pdf) with 256-bit keys and tweaks.
+///
+/// Buffers of at least 64 bytes are encrypted in place without expansion, such that changing any
+/// bit of the plaintext or tweak changes the entire ciphertext. The buffer is split into a 512-bit
+/// block `L` and the remainder `R`, which are encrypted with the Areion512-EM cipher `E`, an XCTR
+/// keystream over `E`, and a keyed hash `H` of the tweak and `R`:
+///
+/// ```text
+/// MM = L ^ H(T, R)
+/// UU = E(MM)
+/// V  = R ^ XCTR(MM ^ UU)
+/// U  = UU ^ H(T, V)
+/// ```
+///
+/// `H` is a PMAC-style sum of Areion512-TEM blocks keyed with the tweak, finalized with a mask
+/// which distinguishes padded and unpadded inputs.
+#[derive(Clone)]
+pub struct Areion512Hctr {
+    em: Areion512Em,
+    h: Areion512Tem,
+}
+
+impl Areion512Hctr {
+    /// Encrypts the given buffer in place under the given tweak.
+    ///
+    /// Returns an error if the buffer is shorter than 64 bytes.
+    pub fn encrypt(&self, tweak: &[u8; 32], buf: &mut [u8]) -> Result<(), InvalidLength> {
+        if buf.len() < BLOCK_LEN {
+            return Err(InvalidLength);
+        }
+        let (l, r) = buf.split_at_mut(BLOCK_LEN);
+
+        let mm = xor4(load4(l), self.hash(tweak, r));
+        let uu = self.em.encrypt_words(mm.0, mm.1, mm.2, mm.3);
+        self.xctr(xor4(mm, uu), r);
+        store4(l, xor4(uu, self.hash(tweak, r)));
+
+        Ok(())
+    }
+
+    /// Decrypts the given buffer in place under the given tweak.
+    ///
+    /// Returns an error if the buffer is shorter than 64 bytes.
+    pub fn decrypt(&self, tweak: &[u8; 32], buf: &mut [u8]) -> Result<(), InvalidLength> {
+        if buf.len() < BLOCK_LEN {
+            return Err(InvalidLength);
+        }
+        let (u, v) = buf.split_at_mut(BLOCK_LEN);
+
+        let uu = xor4(load4(u), self.hash(tweak, v));
+        let mm = self.em.decrypt_words(uu.0, uu.1, uu.2, uu.3);
+        self.xctr(xor4(mm, uu), v);
+        store4(u, xor4(mm, self.hash(tweak, v)));
+
+        Ok(())
+    }
+
+    fn hash(&self, tweak: &[u8; 32], r: &[u8]) -> AesBlock4 {
+        let mut delta = self.h.mask(tweak);
+        let mut acc = (zero(), zero(), zero(), zero());
+
+        let mut blocks = r.chunks_exact(BLOCK_LEN);
+        for block in blocks.by_ref() {
+            acc = xor4(acc, tem(load4(block), &delta));
+            delta = delta.phi();
+        }
+
+        let tail = blocks.remainder();
+        let delta = if tail.is_empty() {
+            delta.phi_1()
+        } else {
+            let mut padded = [0u8; BLOCK_LEN];
+            padded[..tail.len()].copy_from_slice(tail);
+            padded[tail.len()] = 0x80;
+            acc = xor4(acc, tem(load4(&padded), &delta));
+            delta.phi_2()
+        };
+
+        tem(acc, &delta)
+    }
+
+    fn xctr(&self, s: AesBlock4, r: &mut [u8]) {
+        for (i, chunk) in r.chunks_mut(BLOCK_LEN).enumerate() {
+            let mut ctr = [0u8; 16];
+            ctr[..8].copy_from_slice(&(i as u64 + 1).to_le_bytes());
+            let (x0, x1, x2, x3) = self.em.encrypt_words(xor(s.0, load(&ctr)), s.1, s.2, s.3);
+
+            let mut ks = [0u8; BLOCK_LEN];
+            store4(&mut ks, (x0, x1, x2, x3));
+            for (b, k) in chunk.iter_mut().zip(ks) {
+                *b ^= k;
+            }
+        }
+    }
+}
+
+impl KeySizeUser for Areion512Hctr {
+    type KeySize = U32;
+}
+
+impl KeyInit for Areion512Hctr {
+    fn new(key: &Key<Self>) -> Self {
+        // Derive independent cipher and hash keys from the key with domain-separated permutation
+        // calls.
+        let (k0, k1) = (load(&key[..16]), load(&key[16..]));
+        let mut ke = [0u8; 64];
+        store4(&mut ke, crate::areion512(k0, k1, zero(), zero()));
+        let mut kh = [0u8; 64];
+        store4(&mut kh, crate::areion512(k0, k1, zero(), load(&[1; 16])));
+
+        Areion512Hctr {
+            em: Areion512Em::new(&ke.into()),
+            h: Areion512Tem::new_from_slice(&kh[..32]).expect("should be a valid key"),
+        }
+    }
+}
+
+impl AlgorithmName for Areion512Hctr {
+    fn write_alg_name(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-HCTR")
+    }
+}
+
+impl fmt::Debug for Areion512Hctr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-HCTR { ... }")
+    }
+}
+
+#[inline]
+fn tem(x: AesBlock4, delta: &crate::mask::Mask512) -> AesBlock4 {
+    let d = delta.blocks();
+    let x = xor4(x, d);
+    xor4(crate::areion512(x.0, x.1, x.2, x.3), d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_vector() {
+        let cipher = Areion512Hctr::new(&[0x0f; 32].into());
+        let mut buf = [0xf0; 100];
+        cipher.encrypt(&[0xaa; 32], &mut buf).expect("should encrypt");
+        expect![[r#"
+            73 0c 6e 77 8a 62 75 bc 4b 5f ca 04 e7 de 57 32
+            eb a6 45 fc f2 93 b7 61 f0 c8 db ab c5 69 67 ae
+            d9 a3 c9 e5 69 aa f5 a4 e0 51 db 2b 9a 76 bd b3
+            d0 a2 f9 2b f9 78 b1 72 e5 c7 86 54 69 24 c8 54
+            41 7b 1b de a0 b8 77 f7 89 1d 50 9d 8c 17 d5 ab
+            ae 43 48 0e 5b b0 c0 61 c0 e0 06 db 61 d9 3d 3e
+            f2 1f c5 a5"#]]
+        .assert_eq(&hex_fmt(&buf));
+    }
+
+    #[test]
+    fn single_bit_diffusion() {
+        let cipher = Areion512Hctr::new(&[0x0f; 32].into());
+        let mut a = [0xf0; 100];
+        cipher.encrypt(&[0xaa; 32], &mut a).expect("should encrypt");
+
+        // Flip the last bit of the plaintext.
+        let mut b = [0xf0; 100];
+        b[99] ^= 1;
+        cipher.encrypt(&[0xaa; 32], &mut b).expect("should encrypt");
+        expect![[r#"
+            73 69 8d 2f a3 e4 a5 23 15 f3 7c c3 d8 7c c2 56
+            ba 62 00 8d ed 0c dd 93 14 aa 8e 9c c9 aa 0d 02
+            0d 7e 1f bb f4 8d f3 17 46 f1 56 ac 7a d7 c8 b1
+            a6 14 7f 62 d1 96 72 dc 7f e4 26 97 53 b2 7b 17
+            3c 72 b6 32 46 8b 5d 0b 61 d2 c9 84 5b d1 dc ff
+            1c 9d 8f ed 1b a1 31 85 43 13 85 ad 93 1f 8d 6d
+            fa 78 b3 fb"#]]
+        .assert_eq(&hex_fmt(&b));
+
+        // Flip the first bit of the plaintext.
+        let mut c = [0xf0; 100];
+        c[0] ^= 0x80;
+        cipher.encrypt(&[0xaa; 32], &mut c).expect("should encrypt");
+        expect![[r#"
+            a1 0a f8 ae 3e d7 1d 6b 0b 0d 26 1d 0a 0f b7 5c
+            d3 3c b2 fe 53 a8 79 06 ab cf 63 cd 77 1d 98 b5
+            8e 86 0a 4a 6c 8f 7a c6 7c 1a b1 26 0a a3 04 e2
+            60 44 00 95 55 eb 70 bc e8 0f 0e 73 55 14 37 a1
+            a1 af e6 95 a2 18 cf 38 88 12 71 33 09 66 d2 1b
+            4a 2c e3 92 02 22 81 f0 6e 82 34 ab a5 1a c5 b8
+            dd 14 9e 3b"#]]
+        .assert_eq(&hex_fmt(&c));
+
+        // Flip a bit of the tweak.
+        let mut d = [0xf0; 100];
+        let mut tweak = [0xaa; 32];
+        tweak[31] ^= 1;
+        cipher.encrypt(&tweak, &mut d).expect("should encrypt");
+        expect![[r#"
+            82 5a be 07 ab 44 f3 c6 21 24 51 7d 69 86 2d d1
+            c4 60 66 a0 51 37 8f c5 a9 46 6b 4d ae fa df ee
+            82 0d 8e d4 1d 77 39 bf d9 e8 c0 a6 f7 91 02 c0
+            38 27 5b 38 06 40 9f b0 42 4c c5 52 04 e6 1d 8d
+            9a 9a b1 c2 69 1f 8c 87 13 eb 4e b7 39 dc 5b 30
+            52 3b 03 56 22 ae 4e 0f b7 dc 1b 07 8d 17 d0 42
+            d4 16 cc 74"#]]
+        .assert_eq(&hex_fmt(&d));
+
+        for x in [b, c, d] {
+            assert!(a.chunks(16).zip(x.chunks(16)).all(|(a, x)| a != x));
+        }
+    }
+
+    #[test]
+    fn short_buffers() {
+        let cipher = Areion512Hctr::new(&[0x0f; 32].into());
+        assert!(cipher.encrypt(&[0; 32], &mut [0; 63]).is_err());
+        assert!(cipher.decrypt(&[0; 32], &mut [0; 63]).is_err());
+    }
+
+    #[quickcheck]
+    fn round_trip(key: Vec<u8>, tweak: Vec<u8>, pt: Vec<u8>) -> bool {
+        let (mut key, mut tweak, mut pt) = (key, tweak, pt);
+        key.resize(32, 0);
+        tweak.resize(32, 0);
+        pt.resize(pt.len().max(BLOCK_LEN), 0);
+        let cipher = Areion512Hctr::new_from_slice(&key).expect("should be a valid key");
+        let tweak = tweak.try_into().expect("should be 32 bytes");
+
+        let mut ct = pt.clone();
+        cipher.encrypt(&tweak, &mut ct).expect("should encrypt");
+        let encrypted = ct != pt;
+        cipher.decrypt(&tweak, &mut ct).expect("should decrypt");
+        encrypted && ct == pt
+    }
+
+    #[quickcheck]
+    fn bit_flips_change_every_block(pt: Vec<u8>, idx: usize, bit: u8) -> bool {
+        let mut pt = pt;
+        pt.resize(pt.len().max(BLOCK_LEN), 0);
+        let cipher = Areion512Hctr::new(&[0x0f; 32].into());
+
+        let mut a = pt.clone();
+        cipher.encrypt(&[0; 32], &mut a).expect("should encrypt");
+        let mut b = pt;
+        b[idx % a.len()] ^= 1 << (bit % 8);
+        cipher.encrypt(&[0; 32], &mut b).expect("should encrypt");
+
+        a.chunks_exact(16).zip(b.chunks_exact(16)).all(|(a, b)| a != b)
+    }
+}
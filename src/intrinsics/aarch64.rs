@@ -28,6 +28,21 @@ pub fn store_u32(bytes: &mut [u32], block: AesBlock) {
     unsafe { vst1q_u32(bytes.as_mut_ptr(), vreinterpretq_u32_u8(block)) };
 }
 
+#[inline]
+pub fn from_u64(lo: u64, hi: u64) -> AesBlock {
+    unsafe { vreinterpretq_u8_u64(vcombine_u64(vcreate_u64(lo), vcreate_u64(hi))) }
+}
+
+#[inline]
+pub fn low_u64(block: AesBlock) -> u64 {
+    unsafe { vgetq_lane_u64::<0>(vreinterpretq_u64_u8(block)) }
+}
+
+#[inline]
+pub fn high_u64(block: AesBlock) -> u64 {
+    unsafe { vgetq_lane_u64::<1>(vreinterpretq_u64_u8(block)) }
+}
+
 #[inline]
 pub fn xor(a: AesBlock, b: AesBlock) -> AesBlock {
     unsafe { veorq_u8(a, b) }
@@ -9,3 +9,24 @@ pub mod x86_64;
 
 #[cfg(target_arch = "x86_64")]
 pub use crate::x86_64::*;
+
+/// Four AES blocks, the state of the Areion512 permutation.
+pub type AesBlock4 = (AesBlock, AesBlock, AesBlock, AesBlock);
+
+#[inline]
+pub fn load4(bytes: &[u8]) -> AesBlock4 {
+    (load(&bytes[..16]), load(&bytes[16..32]), load(&bytes[32..48]), load(&bytes[48..64]))
+}
+
+#[inline]
+pub fn store4(bytes: &mut [u8], x: AesBlock4) {
+    store(&mut bytes[..16], x.0);
+    store(&mut bytes[16..32], x.1);
+    store(&mut bytes[32..48], x.2);
+    store(&mut bytes[48..64], x.3);
+}
+
+#[inline]
+pub fn xor4(a: AesBlock4, b: AesBlock4) -> AesBlock4 {
+    (xor(a.0, b.0), xor(a.1, b.1), xor(a.2, b.2), xor(a.3, b.3))
+}
@@ -27,6 +27,21 @@ pub fn store_u32(bytes: &mut [u32], block: AesBlock) {
     unsafe { _mm_storeu_si128(bytes.as_mut_ptr() as *mut __m128i, block) };
 }
 
+#[inline]
+pub fn from_u64(lo: u64, hi: u64) -> AesBlock {
+    unsafe { _mm_set_epi64x(hi as i64, lo as i64) }
+}
+
+#[inline]
+pub fn low_u64(block: AesBlock) -> u64 {
+    unsafe { _mm_cvtsi128_si64(block) as u64 }
+}
+
+#[inline]
+pub fn high_u64(block: AesBlock) -> u64 {
+    unsafe { _mm_cvtsi128_si64(_mm_unpackhi_epi64(block, block)) as u64 }
+}
+
 #[inline]
 pub fn xor(a: AesBlock, b: AesBlock) -> AesBlock {
     unsafe { _mm_xor_si128(a, b) }
@@ -56,3 +71,37 @@ pub fn dec_last(state: AesBlock, round_key: AesBlock) -> AesBlock {
 pub fn inv_mix(state: AesBlock) -> AesBlock {
     unsafe { _mm_aesimc_si128(state) }
 }
+
+/// Two AES blocks in a single 256-bit register, for processing two independent states with VAES.
+#[cfg(all(target_feature = "avx2", target_feature = "vaes"))]
+pub type AesBlock2 = __m256i;
+
+#[cfg(all(target_feature = "avx2", target_feature = "vaes"))]
+#[inline]
+pub fn join(lo: AesBlock, hi: AesBlock) -> AesBlock2 {
+    unsafe { _mm256_set_m128i(hi, lo) }
+}
+
+#[cfg(all(target_feature = "avx2", target_feature = "vaes"))]
+#[inline]
+pub fn split(x: AesBlock2) -> (AesBlock, AesBlock) {
+    unsafe { (_mm256_castsi256_si128(x), _mm256_extracti128_si256::<1>(x)) }
+}
+
+#[cfg(all(target_feature = "avx2", target_feature = "vaes"))]
+#[inline]
+pub fn broadcast(x: AesBlock) -> AesBlock2 {
+    unsafe { _mm256_broadcastsi128_si256(x) }
+}
+
+#[cfg(all(target_feature = "avx2", target_feature = "vaes"))]
+#[inline]
+pub fn enc2(state: AesBlock2, round_key: AesBlock2) -> AesBlock2 {
+    unsafe { _mm256_aesenc_epi128(state, round_key) }
+}
+
+#[cfg(all(target_feature = "avx2", target_feature = "vaes"))]
+#[inline]
+pub fn enc_last2(state: AesBlock2, round_key: AesBlock2) -> AesBlock2 {
+    unsafe { _mm256_aesenclast_epi128(state, round_key) }
+}
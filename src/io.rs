@@ -0,0 +1,135 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use digest::{Digest, Output, Update};
+
+/// The size of the buffer used to read data into hashers.
+const BUF_LEN: usize = 64 * 1024;
+
+/// Files smaller than this are read rather than memory-mapped, since the overhead of mapping them
+/// outweighs the cost of copying.
+#[cfg(feature = "mmap")]
+const MMAP_MIN_LEN: u64 = 16 * 1024;
+
+/// Updates `hasher` with the full contents of `reader`, returning the number of bytes read.
+pub fn update_reader<D: Update>(hasher: &mut D, mut reader: impl Read) -> io::Result<u64> {
+    let mut buf = vec![0u8; BUF_LEN];
+    let mut n = 0;
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(n),
+            Ok(len) => {
+                hasher.update(&buf[..len]);
+                n += len as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Returns the digest of the full contents of `reader`.
+pub fn hash_reader<D: Digest + Update>(reader: impl Read) -> io::Result<Output<D>> {
+    let mut hasher = D::new();
+    update_reader(&mut hasher, reader)?;
+    Ok(hasher.finalize())
+}
+
+/// Updates `hasher` with the contents of the file at `path`, returning the number of bytes read.
+///
+/// If the `mmap` feature is enabled, large regular files are memory-mapped instead of read.
+pub fn update_file<D: Update>(hasher: &mut D, path: impl AsRef<Path>) -> io::Result<u64> {
+    let file = File::open(path)?;
+
+    #[cfg(feature = "mmap")]
+    if let Some(map) = maybe_mmap(&file)? {
+        hasher.update(&map);
+        return Ok(map.len() as u64);
+    }
+
+    update_reader(hasher, file)
+}
+
+/// Returns the digest of the contents of the file at `path`.
+///
+/// If the `mmap` feature is enabled, large regular files are memory-mapped instead of read.
+pub fn hash_file<D: Digest + Update>(path: impl AsRef<Path>) -> io::Result<Output<D>> {
+    let mut hasher = D::new();
+    update_file(&mut hasher, path)?;
+    Ok(hasher.finalize())
+}
+
+#[cfg(feature = "mmap")]
+fn maybe_mmap(file: &File) -> io::Result<Option<memmap2::Mmap>> {
+    let metadata = file.metadata()?;
+    if !metadata.is_file() || metadata.len() < MMAP_MIN_LEN {
+        return Ok(None);
+    }
+
+    // SAFETY: The map is only read while hashing, and is dropped immediately afterward. If the
+    // file is modified concurrently, the digest may reflect a mix of old and new contents, which is
+    // no worse than reading it while it's being written.
+    Ok(unsafe { memmap2::Mmap::map(file) }.ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    use crate::{Areion512Md, AreionHaifaVar};
+
+    use digest::VariableOutput;
+
+    fn temp_file(name: &str, data: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("areion-{}-{}", std::process::id(), name));
+        File::create(&path).and_then(|mut f| f.write_all(data)).expect("should write temp file");
+        path
+    }
+
+    #[test]
+    fn digests_implement_write() {
+        let data = vec![0xcc; 3 * BUF_LEN + 17];
+        let mut hasher = Areion512Md::new();
+        io::copy(&mut data.as_slice(), &mut hasher).expect("should copy");
+        assert_eq!(hasher.finalize(), Areion512Md::digest(&data));
+    }
+
+    #[test]
+    fn reader() {
+        let data = vec![0xcc; 3 * BUF_LEN + 17];
+        let digest = hash_reader::<Areion512Md>(data.as_slice()).expect("should hash");
+        assert_eq!(digest, Areion512Md::digest(&data));
+    }
+
+    #[test]
+    fn variable_output_reader() {
+        let data = vec![0xcc; BUF_LEN + 17];
+        let mut hasher = AreionHaifaVar::new(20).expect("should be a valid output size");
+        let n = update_reader(&mut hasher, data.as_slice()).expect("should hash");
+        assert_eq!(n, data.len() as u64);
+
+        let mut expected = AreionHaifaVar::new(20).expect("should be a valid output size");
+        expected.update(&data);
+        assert_eq!(hasher.finalize_boxed(), expected.finalize_boxed());
+    }
+
+    #[test]
+    fn files() {
+        for (name, len) in [("empty", 0), ("small", 100), ("large", 3 * BUF_LEN + 17)] {
+            let data = vec![0xcc; len];
+            let path = temp_file(name, &data);
+            let digest = hash_file::<Areion512Md>(&path);
+            std::fs::remove_file(&path).expect("should remove temp file");
+            assert_eq!(digest.expect("should hash"), Areion512Md::digest(&data), "{name}");
+        }
+    }
+
+    #[test]
+    fn missing_file() {
+        let err = hash_file::<Areion512Md>("/this/does/not/exist").expect_err("should fail");
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
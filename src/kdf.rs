@@ -0,0 +1,229 @@
+use core::fmt;
+
+use crate::haifa::{AreionHaifa, MacCore};
+
+use digest::consts::U32;
+use digest::core_api::CoreWrapper;
+use digest::{Digest, InvalidOutputSize, Mac};
+
+/// The length of the pseudorandom key, in bytes.
+const PRK_LEN: usize = 32;
+
+/// The length of each block of output key material, in bytes.
+const BLOCK_LEN: usize = 64;
+
+/// The domain separation tag for extraction.
+const EXTRACT: u128 = 0x01 << 120;
+
+/// The domain separation tag for expansion.
+const EXPAND: u128 = 0x02 << 120;
+
+/// AreionKdf, an extract-and-expand key derivation function in the style of
+/// [HKDF](https://www.rfc-editor.org/rfc/rfc5869) which uses the Areion512-HAIFA tweak for keying
+/// and domain separation instead of HMAC.
+///
+/// The tweak word `t_2` holds the key length in its low bytes, a domain separation tag in its first
+/// byte, and a big-endian 32-bit block counter in bytes 4 to 8. Extraction computes a 256-bit
+/// pseudorandom key from the input key material with the salt as the HAIFA key, hashing salts
+/// longer than 256 bits first. Expansion computes each 512-bit block of output independently:
+///
+/// ```text
+/// PRK = HAIFA(K = salt, T = EXTRACT, M = IKM)[..32]
+/// T_i = HAIFA(K = PRK, T = EXPAND || BE32(i), M = info)
+/// ```
+///
+/// Like [`AreionHaifaMac`](crate::AreionHaifaMac), each block is the keyed output transform of the
+/// final chaining value rather than the chaining value itself, so blocks for related contexts
+/// aren't linearly related.
+///
+/// Output is limited to 255 blocks, or [`AreionKdf::MAX_OUTPUT_LEN`] bytes.
+#[derive(Clone)]
+pub struct AreionKdf {
+    prk: [u8; PRK_LEN],
+}
+
+impl AreionKdf {
+    /// The maximum length of output key material, in bytes.
+    pub const MAX_OUTPUT_LEN: usize = 255 * BLOCK_LEN;
+
+    /// Extracts a pseudorandom key from the given salt and input key material.
+    pub fn extract(salt: &[u8], ikm: &[u8]) -> AreionKdf {
+        let digest;
+        let salt = if salt.len() > PRK_LEN {
+            digest = AreionHaifa::<U32>::digest(salt);
+            digest.as_slice()
+        } else {
+            salt
+        };
+
+        let mut prf = prf(salt, EXTRACT);
+        prf.update(ikm);
+        let mut prk = [0u8; PRK_LEN];
+        prk.copy_from_slice(&prf.finalize().into_bytes()[..PRK_LEN]);
+        AreionKdf { prk }
+    }
+
+    /// Creates a KDF from a pseudorandom key, skipping extraction.
+    pub fn from_prk(prk: &[u8; PRK_LEN]) -> AreionKdf {
+        AreionKdf { prk: *prk }
+    }
+
+    /// Returns the pseudorandom key.
+    pub fn prk(&self) -> [u8; PRK_LEN] {
+        self.prk
+    }
+
+    /// Fills `okm` with output key material bound to the given context.
+    ///
+    /// Returns an error if `okm` is longer than [`AreionKdf::MAX_OUTPUT_LEN`] bytes.
+    pub fn expand(&self, info: &[u8], okm: &mut [u8]) -> Result<(), InvalidOutputSize> {
+        if okm.len() > Self::MAX_OUTPUT_LEN {
+            return Err(InvalidOutputSize);
+        }
+
+        for (i, block) in okm.chunks_mut(BLOCK_LEN).enumerate() {
+            let mut prf = prf(&self.prk, EXPAND | (i as u128 + 1) << 64);
+            prf.update(info);
+            block.copy_from_slice(&prf.finalize().into_bytes()[..block.len()]);
+        }
+        Ok(())
+    }
+
+    /// Extracts a pseudorandom key from the given salt and input key material and expands it into
+    /// `okm` with the given context.
+    ///
+    /// Returns an error if `okm` is longer than [`AreionKdf::MAX_OUTPUT_LEN`] bytes.
+    pub fn derive(
+        salt: &[u8],
+        ikm: &[u8],
+        info: &[u8],
+        okm: &mut [u8],
+    ) -> Result<(), InvalidOutputSize> {
+        AreionKdf::extract(salt, ikm).expand(info, okm)
+    }
+}
+
+fn prf(key: &[u8], domain: u128) -> CoreWrapper<MacCore> {
+    CoreWrapper::from_core(MacCore::new_with_domain(key, domain))
+}
+
+impl fmt::Debug for AreionKdf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AreionKdf { ... }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+    use crate::AreionHaifaMac;
+
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    fn derive(salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+        let mut okm = vec![0u8; len];
+        AreionKdf::derive(salt, ikm, info, &mut okm).expect("should be a valid length");
+        okm
+    }
+
+    #[test]
+    fn test_vector() {
+        let kdf = AreionKdf::extract(&(0..13).collect::<Vec<u8>>(), &[0x0b; 22]);
+        expect![[r#"
+            df 7d 66 72 86 c1 ef 41 8e 7a c6 33 50 09 19 85
+            11 7b 56 cb 02 04 ad cc 0f 94 6f d2 cc 7e 35 dc"#]]
+        .assert_eq(&hex_fmt(&kdf.prk()));
+
+        let mut okm = [0u8; 100];
+        kdf.expand(&(0xf0..0xfa).collect::<Vec<u8>>(), &mut okm).expect("should be a valid length");
+        expect![[r#"
+            ac b4 c6 36 22 4f e7 5a b9 49 51 9d 9d 25 87 92
+            8b 24 35 68 76 1d e7 db a6 29 46 93 05 05 2d 5b
+            a5 a9 c4 da 33 3c 5d 39 bd 60 f3 33 37 10 d9 3d
+            05 08 4c 33 bd 5e 0c 8e ea 76 5c d9 a6 74 6d 48
+            03 ce 17 90 04 f5 07 9e d2 b4 28 71 42 b6 d2 6b
+            3a 2a 4a 77 c3 89 d1 99 b1 de 60 bd 5b 1a 2d 28
+            83 57 f3 69"#]]
+        .assert_eq(&hex_fmt(&okm));
+    }
+
+    #[test]
+    fn empty_inputs() {
+        expect![[r#"
+            a1 9e 71 7e 66 7c 5d 15 8a 7b 7a 8b ea f8 97 5f
+            50 97 1a 23 2b 52 e3 8b 81 a1 4c ef 08 b8 af f0
+            5e d1 bd 98 91 7b 56 06 10 28 76 70 51 31 49 eb
+            21 20 60 54 05 d0 54 11 3b 0c d5 c7 f1 a6 ee c2"#]]
+        .assert_eq(&hex_fmt(&derive(b"", b"", b"", 64)));
+        assert_ne!(derive(b"", b"", b"", 64), derive(&[0], b"", b"", 64));
+        assert_ne!(derive(b"", b"", b"", 64), derive(b"", &[0], b"", 64));
+        assert_ne!(derive(b"", b"", b"", 64), derive(b"", b"", &[0], 64));
+    }
+
+    #[test]
+    fn long_salts() {
+        let salt = [0x5a; 100];
+        let digest = AreionHaifa::<U32>::digest(salt);
+        assert_eq!(derive(&salt, b"ikm", b"", 32), derive(&digest, b"ikm", b"", 32));
+        assert_ne!(derive(&salt, b"ikm", b"", 32), derive(&salt[..32], b"ikm", b"", 32));
+    }
+
+    #[test]
+    fn domain_separation() {
+        // Extraction is distinct from the MAC with the same key.
+        let mut mac = <AreionHaifaMac as Mac>::new_from_slice(b"salt").expect("should be valid");
+        mac.update(b"ikm");
+        let mac = mac.finalize().into_bytes();
+        assert_ne!(AreionKdf::extract(b"salt", b"ikm").prk(), mac[..PRK_LEN]);
+
+        // Expansion is distinct from extraction with the same key.
+        let kdf = AreionKdf::from_prk(&[7; PRK_LEN]);
+        let mut okm = [0u8; PRK_LEN];
+        kdf.expand(b"info", &mut okm).expect("should be a valid length");
+        assert_ne!(AreionKdf::extract(&[7; PRK_LEN], b"info").prk(), okm);
+    }
+
+    #[test]
+    fn no_linear_relation() {
+        // With raw chaining values as output blocks, T(i1' || i2 ^ h1 ^ h1') would equal
+        // T(i1 || i2) ^ h1 ^ h1', where h1 = T(i1) and h1' = T(i1') for 64-byte contexts.
+        let kdf = AreionKdf::from_prk(&[7; PRK_LEN]);
+        let okm = |info: &[u8]| {
+            let mut okm = [0u8; BLOCK_LEN];
+            kdf.expand(info, &mut okm).expect("should be a valid length");
+            okm
+        };
+        let (i1, i1_p, i2) = ([1u8; 64], [2u8; 64], [3u8; 64]);
+        let diff = okm(&i1).iter().zip(okm(&i1_p)).map(|(a, b)| a ^ b).collect::<Vec<u8>>();
+
+        let mut info = i1.to_vec();
+        info.extend_from_slice(&i2);
+        let mut related = i1_p.to_vec();
+        related.extend(i2.iter().zip(&diff).map(|(a, b)| a ^ b));
+
+        let predicted = okm(&info).iter().zip(&diff).map(|(a, b)| a ^ b).collect::<Vec<u8>>();
+        assert_ne!(okm(&related).as_slice(), predicted);
+    }
+
+    #[test]
+    fn output_limit() {
+        let kdf = AreionKdf::from_prk(&[7; PRK_LEN]);
+        assert!(kdf.expand(b"", &mut [0u8; AreionKdf::MAX_OUTPUT_LEN]).is_ok());
+        assert!(kdf.expand(b"", &mut [0u8; AreionKdf::MAX_OUTPUT_LEN + 1]).is_err());
+    }
+
+    #[quickcheck]
+    fn shorter_outputs_are_prefixes(a: u16, b: u16) -> bool {
+        let (a, b) = (a as usize % 1000, b as usize % 1000);
+        let (short, long) = (a.min(b), a.max(b));
+        derive(b"salt", b"ikm", b"info", long)[..short] == derive(b"salt", b"ikm", b"info", short)
+    }
+
+    #[quickcheck]
+    fn different_contexts_yield_different_outputs(a: Vec<u8>, b: Vec<u8>) -> bool {
+        (derive(b"salt", b"ikm", &a, 32) != derive(b"salt", b"ikm", &b, 32)) == (a != b)
+    }
+}
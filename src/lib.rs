@@ -1,19 +1,66 @@
+pub mod balloon;
+#[cfg(feature = "std")]
+pub mod bao;
+mod ctr;
+mod deck;
+mod drbg;
+mod duplex;
+mod em;
 mod haifa;
+mod hasher;
+mod hctr;
 mod intrinsics;
+#[cfg(feature = "std")]
+mod io;
+mod kdf;
+pub mod lms;
+mod mask;
 mod md;
+pub mod merkle;
 mod mmo;
+mod opp;
+pub mod pbkdf2;
+mod pmac;
+pub mod pow;
+mod rng;
+mod siv;
+pub mod smt;
+pub mod sphincs;
 mod sponge;
+pub mod stream;
+mod tem;
 
 use intrinsics::*;
 
-pub use crate::haifa::{AreionHaifa512, AreionHaifaVar};
+pub use crate::ctr::{Areion512Ctr, Areion512CtrCore};
+pub use crate::deck::Areion512Deck;
+pub use crate::drbg::{AreionHashDrbg, DrbgError};
+pub use crate::duplex::Areion512Duplex;
+pub use crate::em::{Areion256Em, Areion512Em};
+pub use crate::haifa::{AreionHaifa512, AreionHaifaMac, AreionHaifaVar};
+pub use crate::hasher::{AreionBuildHasher, AreionHasher};
+pub use crate::hctr::Areion512Hctr;
 pub use crate::intrinsics::{load, store};
-pub use crate::md::Areion512Md;
-pub use crate::mmo::Areion512Mmo;
+#[cfg(feature = "std")]
+pub use crate::io::{hash_file, hash_reader, update_file, update_reader};
+pub use crate::kdf::AreionKdf;
+pub use crate::md::{Areion512Md, Areion512MdF};
+pub use crate::mmo::{Areion512Mmo, Areion512MmoF};
+pub use crate::opp::{Areion512Opp, OppSize};
+pub use crate::pbkdf2::{pbkdf2_hmac_areion512md, pbkdf2_hmac_areion512mmo};
+pub use crate::pmac::{Areion512Pmac, Areion512PmacCore};
+pub use crate::rng::AreionRng;
+pub use crate::siv::Areion512Siv;
 pub use crate::sponge::Areion256Sponge;
+pub use crate::tem::{Areion256Tem, Areion512Tem};
 
+pub use aead;
+pub use cipher;
 pub use digest;
 use hex_literal::hex;
+pub use password_hash;
+pub use rand_core;
+pub use signature;
 
 static RC0: [[u8; 16]; 24] = [
     hex!("447370032e8a1913d308a385886a3f24"),
@@ -128,6 +175,107 @@ pub fn areion512(
     (x3, x0, x1, x2)
 }
 
+/// Applies Areion512 to `N` independent states at once, where `N` is even.
+///
+/// With VAES, pairs of states share 256-bit registers, so each AES instruction advances two
+/// states. The pairs are advanced a round at a time so that the latencies of their AES
+/// instructions overlap. Otherwise, the permutation calls are made one after the other.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx2", target_feature = "vaes"))]
+#[inline]
+pub(crate) fn areion512_par<const N: usize>(x: [AesBlock4; N]) -> [AesBlock4; N] {
+    const { assert!(N.is_multiple_of(2), "states are permuted in pairs") };
+    let mut s = [[broadcast(zero()); 4]; N];
+    for (s, x) in s.iter_mut().zip(x.chunks_exact(2)) {
+        let (a, b) = (x[0], x[1]);
+        *s = [join(a.0, b.0), join(a.1, b.1), join(a.2, b.2), join(a.3, b.3)];
+    }
+    let s = &mut s[..N / 2];
+    round_512_x2::<0>(s);
+    round_512_x2::<1>(s);
+    round_512_x2::<2>(s);
+    round_512_x2::<3>(s);
+    round_512_x2::<4>(s);
+    round_512_x2::<5>(s);
+    round_512_x2::<6>(s);
+    round_512_x2::<7>(s);
+    round_512_x2::<8>(s);
+    round_512_x2::<9>(s);
+    round_512_x2::<10>(s);
+    round_512_x2::<11>(s);
+    round_512_x2::<12>(s);
+    round_512_x2::<13>(s);
+    round_512_x2::<14>(s);
+
+    let mut out = x;
+    for (out, &[x0, x1, x2, x3]) in out.chunks_exact_mut(2).zip(s.iter()) {
+        let [y0, y1, y2, y3] = [x3, x0, x1, x2].map(split);
+        out[0] = (y0.0, y1.0, y2.0, y3.0);
+        out[1] = (y0.1, y1.1, y2.1, y3.1);
+    }
+    out
+}
+
+/// Applies round `R` of Areion512 to pairs of states, where the round's first word is at index
+/// `R % 4` of each pair.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx2", target_feature = "vaes"))]
+#[inline(always)]
+fn round_512_x2<const R: usize>(s: &mut [[AesBlock2; 4]]) {
+    let (i0, i1, i2, i3) = (R % 4, (R + 1) % 4, (R + 2) % 4, (R + 3) % 4);
+    let rc0 = broadcast(load(&RC0[R]));
+    let rc1 = broadcast(zero());
+    for x in s.iter_mut() {
+        let x1 = enc2(x[i0], x[i1]);
+        let x3 = enc2(x[i2], x[i3]);
+        let x0 = enc_last2(x[i0], rc1);
+        let x2 = enc2(enc_last2(x[i2], rc0), rc1);
+        (x[i0], x[i1], x[i2], x[i3]) = (x0, x1, x2, x3);
+    }
+}
+
+/// Applies Areion512 to `N` independent states at once, where `N` is even.
+///
+/// The states are advanced a round at a time so that the latencies of their AES instructions
+/// overlap.
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx2", target_feature = "vaes")))]
+#[inline]
+pub(crate) fn areion512_par<const N: usize>(x: [AesBlock4; N]) -> [AesBlock4; N] {
+    const { assert!(N.is_multiple_of(2), "states are permuted in pairs") };
+    let mut s = x.map(|(x0, x1, x2, x3)| [x0, x1, x2, x3]);
+    round_512_xn::<0>(&mut s);
+    round_512_xn::<1>(&mut s);
+    round_512_xn::<2>(&mut s);
+    round_512_xn::<3>(&mut s);
+    round_512_xn::<4>(&mut s);
+    round_512_xn::<5>(&mut s);
+    round_512_xn::<6>(&mut s);
+    round_512_xn::<7>(&mut s);
+    round_512_xn::<8>(&mut s);
+    round_512_xn::<9>(&mut s);
+    round_512_xn::<10>(&mut s);
+    round_512_xn::<11>(&mut s);
+    round_512_xn::<12>(&mut s);
+    round_512_xn::<13>(&mut s);
+    round_512_xn::<14>(&mut s);
+    s.map(|[x0, x1, x2, x3]| (x3, x0, x1, x2))
+}
+
+/// Applies round `R` of Areion512 to states, where the round's first word is at index `R % 4` of
+/// each state.
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx2", target_feature = "vaes")))]
+#[inline(always)]
+fn round_512_xn<const R: usize>(s: &mut [[AesBlock; 4]]) {
+    let (i0, i1, i2, i3) = (R % 4, (R + 1) % 4, (R + 2) % 4, (R + 3) % 4);
+    let rc0 = load(&RC0[R]);
+    let rc1 = zero();
+    for x in s.iter_mut() {
+        let x1 = enc(x[i0], x[i1]);
+        let x3 = enc(x[i2], x[i3]);
+        let x0 = enc_last(x[i0], rc1);
+        let x2 = enc(enc_last(x[i2], rc0), rc1);
+        (x[i0], x[i1], x[i2], x[i3]) = (x0, x1, x2, x3);
+    }
+}
+
 #[inline]
 fn inv_round_512<const R: usize>(
     x0: AesBlock,
@@ -236,6 +384,18 @@ pub mod tests {
         .assert_eq(&hex_fmt(&x_p));
     }
 
+    #[test]
+    fn perm512_par_matches_perm512() {
+        let x: [AesBlock4; 8] = core::array::from_fn(|i| load4(&[i as u8 + 1; 64]));
+        let y = areion512_par(x);
+        for (x, y) in x.into_iter().zip(y) {
+            let (mut a, mut b) = ([0u8; 64], [0u8; 64]);
+            store4(&mut a, areion512(x.0, x.1, x.2, x.3));
+            store4(&mut b, y);
+            assert_eq!(a, b);
+        }
+    }
+
     #[test]
     fn perm256_test_vector_1() {
         let x0 = load(&hex!("00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00"));
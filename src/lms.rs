@@ -0,0 +1,949 @@
+//! Stateful hash-based signatures following the
+//! [Leighton-Micali](https://www.rfc-editor.org/rfc/rfc8554) LM-OTS, LMS, and HSS algorithms, with
+//! Areion512-MD in place of SHA-256.
+//!
+//! The algorithms, domain separation constants, and wire formats of public keys and signatures are
+//! exactly those of RFC 8554, with `n = m = 32`. The algorithm type codes are in the RFC's private
+//! use range (`0xDDDDDDDD` to `0xFFFFFFFF`), mirroring the SHA-256 codes:
+//!
+//! | LM-OTS type           | Code         | LMS type              | Code         |
+//! |-----------------------|--------------|-----------------------|--------------|
+//! | `LMOTS_AREION_N32_W1` | `0xE0000001` | `LMS_AREION_M32_H5`   | `0xE0000005` |
+//! | `LMOTS_AREION_N32_W2` | `0xE0000002` | `LMS_AREION_M32_H10`  | `0xE0000006` |
+//! | `LMOTS_AREION_N32_W4` | `0xE0000003` | `LMS_AREION_M32_H15`  | `0xE0000007` |
+//! | `LMOTS_AREION_N32_W8` | `0xE0000004` |                       |              |
+//!
+//! Each level of a [`SigningKey`] keeps its entire tree in memory and computes every one-time
+//! public key when it's created, so tree heights are limited to 15. Deeper hierarchies are built
+//! from more levels instead.
+//!
+//! One-time private keys are derived from a secret seed as in Appendix A of the RFC, and the
+//! randomizer `C` of each one-time signature is derived in the same way with the index `0xFFFD`, so
+//! signing is deterministic. The identifiers and seeds of lower-level trees are derived from the
+//! parent tree's seed and the index of the leaf which signs them, with the indexes `0xFFFF` and
+//! `0xFFFE`, so the entire [`SigningKey`] is determined by its parameters, the top-level identifier
+//! and seed, and the number of leaves used in each level.
+//!
+//! Each one-time key must never be used twice. [`SigningKey::sign`] reserves the next one-time key
+//! and hands the updated key state to a [`StateStore`] before computing the signature, and returns
+//! an error without signing if the state could not be stored.
+
+use core::fmt;
+
+use crate::Areion512Md;
+
+use digest::Digest;
+use rand_core::CryptoRngCore;
+use signature::{Error, Keypair, SignatureEncoding, Verifier};
+
+/// The length of hashes and tree nodes, in bytes.
+const N: usize = 32;
+
+/// The length of an LMS public key, in bytes.
+const LMS_PUBLIC_KEY_LEN: usize = 8 + 16 + N;
+
+/// The maximum number of HSS levels.
+const MAX_LEVELS: usize = 8;
+
+/// The domain separation constants from the RFC.
+const D_PBLC: u16 = 0x8080;
+const D_MESG: u16 = 0x8181;
+const D_LEAF: u16 = 0x8282;
+const D_INTR: u16 = 0x8383;
+
+/// The domain separation constants for deriving `C` and lower-level trees.
+const D_C: u16 = 0xfffd;
+const D_CHILD_SEED: u16 = 0xfffe;
+const D_CHILD_I: u16 = 0xffff;
+
+type Node = [u8; N];
+type Identifier = [u8; 16];
+
+/// An LM-OTS algorithm type, with `n = 32` and Winternitz parameter `w`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LmotsType {
+    /// `LMOTS_AREION_N32_W1`
+    AreionN32W1,
+    /// `LMOTS_AREION_N32_W2`
+    AreionN32W2,
+    /// `LMOTS_AREION_N32_W4`
+    AreionN32W4,
+    /// `LMOTS_AREION_N32_W8`
+    AreionN32W8,
+}
+
+impl LmotsType {
+    /// Returns the algorithm's type code.
+    pub const fn type_code(self) -> u32 {
+        match self {
+            LmotsType::AreionN32W1 => 0xE000_0001,
+            LmotsType::AreionN32W2 => 0xE000_0002,
+            LmotsType::AreionN32W4 => 0xE000_0003,
+            LmotsType::AreionN32W8 => 0xE000_0004,
+        }
+    }
+
+    /// Returns the algorithm with the given type code, if any.
+    pub const fn from_type_code(code: u32) -> Option<LmotsType> {
+        match code {
+            0xE000_0001 => Some(LmotsType::AreionN32W1),
+            0xE000_0002 => Some(LmotsType::AreionN32W2),
+            0xE000_0003 => Some(LmotsType::AreionN32W4),
+            0xE000_0004 => Some(LmotsType::AreionN32W8),
+            _ => None,
+        }
+    }
+
+    /// The width of each Winternitz digit, in bits.
+    const fn w(self) -> usize {
+        match self {
+            LmotsType::AreionN32W1 => 1,
+            LmotsType::AreionN32W2 => 2,
+            LmotsType::AreionN32W4 => 4,
+            LmotsType::AreionN32W8 => 8,
+        }
+    }
+
+    /// The number of hash chains.
+    const fn p(self) -> usize {
+        match self {
+            LmotsType::AreionN32W1 => 265,
+            LmotsType::AreionN32W2 => 133,
+            LmotsType::AreionN32W4 => 67,
+            LmotsType::AreionN32W8 => 34,
+        }
+    }
+
+    /// The left shift applied to the checksum.
+    const fn ls(self) -> u32 {
+        match self {
+            LmotsType::AreionN32W1 => 7,
+            LmotsType::AreionN32W2 => 6,
+            LmotsType::AreionN32W4 => 4,
+            LmotsType::AreionN32W8 => 0,
+        }
+    }
+
+    /// The length of a one-time signature, in bytes.
+    const fn sig_len(self) -> usize {
+        4 + N * (self.p() + 1)
+    }
+}
+
+/// An LMS algorithm type, with `m = 32` and tree height `h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LmsType {
+    /// `LMS_AREION_M32_H5`
+    AreionM32H5,
+    /// `LMS_AREION_M32_H10`
+    AreionM32H10,
+    /// `LMS_AREION_M32_H15`
+    AreionM32H15,
+}
+
+impl LmsType {
+    /// Returns the algorithm's type code.
+    pub const fn type_code(self) -> u32 {
+        match self {
+            LmsType::AreionM32H5 => 0xE000_0005,
+            LmsType::AreionM32H10 => 0xE000_0006,
+            LmsType::AreionM32H15 => 0xE000_0007,
+        }
+    }
+
+    /// Returns the algorithm with the given type code, if any.
+    pub const fn from_type_code(code: u32) -> Option<LmsType> {
+        match code {
+            0xE000_0005 => Some(LmsType::AreionM32H5),
+            0xE000_0006 => Some(LmsType::AreionM32H10),
+            0xE000_0007 => Some(LmsType::AreionM32H15),
+            _ => None,
+        }
+    }
+
+    /// The height of the tree.
+    const fn h(self) -> usize {
+        match self {
+            LmsType::AreionM32H5 => 5,
+            LmsType::AreionM32H10 => 10,
+            LmsType::AreionM32H15 => 15,
+        }
+    }
+
+    /// The number of leaves in the tree.
+    const fn leaves(self) -> u32 {
+        1 << self.h()
+    }
+
+    /// The length of a signature with the given one-time signature type, in bytes.
+    const fn sig_len(self, ots: LmotsType) -> usize {
+        4 + ots.sig_len() + 4 + N * self.h()
+    }
+}
+
+fn hash(parts: &[&[u8]]) -> Node {
+    let mut h = Areion512Md::new();
+    for part in parts {
+        h.update(part);
+    }
+    h.finalize().into()
+}
+
+fn u32_at(b: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(b.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Returns the `i`th `w`-bit digit of `s`.
+fn coef(s: &[u8], i: usize, w: usize) -> usize {
+    ((s[i * w / 8] >> (8 - (w * (i % (8 / w)) + w))) as usize) & ((1 << w) - 1)
+}
+
+/// Returns the digits of the message hash `q` followed by its checksum.
+fn digits(ots: LmotsType, q: &Node) -> Vec<usize> {
+    let w = ots.w();
+    let cksm = (0..N * 8 / w).map(|i| (1 << w) - 1 - coef(q, i, w)).sum::<usize>() << ots.ls();
+    let s = [q.as_slice(), &(cksm as u16).to_be_bytes()].concat();
+    (0..ots.p()).map(|i| coef(&s, i, w)).collect()
+}
+
+/// Iterates the hash chain `i` of the one-time key `q` over the steps `from..to`.
+fn chain(id: &Identifier, q: u32, i: u16, mut tmp: Node, from: usize, to: usize) -> Node {
+    for j in from..to {
+        tmp = hash(&[id, &q.to_be_bytes(), &i.to_be_bytes(), &[j as u8], &tmp]);
+    }
+    tmp
+}
+
+/// Derives a pseudorandom value for the one-time key `q` as in Appendix A of the RFC.
+fn derive(id: &Identifier, q: u32, i: u16, seed: &Node) -> Node {
+    hash(&[id, &q.to_be_bytes(), &i.to_be_bytes(), &[0xff], seed])
+}
+
+fn ots_public_key(ots: LmotsType, id: &Identifier, q: u32, seed: &Node) -> Node {
+    let mut h = Areion512Md::new()
+        .chain_update(id)
+        .chain_update(q.to_be_bytes())
+        .chain_update(D_PBLC.to_be_bytes());
+    for i in 0..ots.p() as u16 {
+        h.update(chain(id, q, i, derive(id, q, i, seed), 0, (1 << ots.w()) - 1));
+    }
+    h.finalize().into()
+}
+
+fn ots_sign(ots: LmotsType, id: &Identifier, q: u32, seed: &Node, message: &[u8]) -> Vec<u8> {
+    let c = derive(id, q, D_C, seed);
+    let q_hash = hash(&[id, &q.to_be_bytes(), &D_MESG.to_be_bytes(), &c, message]);
+
+    let mut sig = Vec::with_capacity(ots.sig_len());
+    sig.extend_from_slice(&ots.type_code().to_be_bytes());
+    sig.extend_from_slice(&c);
+    for (i, a) in digits(ots, &q_hash).into_iter().enumerate() {
+        let i = i as u16;
+        sig.extend_from_slice(&chain(id, q, i, derive(id, q, i, seed), 0, a));
+    }
+    sig
+}
+
+/// Computes the candidate public key from a one-time signature of the expected length.
+fn ots_candidate(ots: LmotsType, id: &Identifier, q: u32, sig: &[u8], message: &[u8]) -> Node {
+    let (c, y) = sig[4..].split_at(N);
+    let q_hash = hash(&[id, &q.to_be_bytes(), &D_MESG.to_be_bytes(), c, message]);
+
+    let mut h = Areion512Md::new()
+        .chain_update(id)
+        .chain_update(q.to_be_bytes())
+        .chain_update(D_PBLC.to_be_bytes());
+    for (i, (a, y)) in digits(ots, &q_hash).into_iter().zip(y.chunks_exact(N)).enumerate() {
+        let y = y.try_into().expect("should be a node");
+        h.update(chain(id, q, i as u16, y, a, (1 << ots.w()) - 1));
+    }
+    h.finalize().into()
+}
+
+/// An LMS public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LmsPublicKey {
+    lms: LmsType,
+    ots: LmotsType,
+    id: Identifier,
+    root: Node,
+}
+
+impl LmsPublicKey {
+    fn from_bytes(b: &[u8]) -> Option<LmsPublicKey> {
+        if b.len() != LMS_PUBLIC_KEY_LEN {
+            return None;
+        }
+        Some(LmsPublicKey {
+            lms: LmsType::from_type_code(u32_at(b, 0)?)?,
+            ots: LmotsType::from_type_code(u32_at(b, 4)?)?,
+            id: b[8..24].try_into().ok()?,
+            root: b[24..].try_into().ok()?,
+        })
+    }
+
+    fn to_bytes(&self) -> [u8; LMS_PUBLIC_KEY_LEN] {
+        let mut b = [0u8; LMS_PUBLIC_KEY_LEN];
+        b[..4].copy_from_slice(&self.lms.type_code().to_be_bytes());
+        b[4..8].copy_from_slice(&self.ots.type_code().to_be_bytes());
+        b[8..24].copy_from_slice(&self.id);
+        b[24..].copy_from_slice(&self.root);
+        b
+    }
+
+    /// Verifies an LMS signature as in Section 5.4.2 of the RFC.
+    fn verify(&self, message: &[u8], sig: &[u8]) -> bool {
+        let Some(q) = u32_at(sig, 0) else { return false };
+        if u32_at(sig, 4) != Some(self.ots.type_code()) {
+            return false;
+        }
+        let ots_len = self.ots.sig_len();
+        if u32_at(sig, 4 + ots_len) != Some(self.lms.type_code())
+            || q >= self.lms.leaves()
+            || sig.len() != self.lms.sig_len(self.ots)
+        {
+            return false;
+        }
+
+        let k = ots_candidate(self.ots, &self.id, q, &sig[4..4 + ots_len], message);
+        let mut r = self.lms.leaves() + q;
+        let mut tmp = hash(&[&self.id, &r.to_be_bytes(), &D_LEAF.to_be_bytes(), &k]);
+        for path in sig[8 + ots_len..].chunks_exact(N) {
+            tmp = if r & 1 == 1 {
+                hash(&[&self.id, &(r / 2).to_be_bytes(), &D_INTR.to_be_bytes(), path, &tmp])
+            } else {
+                hash(&[&self.id, &(r / 2).to_be_bytes(), &D_INTR.to_be_bytes(), &tmp, path])
+            };
+            r /= 2;
+        }
+        tmp == self.root
+    }
+}
+
+/// Returns the length of the LMS signature at the start of `sig`, as determined by its type codes.
+fn lms_sig_len(sig: &[u8]) -> Option<usize> {
+    let ots = LmotsType::from_type_code(u32_at(sig, 4)?)?;
+    let lms = LmsType::from_type_code(u32_at(sig, 4 + ots.sig_len())?)?;
+    Some(lms.sig_len(ots))
+}
+
+/// A single LMS tree of an HSS private key.
+struct Level {
+    lms: LmsType,
+    ots: LmotsType,
+    id: Identifier,
+    seed: Node,
+    /// The number of one-time keys which have been used.
+    q: u32,
+    /// The tree nodes, indexed from 1 as in the RFC.
+    tree: Vec<Node>,
+    /// The parent's signature of this tree's public key, followed by the public key.
+    signed_public_key: Vec<u8>,
+}
+
+impl Level {
+    fn new(lms: LmsType, ots: LmotsType, id: Identifier, seed: Node, q: u32) -> Level {
+        let leaves = lms.leaves() as usize;
+        let mut tree = vec![[0u8; N]; 2 * leaves];
+        for (q, leaf) in tree[leaves..].iter_mut().enumerate() {
+            let r = (leaves + q) as u32;
+            let k = ots_public_key(ots, &id, q as u32, &seed);
+            *leaf = hash(&[&id, &r.to_be_bytes(), &D_LEAF.to_be_bytes(), &k]);
+        }
+        for r in (1..leaves).rev() {
+            tree[r] = hash(&[
+                &id,
+                &(r as u32).to_be_bytes(),
+                &D_INTR.to_be_bytes(),
+                &tree[2 * r],
+                &tree[2 * r + 1],
+            ]);
+        }
+        Level { lms, ots, id, seed, q, tree, signed_public_key: Vec::new() }
+    }
+
+    /// Creates the child tree signed by this tree's one-time key `q`.
+    fn child(&self, lms: LmsType, ots: LmotsType, q: u32, child_q: u32) -> Level {
+        let seed = derive(&self.id, q, D_CHILD_SEED, &self.seed);
+        let id = derive(&self.id, q, D_CHILD_I, &self.seed)[..16].try_into().expect("should be 16");
+        let mut child = Level::new(lms, ots, id, seed, child_q);
+        let public_key = child.public_key().to_bytes();
+        child.signed_public_key = [self.sign(q, &public_key), public_key.to_vec()].concat();
+        child
+    }
+
+    fn public_key(&self) -> LmsPublicKey {
+        LmsPublicKey { lms: self.lms, ots: self.ots, id: self.id, root: self.tree[1] }
+    }
+
+    /// Signs a message with the one-time key `q`.
+    fn sign(&self, q: u32, message: &[u8]) -> Vec<u8> {
+        let mut sig = Vec::with_capacity(self.lms.sig_len(self.ots));
+        sig.extend_from_slice(&q.to_be_bytes());
+        sig.extend_from_slice(&ots_sign(self.ots, &self.id, q, &self.seed, message));
+        sig.extend_from_slice(&self.lms.type_code().to_be_bytes());
+        let mut r = (self.lms.leaves() + q) as usize;
+        while r > 1 {
+            sig.extend_from_slice(&self.tree[r ^ 1]);
+            r /= 2;
+        }
+        sig
+    }
+}
+
+/// Durable storage for the state of a [`SigningKey`].
+///
+/// Implementations must not return until the state has been durably written (e.g. after an
+/// `fsync`), or a crash could cause one-time keys to be reused. The state is the same encoding as
+/// [`SigningKey::to_bytes`].
+pub trait StateStore {
+    /// Durably stores the given key state.
+    fn store(&mut self, state: &[u8]) -> Result<(), Error>;
+}
+
+impl<F: FnMut(&[u8]) -> Result<(), Error>> StateStore for F {
+    fn store(&mut self, state: &[u8]) -> Result<(), Error> {
+        self(state)
+    }
+}
+
+/// An HSS private key with between one and eight levels of LMS trees.
+///
+/// Creating or loading a key computes every one-time public key of each level's tree, and signing
+/// recomputes lower-level trees as they are exhausted, so the cost of both grows with `2^h`.
+///
+/// Signing keys are deliberately not `Clone`, as two copies would sign with the same one-time keys.
+/// Use [`SigningKey::to_bytes`] and [`SigningKey::from_bytes`] with a [`StateStore`] to persist a
+/// key's state.
+pub struct SigningKey {
+    levels: Vec<Level>,
+}
+
+impl SigningKey {
+    /// Generates a key with the given LMS and LM-OTS types for each level, from the top down.
+    ///
+    /// Returns an error if there are no levels or more than eight.
+    pub fn generate(
+        params: &[(LmsType, LmotsType)],
+        mut rng: impl CryptoRngCore,
+    ) -> Result<SigningKey, Error> {
+        let (mut id, mut seed) = ([0u8; 16], [0u8; N]);
+        rng.try_fill_bytes(&mut id).map_err(|_| Error::new())?;
+        rng.try_fill_bytes(&mut seed).map_err(|_| Error::new())?;
+        SigningKey::from_seed(params, &id, &seed)
+    }
+
+    /// Derives a key with the given LMS and LM-OTS types for each level, from the top down, from
+    /// the top-level identifier and seed.
+    ///
+    /// Returns an error if there are no levels or more than eight.
+    pub fn from_seed(
+        params: &[(LmsType, LmotsType)],
+        id: &[u8; 16],
+        seed: &[u8; N],
+    ) -> Result<SigningKey, Error> {
+        // Each parent has signed its child with its first one-time key.
+        let mut q = vec![1; params.len()];
+        if let Some(q) = q.last_mut() {
+            *q = 0;
+        }
+        SigningKey::restore(params, id, seed, &q)
+    }
+
+    fn restore(
+        params: &[(LmsType, LmotsType)],
+        id: &Identifier,
+        seed: &Node,
+        q: &[u32],
+    ) -> Result<SigningKey, Error> {
+        if params.is_empty() || params.len() > MAX_LEVELS {
+            return Err(Error::new());
+        }
+
+        let mut levels: Vec<Level> = Vec::with_capacity(params.len());
+        for (&(lms, ots), &q) in params.iter().zip(q) {
+            if q > lms.leaves() {
+                return Err(Error::new());
+            }
+            let level = match levels.last() {
+                None => Level::new(lms, ots, *id, *seed, q),
+                Some(parent) => {
+                    // Every used parent key has signed a child, the last of which is this one.
+                    let parent_q = parent.q.checked_sub(1).ok_or_else(Error::new)?;
+                    parent.child(lms, ots, parent_q, q)
+                }
+            };
+            levels.push(level);
+        }
+        Ok(SigningKey { levels })
+    }
+
+    /// Decodes a key state, as returned by [`SigningKey::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<SigningKey, Error> {
+        let l = u32_at(bytes, 0).ok_or_else(Error::new)? as usize;
+        if l == 0 || l > MAX_LEVELS || bytes.len() != 4 + 12 * l + 16 + N {
+            return Err(Error::new());
+        }
+
+        let params = (0..l)
+            .map(|i| {
+                let lms = LmsType::from_type_code(u32_at(bytes, 4 + 8 * i)?)?;
+                let ots = LmotsType::from_type_code(u32_at(bytes, 8 + 8 * i)?)?;
+                Some((lms, ots))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(Error::new)?;
+        let (id, rest) = bytes[4 + 8 * l..].split_at(16);
+        let (seed, q) = rest.split_at(N);
+        let q =
+            q.chunks_exact(4).map(|q| u32::from_be_bytes(q.try_into().expect("should be 4 bytes")));
+        SigningKey::restore(
+            &params,
+            id.try_into().expect("should be 16 bytes"),
+            seed.try_into().expect("should be 32 bytes"),
+            &q.collect::<Vec<_>>(),
+        )
+    }
+
+    /// Encodes the key state as the number of levels `L`, the LMS and LM-OTS type codes of each
+    /// level, the top-level identifier and seed, and the number of used one-time keys in each
+    /// level:
+    ///
+    /// ```text
+    /// u32str(L) || (u32str(lms_type) || u32str(lmots_type))^L || I || SEED || u32str(q)^L
+    /// ```
+    ///
+    /// RFC 8554 doesn't specify a private key format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode(&self.levels.iter().map(|l| l.q).collect::<Vec<_>>())
+    }
+
+    fn encode(&self, q: &[u32]) -> Vec<u8> {
+        let mut b = Vec::with_capacity(4 + 12 * self.levels.len() + 16 + N);
+        b.extend_from_slice(&(self.levels.len() as u32).to_be_bytes());
+        for level in &self.levels {
+            b.extend_from_slice(&level.lms.type_code().to_be_bytes());
+            b.extend_from_slice(&level.ots.type_code().to_be_bytes());
+        }
+        b.extend_from_slice(&self.levels[0].id);
+        b.extend_from_slice(&self.levels[0].seed);
+        for q in q {
+            b.extend_from_slice(&q.to_be_bytes());
+        }
+        b
+    }
+
+    /// Returns the number of signatures the key can still produce, saturating at `u64::MAX`.
+    pub fn remaining(&self) -> u64 {
+        // Each unused one-time key in a level can sign a fresh tree for every level below it.
+        let mut below = 1u64;
+        let mut remaining = 0u64;
+        for level in self.levels.iter().rev() {
+            let unused = u64::from(level.lms.leaves() - level.q);
+            remaining = remaining.saturating_add(unused.saturating_mul(below));
+            below = below.saturating_mul(level.lms.leaves().into());
+        }
+        remaining
+    }
+
+    /// Signs a message with the next unused one-time key, as in Section 6.2 of the RFC.
+    ///
+    /// The updated key state is passed to `store` before the signature is computed. Returns an
+    /// error if the key is exhausted or if the state could not be stored, in which case the key is
+    /// unchanged.
+    pub fn sign(
+        &mut self,
+        message: &[u8],
+        store: &mut impl StateStore,
+    ) -> Result<Signature, Error> {
+        let l = self.levels.len();
+        let mut q = self.levels.iter().map(|l| l.q).collect::<Vec<_>>();
+
+        // Find the lowest level with unused one-time keys, and reserve one key from it and each
+        // level below it, which all need new trees.
+        let mut d = l;
+        while q[d - 1] == self.levels[d - 1].lms.leaves() {
+            d -= 1;
+            if d == 0 {
+                return Err(Error::new());
+            }
+        }
+        for j in d..l {
+            q[j - 1] += 1;
+            q[j] = 0;
+        }
+        q[l - 1] += 1;
+
+        store.store(&self.encode(&q))?;
+
+        for (level, &q) in self.levels.iter_mut().zip(&q) {
+            level.q = q;
+        }
+        for j in d..l {
+            let Level { lms, ots, q, .. } = self.levels[j];
+            let parent = &self.levels[j - 1];
+            self.levels[j] = parent.child(lms, ots, parent.q - 1, q);
+        }
+
+        let mut sig = Vec::new();
+        sig.extend_from_slice(&(l as u32 - 1).to_be_bytes());
+        for level in &self.levels[1..] {
+            sig.extend_from_slice(&level.signed_public_key);
+        }
+        sig.extend_from_slice(&self.levels[l - 1].sign(q[l - 1] - 1, message));
+        Ok(Signature(sig))
+    }
+}
+
+impl Keypair for SigningKey {
+    type VerifyingKey = VerifyingKey;
+
+    fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey { levels: self.levels.len() as u32, top: self.levels[0].public_key() }
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SigningKey { ... }")
+    }
+}
+
+/// An HSS public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyingKey {
+    levels: u32,
+    top: LmsPublicKey,
+}
+
+impl VerifyingKey {
+    /// The length of an encoded public key, in bytes.
+    pub const LEN: usize = 4 + LMS_PUBLIC_KEY_LEN;
+
+    /// Decodes a public key from its RFC 8554 encoding, `u32str(L) || pub[0]`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<VerifyingKey, Error> {
+        let levels = u32_at(bytes, 0).ok_or_else(Error::new)?;
+        if levels == 0 || levels as usize > MAX_LEVELS {
+            return Err(Error::new());
+        }
+        let top = LmsPublicKey::from_bytes(&bytes[4..]).ok_or_else(Error::new)?;
+        Ok(VerifyingKey { levels, top })
+    }
+
+    /// Encodes the public key as `u32str(L) || pub[0]`.
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut b = [0u8; Self::LEN];
+        b[..4].copy_from_slice(&self.levels.to_be_bytes());
+        b[4..].copy_from_slice(&self.top.to_bytes());
+        b
+    }
+}
+
+impl Verifier<Signature> for VerifyingKey {
+    /// Verifies an HSS signature as in Section 6.3 of the RFC.
+    fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), Error> {
+        let sig = &signature.0;
+        let nspk = u32_at(sig, 0).ok_or_else(Error::new)?;
+        if nspk.checked_add(1) != Some(self.levels) {
+            return Err(Error::new());
+        }
+
+        let mut key = self.top.clone();
+        let mut rest = &sig[4..];
+        for _ in 0..nspk {
+            let len = lms_sig_len(rest).ok_or_else(Error::new)?;
+            if rest.len() < len + LMS_PUBLIC_KEY_LEN {
+                return Err(Error::new());
+            }
+            let (lms_sig, tail) = rest.split_at(len);
+            let (public_key, tail) = tail.split_at(LMS_PUBLIC_KEY_LEN);
+            if !key.verify(public_key, lms_sig) {
+                return Err(Error::new());
+            }
+            key = LmsPublicKey::from_bytes(public_key).ok_or_else(Error::new)?;
+            rest = tail;
+        }
+
+        if key.verify(msg, rest) {
+            Ok(())
+        } else {
+            Err(Error::new())
+        }
+    }
+}
+
+/// An HSS signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature(Vec<u8>);
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8]> for Signature {
+    type Error = Error;
+
+    /// Wraps an encoded signature. Its structure is only checked during verification.
+    fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Signature(bytes.to_vec()))
+    }
+}
+
+impl From<Signature> for Vec<u8> {
+    fn from(signature: Signature) -> Vec<u8> {
+        signature.0
+    }
+}
+
+impl SignatureEncoding for Signature {
+    type Repr = Vec<u8>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+    use crate::AreionRng;
+
+    use expect_test::expect;
+    use rand_core::SeedableRng;
+
+    const ONE_LEVEL: &[(LmsType, LmotsType)] = &[(LmsType::AreionM32H5, LmotsType::AreionN32W4)];
+    const TWO_LEVELS: &[(LmsType, LmotsType)] = &[
+        (LmsType::AreionM32H5, LmotsType::AreionN32W2),
+        (LmsType::AreionM32H5, LmotsType::AreionN32W4),
+    ];
+
+    fn no_store(_: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Returns the leaf indexes of each level used by an HSS signature.
+    fn leaf_indexes(sig: &Signature) -> Vec<u32> {
+        let nspk = u32_at(sig.as_ref(), 0).expect("should have Nspk");
+        let mut rest = &sig.as_ref()[4..];
+        let mut q = Vec::new();
+        for _ in 0..=nspk {
+            q.push(u32_at(rest, 0).expect("should have q"));
+            let len = lms_sig_len(rest).expect("should be a valid signature");
+            rest = &rest[(len + LMS_PUBLIC_KEY_LEN).min(rest.len())..];
+        }
+        q
+    }
+
+    #[test]
+    fn coef_matches_rfc() {
+        assert_eq!(coef(&[0x12, 0x34], 7, 1), 0);
+        assert_eq!(coef(&[0x12, 0x34], 0, 4), 1);
+        assert_eq!(coef(&[0x12, 0x34], 3, 4), 4);
+        assert_eq!(coef(&[0x12, 0x34], 1, 8), 0x34);
+    }
+
+    #[test]
+    fn type_codes_are_private() {
+        let ots = [
+            LmotsType::AreionN32W1,
+            LmotsType::AreionN32W2,
+            LmotsType::AreionN32W4,
+            LmotsType::AreionN32W8,
+        ];
+        for ots in ots {
+            assert!(ots.type_code() >= 0xDDDD_DDDD);
+            assert_eq!(LmotsType::from_type_code(ots.type_code()), Some(ots));
+            // p = u + v, where u digits hold the hash and v digits hold the checksum.
+            let (w, u) = (ots.w(), N * 8 / ots.w());
+            let v = (usize::BITS - (((1 << w) - 1) * u).leading_zeros()) as usize;
+            assert_eq!(ots.p(), u + v.div_ceil(w));
+            assert_eq!(ots.ls() as usize, 16 - v.div_ceil(w) * w);
+        }
+        let lms = [LmsType::AreionM32H5, LmsType::AreionM32H10, LmsType::AreionM32H15];
+        for lms in lms {
+            assert!(lms.type_code() >= 0xDDDD_DDDD);
+            assert_eq!(LmsType::from_type_code(lms.type_code()), Some(lms));
+        }
+        assert_eq!(LmotsType::from_type_code(4), None);
+        assert_eq!(LmsType::from_type_code(5), None);
+        assert_eq!(LmsType::from_type_code(0xE000_0008), None);
+    }
+
+    #[test]
+    fn public_key() {
+        let sk = SigningKey::from_seed(TWO_LEVELS, &[1; 16], &[2; 32]).expect("should be valid");
+        let vk = sk.verifying_key();
+        expect![[r#"
+            00 00 00 02 e0 00 00 05 e0 00 00 02 01 01 01 01
+            01 01 01 01 01 01 01 01 01 01 01 01 56 13 7d f7
+            4c d7 45 46 74 e6 e2 29 34 08 29 d0 be 96 3f 15
+            b1 c7 6d 96 84 b7 ec b1 26 1d 80 55"#]]
+        .assert_eq(&hex_fmt(&vk.to_bytes()));
+        assert_eq!(VerifyingKey::from_bytes(&vk.to_bytes()).expect("should be valid"), vk);
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let mut sk = SigningKey::generate(TWO_LEVELS, AreionRng::from_seed([7; 32]))
+            .expect("should be valid");
+        let vk = sk.verifying_key();
+        assert_eq!(sk.remaining(), 32 * 32);
+
+        // Cross into a second bottom-level tree.
+        let mut seen = Vec::new();
+        for i in 0..40u32 {
+            let msg = i.to_be_bytes();
+            let sig = sk.sign(&msg, &mut no_store).expect("should sign");
+            assert_eq!(
+                sig.as_ref().len(),
+                4 + LmsType::AreionM32H5.sig_len(LmotsType::AreionN32W2)
+                    + LMS_PUBLIC_KEY_LEN
+                    + LmsType::AreionM32H5.sig_len(LmotsType::AreionN32W4)
+            );
+            assert!(vk.verify(&msg, &sig).is_ok(), "i = {i}");
+            assert!(vk.verify(b"other", &sig).is_err(), "i = {i}");
+
+            let q = leaf_indexes(&sig);
+            assert!(!seen.contains(&q), "reused {q:?}");
+            seen.push(q);
+        }
+        assert_eq!(seen[31], vec![0, 31]);
+        assert_eq!(seen[32], vec![1, 0]);
+        assert_eq!(sk.remaining(), 32 * 32 - 40);
+    }
+
+    #[test]
+    fn signatures_from_rotated_trees_differ() {
+        let mut sk =
+            SigningKey::from_seed(TWO_LEVELS, &[1; 16], &[2; 32]).expect("should be valid");
+        let first = sk.sign(b"message", &mut no_store).expect("should sign");
+        for _ in 1..32 {
+            sk.sign(b"message", &mut no_store).expect("should sign");
+        }
+        let second = sk.sign(b"message", &mut no_store).expect("should sign");
+        let bottom = |sig: &Signature| sig.as_ref()[sig.as_ref().len() - 32..].to_vec();
+        assert_ne!(bottom(&first), bottom(&second));
+    }
+
+    #[test]
+    fn exhaustion() {
+        let mut sk = SigningKey::from_seed(ONE_LEVEL, &[1; 16], &[2; 32]).expect("should be valid");
+        for _ in 0..32 {
+            sk.sign(b"message", &mut no_store).expect("should sign");
+        }
+        assert_eq!(sk.remaining(), 0);
+        assert!(sk.sign(b"message", &mut no_store).is_err());
+
+        let state = sk.to_bytes();
+        let mut sk = SigningKey::from_bytes(&state).expect("should be valid");
+        assert!(sk.sign(b"message", &mut no_store).is_err());
+    }
+
+    #[test]
+    fn state_is_stored_before_signing() {
+        let mut sk =
+            SigningKey::from_seed(TWO_LEVELS, &[1; 16], &[2; 32]).expect("should be valid");
+        let vk = sk.verifying_key();
+        let before = sk.to_bytes();
+
+        // A failed store doesn't produce a signature or advance the key.
+        let mut failing = |_: &[u8]| Err(Error::new());
+        assert!(sk.sign(b"message", &mut failing).is_err());
+        assert_eq!(sk.to_bytes(), before);
+        assert_eq!(sk.remaining(), 32 * 32);
+
+        // The stored state already accounts for the signature.
+        let mut stored = Vec::new();
+        let sig = sk
+            .sign(b"message", &mut |state: &[u8]| {
+                stored = state.to_vec();
+                Ok(())
+            })
+            .expect("should sign");
+        assert_eq!(stored, sk.to_bytes());
+        assert_eq!(leaf_indexes(&sig), vec![0, 0]);
+
+        // A key restored from the stored state continues with the next one-time key.
+        let mut restored = SigningKey::from_bytes(&stored).expect("should be valid");
+        assert_eq!(restored.verifying_key(), vk);
+        let sig = restored.sign(b"message", &mut no_store).expect("should sign");
+        assert_eq!(leaf_indexes(&sig), vec![0, 1]);
+        assert!(vk.verify(b"message", &sig).is_ok());
+    }
+
+    #[test]
+    fn state_encoding() {
+        let sk = SigningKey::from_seed(TWO_LEVELS, &[1; 16], &[2; 32]).expect("should be valid");
+        expect![[r#"
+            00 00 00 02 e0 00 00 05 e0 00 00 02 e0 00 00 05
+            e0 00 00 03 01 01 01 01 01 01 01 01 01 01 01 01
+            01 01 01 01 02 02 02 02 02 02 02 02 02 02 02 02
+            02 02 02 02 02 02 02 02 02 02 02 02 02 02 02 02
+            02 02 02 02 00 00 00 01 00 00 00 00"#]]
+        .assert_eq(&hex_fmt(&sk.to_bytes()));
+
+        assert!(SigningKey::from_bytes(&[]).is_err());
+        assert!(SigningKey::from_bytes(&sk.to_bytes()[1..]).is_err());
+        let mut state = sk.to_bytes();
+        state[3] = 9;
+        assert!(SigningKey::from_bytes(&state).is_err());
+        // A lower level can't exist without a used parent key.
+        let mut state = sk.to_bytes();
+        state[4 + 16 + 16 + 32 + 3] = 0;
+        assert!(SigningKey::from_bytes(&state).is_err());
+        // A level can't have used more keys than it has.
+        let mut state = sk.to_bytes();
+        state[4 + 16 + 16 + 32 + 7] = 33;
+        assert!(SigningKey::from_bytes(&state).is_err());
+
+        assert!(SigningKey::from_seed(&[], &[1; 16], &[2; 32]).is_err());
+        assert!(SigningKey::from_seed(&[ONE_LEVEL[0]; 9], &[1; 16], &[2; 32]).is_err());
+    }
+
+    #[test]
+    fn invalid_public_keys() {
+        let sk = SigningKey::from_seed(ONE_LEVEL, &[1; 16], &[2; 32]).expect("should be valid");
+        let vk = sk.verifying_key().to_bytes();
+        assert!(VerifyingKey::from_bytes(&vk[..VerifyingKey::LEN - 1]).is_err());
+        assert!(VerifyingKey::from_bytes(&[vk.as_slice(), &[0]].concat()).is_err());
+        for (i, b) in [(3, 0), (3, 9), (7, 1), (11, 0)] {
+            let mut vk = vk;
+            vk[i] = b;
+            assert!(VerifyingKey::from_bytes(&vk).is_err(), "i = {i}");
+        }
+    }
+
+    #[test]
+    fn invalid_signatures() {
+        let mut sk =
+            SigningKey::from_seed(TWO_LEVELS, &[1; 16], &[2; 32]).expect("should be valid");
+        let vk = sk.verifying_key();
+        let sig = sk.sign(b"message", &mut no_store).expect("should sign");
+        assert!(vk.verify(b"message", &sig).is_ok());
+
+        let bytes = sig.as_ref();
+        for len in [0, 3, 4, 100, bytes.len() - 1] {
+            let sig = Signature::try_from(&bytes[..len]).expect("should wrap");
+            assert!(vk.verify(b"message", &sig).is_err(), "len = {len}");
+        }
+        let sig = Signature::try_from([bytes, &[0]].concat().as_slice()).expect("should wrap");
+        assert!(vk.verify(b"message", &sig).is_err());
+
+        for i in (0..bytes.len()).step_by(97) {
+            let mut bytes = bytes.to_vec();
+            bytes[i] ^= 1;
+            let sig = Signature::try_from(bytes.as_slice()).expect("should wrap");
+            assert!(vk.verify(b"message", &sig).is_err(), "i = {i}");
+        }
+
+        // The number of signed public keys must match the key's levels.
+        let one = SigningKey::from_seed(ONE_LEVEL, &[1; 16], &[2; 32]).expect("should be valid");
+        assert!(one.verifying_key().verify(b"message", &sig).is_err());
+    }
+
+    #[test]
+    fn debug_is_redacted() {
+        let sk = SigningKey::from_seed(ONE_LEVEL, &[1; 16], &[2; 32]).expect("should be valid");
+        assert_eq!(format!("{sk:?}"), "SigningKey { ... }");
+    }
+}
@@ -0,0 +1,210 @@
+//! Masks for the MEM-style tweakable Even-Mansour constructions, from
+//! [Granger et al.](https://eprint.iacr.org/2015/999.pdf).
+//!
+//! Each mask is an `N`-word state over 64-bit little-endian words, updated with a word-wise LFSR
+//! `φ` which is cheap to evaluate and, for the state sizes used here, generates distinct masks.
+
+use crate::intrinsics::*;
+
+/// The characteristic polynomial of `φ` on 512-bit masks: `x^512` plus the terms whose
+/// coefficients are the bits of these words, least significant first.
+const PHI512_POLY: [u64; 8] = [
+    0x0080_0080_0000_0001,
+    0x0020_0000_0000_4000,
+    0x0000_0000_1000_1000,
+    0x0002_0002_0000_0400,
+    0x0000_0000_0000_0100,
+    0x0000_2000_0000_0000,
+    0x0000_0000_0000_0010,
+    0x0000_0000_0000_0004,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Mask<const N: usize>([u64; N]);
+
+/// A 256-bit mask.
+pub(crate) type Mask256 = Mask<4>;
+
+/// A 512-bit mask.
+pub(crate) type Mask512 = Mask<8>;
+
+impl<const N: usize> Mask<N> {
+    pub(crate) fn from_bytes(b: &[u8]) -> Mask<N> {
+        debug_assert_eq!(b.len(), N * 8);
+        let mut x = [0u64; N];
+        for (x, b) in x.iter_mut().zip(b.chunks_exact(8)) {
+            *x = u64::from_le_bytes(b.try_into().expect("should be 8 bytes"));
+        }
+        Mask(x)
+    }
+
+    /// Returns the `i`th 128-bit word of the mask.
+    #[inline]
+    pub(crate) fn block(&self, i: usize) -> AesBlock {
+        let mut b = [0u8; 16];
+        b[..8].copy_from_slice(&self.0[2 * i].to_le_bytes());
+        b[8..].copy_from_slice(&self.0[2 * i + 1].to_le_bytes());
+        load(&b)
+    }
+
+    pub(crate) fn xor(self, other: Mask<N>) -> Mask<N> {
+        let mut x = self.0;
+        for (x, y) in x.iter_mut().zip(other.0) {
+            *x ^= y;
+        }
+        Mask(x)
+    }
+}
+
+impl Mask256 {
+    /// φ(x_0, ..., x_3) = (x_1, x_2, x_3, (x_0 <<< 3) ^ (x_3 >> 5))
+    #[inline]
+    pub(crate) fn phi(self) -> Mask256 {
+        let [x0, x1, x2, x3] = self.0;
+        Mask([x1, x2, x3, x0.rotate_left(3) ^ (x3 >> 5)])
+    }
+}
+
+impl Mask512 {
+    /// Returns the mask as an Areion512 state.
+    #[inline]
+    pub(crate) fn blocks(&self) -> AesBlock4 {
+        (self.block(0), self.block(1), self.block(2), self.block(3))
+    }
+
+    /// φ(x_0, ..., x_7) = (x_1, ..., x_7, (x_0 <<< 29) ^ (x_1 << 9))
+    #[inline]
+    pub(crate) fn phi(self) -> Mask512 {
+        let [x0, x1, x2, x3, x4, x5, x6, x7] = self.0;
+        Mask([x1, x2, x3, x4, x5, x6, x7, x0.rotate_left(29) ^ (x1 << 9)])
+    }
+
+    /// φ^n(x), computed as `r(φ)(x)` for `r = x^n` modulo the characteristic polynomial of `φ`,
+    /// which takes `O(log n)` polynomial multiplications rather than `n` evaluations of `φ`.
+    pub(crate) fn phi_n(self, n: u64) -> Mask512 {
+        if n < 512 {
+            return (0..n).fold(self, |x, _| x.phi());
+        }
+
+        let mut r = [0u64; 8];
+        r[0] = 1;
+        for i in (0..u64::BITS - n.leading_zeros()).rev() {
+            r = poly_mul(r, r);
+            if n >> i & 1 == 1 {
+                r = poly_mul_x(r);
+            }
+        }
+
+        let (mut acc, mut x) = (Mask([0; 8]), self);
+        for i in 0..512 {
+            if r[i / 64] >> (i % 64) & 1 == 1 {
+                acc = acc.xor(x);
+            }
+            x = x.phi();
+        }
+        acc
+    }
+
+    /// (φ ⊕ 1)(x)
+    pub(crate) fn phi_1(self) -> Mask512 {
+        self.phi().xor(self)
+    }
+
+    /// (φ² ⊕ φ ⊕ 1)(x)
+    pub(crate) fn phi_2(self) -> Mask512 {
+        let phi = self.phi();
+        phi.phi().xor(phi).xor(self)
+    }
+}
+
+/// Returns `a * x` modulo the characteristic polynomial of `φ`.
+fn poly_mul_x(a: [u64; 8]) -> [u64; 8] {
+    let mut out = [0u64; 8];
+    for i in (1..8).rev() {
+        out[i] = a[i] << 1 | a[i - 1] >> 63;
+    }
+    out[0] = a[0] << 1;
+    if a[7] >> 63 == 1 {
+        out.iter_mut().zip(PHI512_POLY).for_each(|(o, p)| *o ^= p);
+    }
+    out
+}
+
+/// Returns `a * b` modulo the characteristic polynomial of `φ`.
+fn poly_mul(a: [u64; 8], b: [u64; 8]) -> [u64; 8] {
+    let mut out = [0u64; 8];
+    for i in (0..512).rev() {
+        out = poly_mul_x(out);
+        if b[i / 64] >> (i % 64) & 1 == 1 {
+            out.iter_mut().zip(a).for_each(|(o, a)| *o ^= a);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_are_distinct() {
+        let l = Mask512::from_bytes(&[0x5a; 64]);
+        let mut masks = Vec::new();
+        for j in 0..3 {
+            for k in 0..2 {
+                let mut delta = l;
+                for _ in 0..j {
+                    delta = delta.phi_1();
+                }
+                for _ in 0..k {
+                    delta = delta.phi_2();
+                }
+                for _ in 0..32 {
+                    masks.push(delta);
+                    delta = delta.phi();
+                }
+            }
+        }
+        for (i, a) in masks.iter().enumerate() {
+            assert!(!masks[i + 1..].contains(a));
+        }
+    }
+
+    #[test]
+    fn phi_n() {
+        let l = Mask512::from_bytes(&[0x5a; 64]);
+        let mut x = l;
+        for n in 0..2048 {
+            if n < 520 || n % 61 == 0 {
+                assert_eq!(l.phi_n(n), x, "n = {n}");
+            }
+            x = x.phi();
+        }
+
+        // φ^(a + b) = φ^a(φ^b) for offsets far beyond those which can be stepped through.
+        let (a, b) = (0x1234_5678_9abc_def0, u64::MAX - 0x1234_5678_9abc_def0);
+        assert_eq!(l.phi_n(a).phi_n(b), l.phi_n(u64::MAX));
+        assert_eq!(l.phi_n(a).phi_n(700), l.phi_n(a + 700));
+    }
+
+    #[test]
+    fn no_short_cycles() {
+        let l = Mask256::from_bytes(&[0x5a; 32]);
+        let mut masks = vec![l];
+        for _ in 0..1024 {
+            masks.push(masks.last().expect("should have a mask").phi());
+        }
+        masks.sort_by_key(|m| m.0);
+        masks.dedup();
+        assert_eq!(masks.len(), 1025);
+
+        let l = Mask512::from_bytes(&[0x5a; 64]);
+        let mut masks = vec![l];
+        for _ in 0..1024 {
+            masks.push(masks.last().expect("should have a mask").phi());
+        }
+        masks.sort_by_key(|m| m.0);
+        masks.dedup();
+        assert_eq!(masks.len(), 1025);
+    }
+}
@@ -33,29 +33,40 @@ impl State {
         }
         *self = Self(h0, h1);
     }
+
+    fn finalize(&mut self) {
+        // Ω(H) = DM(F, H), where F is a fixed chaining value distinct from the IV. Only the
+        // output of Ω is revealed, so the final chaining value can't be used to extend the
+        // message without first finding a prefix which compresses to F.
+        let Self(h0, h1) = *self;
+        let (h0, h1) = crate::areion512_dm(h0, h1, zero(), zero());
+        *self = Self(h0, h1);
+    }
 }
 
+/// The Areion512-MD core. If `FINAL` is true, the final chaining value is passed through an output
+/// transformation, making the hash resistant to length-extension attacks.
 #[derive(Debug, Default, Clone)]
-pub struct Core {
+pub struct Core<const FINAL: bool> {
     state: State,
     block_len: u64,
 }
 
-impl HashMarker for Core {}
+impl<const FINAL: bool> HashMarker for Core<FINAL> {}
 
-impl BlockSizeUser for Core {
+impl<const FINAL: bool> BlockSizeUser for Core<FINAL> {
     type BlockSize = U32;
 }
 
-impl BufferKindUser for Core {
+impl<const FINAL: bool> BufferKindUser for Core<FINAL> {
     type BufferKind = Eager;
 }
 
-impl OutputSizeUser for Core {
+impl<const FINAL: bool> OutputSizeUser for Core<FINAL> {
     type OutputSize = U32;
 }
 
-impl UpdateCore for Core {
+impl<const FINAL: bool> UpdateCore for Core<FINAL> {
     #[inline]
     fn update_blocks(&mut self, blocks: &[Block<Self>]) {
         self.block_len += blocks.len() as u64;
@@ -63,32 +74,36 @@ impl UpdateCore for Core {
     }
 }
 
-impl FixedOutputCore for Core {
+impl<const FINAL: bool> FixedOutputCore for Core<FINAL> {
     fn finalize_fixed_core(&mut self, buffer: &mut Buffer<Self>, out: &mut Output<Self>) {
         let bs = Self::BlockSize::U64;
         let bit_len = 8 * (buffer.get_pos() as u64 + bs * self.block_len);
         buffer.len64_padding_be(bit_len, |b| self.state.compress(slice::from_ref(b)));
+        if FINAL {
+            self.state.finalize();
+        }
 
         store(&mut out[..16], self.state.0);
         store(&mut out[16..], self.state.1);
     }
 }
 
-impl Reset for Core {
+impl<const FINAL: bool> Reset for Core<FINAL> {
     #[inline]
     fn reset(&mut self) {
         *self = Default::default();
     }
 }
 
-impl AlgorithmName for Core {
+impl<const FINAL: bool> AlgorithmName for Core<FINAL> {
     #[inline]
     fn write_alg_name(f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("Areion512-MD")
+        f.write_str(if FINAL { "Areion512-MD-F" } else { "Areion512-MD" })
     }
 }
 
-pub type Areion512Md = CoreWrapper<Core>;
+pub type Areion512Md = CoreWrapper<Core<false>>;
+pub type Areion512MdF = CoreWrapper<Core<true>>;
 
 #[cfg(test)]
 mod tests {
@@ -107,6 +122,47 @@ mod tests {
         aa == bb || a != b
     }
 
+    #[quickcheck]
+    fn finalized_different_inputs_yield_different_digests(a: Vec<u8>, b: Vec<u8>) -> bool {
+        let aa = Areion512MdF::new().chain_update(&a).finalize();
+        let bb = Areion512MdF::new().chain_update(&b).finalize();
+        aa == bb || a != b
+    }
+
+    /// Resumes hashing from a digest of `m`, assuming it's the chaining value after `blocks`
+    /// padded blocks.
+    fn extend<const FINAL: bool>(digest: &[u8], blocks: u64, ext: &[u8]) -> Vec<u8> {
+        let core = Core::<FINAL> {
+            state: State(load(&digest[..16]), load(&digest[16..])),
+            block_len: blocks,
+        };
+        CoreWrapper::from_core(core).chain_update(ext).finalize().to_vec()
+    }
+
+    #[test]
+    fn length_extension() {
+        let m = [0xaa; 40];
+        let mut padded = m.to_vec();
+        padded.push(0x80);
+        padded.resize(56, 0);
+        padded.extend_from_slice(&(8 * m.len() as u64).to_be_bytes());
+        padded.extend_from_slice(b"extension");
+
+        let digest = Areion512Md::new().chain_update(m).finalize();
+        assert_eq!(
+            extend::<false>(&digest, 2, b"extension"),
+            Areion512Md::new().chain_update(&padded).finalize().to_vec(),
+            "Areion512-MD should be vulnerable to length extension"
+        );
+
+        let digest = Areion512MdF::new().chain_update(m).finalize();
+        assert_ne!(
+            extend::<true>(&digest, 2, b"extension"),
+            Areion512MdF::new().chain_update(&padded).finalize().to_vec(),
+            "Areion512-MD-F should not be vulnerable to length extension"
+        );
+    }
+
     #[test]
     fn areion512_md_test_vector_1() {
         let data = hex!(
@@ -147,6 +203,26 @@ mod tests {
         .assert_eq(&hex_fmt(&Areion512Md::default().chain_update(data).finalize()));
     }
 
+    #[test]
+    fn areion512_md_f_test_vector() {
+        let data = hex!(
+            "
+            00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f
+            10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f
+            20 21 22 23 24 25 26 27 28 29 2a 2b 2c 2d 2e 2f
+            30 31 32 33 34 35 36 37 38 39 3a 3b 3c 3d 3e 3f
+            40 41 42 43 44 45 46 47 48 49 4a 4b 4c 4d 4e 4f
+            50 51 52 53 54 55 56 57 58 59 5a 5b 5c 5d 5e 5f
+            60 61 62 63 64 65 66 67 68 69 6a 6b 6c 6d 6e 6f
+            70 71 72 73 74 75 76 77 78 79 7a 7b 7c 7d 7e 7f"
+        );
+
+        expect![[r#"
+            88 12 96 7a e6 26 98 54 f4 37 f4 d0 b1 dd f3 99
+            f2 df c7 6c aa fe 5a 80 f7 c7 1a b9 16 2e 3e cb"#]]
+        .assert_eq(&hex_fmt(&Areion512MdF::default().chain_update(data).finalize()));
+    }
+
     #[test]
     fn fuzz() {
         bolero::check!().with_type::<Vec<u8>>().for_each(|input| {
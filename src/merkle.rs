@@ -0,0 +1,659 @@
+//! An append-only Merkle log in the style of
+//! [Certificate Transparency](https://www.rfc-editor.org/rfc/rfc9162) (RFC 6962 and RFC 9162).
+//!
+//! The tree shape, proof generation, and proof verification algorithms are those of RFC 9162, with
+//! the RFC's domain-separated hashes replaced by Areion:
+//!
+//! * The hash of the empty tree is `Areion512-MD("")`.
+//! * Leaves are hashed as `Areion512-MD(0x00 || entry)`.
+//! * Interior nodes are hashed as `DM(right, DM(left, F))`, with the children in the message
+//!   position of the Areion512-MD compression function and a fixed chaining value `F` distinct from
+//!   the Areion512-MD IV. This plays the role of the RFC's `0x01` prefix: an interior node hash can
+//!   only equal a leaf hash if `DM(left, F)` collides with a chaining value reachable from the IV.
+//!
+//! Proofs use a compact encoding: the two tree sizes (or leaf index and tree size) as big-endian
+//! 64-bit integers followed by the concatenated node hashes. The number of hashes is determined by
+//! the sizes, so it isn't encoded separately.
+
+use core::fmt;
+
+use crate::intrinsics::*;
+use crate::Areion512Md;
+
+use digest::Digest;
+use hex_literal::hex;
+
+/// The number of bytes in a hash.
+pub const HASH_LEN: usize = 32;
+
+/// A leaf, subtree, or root hash.
+pub type Hash = [u8; HASH_LEN];
+
+/// The fixed chaining value for interior nodes: the first 256 bits of the SHA2-512 IV, where
+/// Areion512-MD uses the SHA2-256 IV.
+const NODE_IV: [[u8; 16]; 2] =
+    [hex!("6a09e667f3bcc908bb67ae8584caa73b"), hex!("3c6ef372fe94f82ba54ff53a5f1d36f1")];
+
+/// The longest possible proof, for a tree of `2^64 - 1` leaves.
+const MAX_PROOF_LEN: usize = 64;
+
+/// An error returned when generating, decoding, or verifying a proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofError {
+    /// The requested leaf index or tree size is outside the log.
+    OutOfRange,
+    /// The encoded proof has an invalid length.
+    Malformed,
+    /// The proof doesn't match the given hashes.
+    Invalid,
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProofError::OutOfRange => "leaf index or tree size out of range",
+            ProofError::Malformed => "malformed proof",
+            ProofError::Invalid => "invalid proof",
+        })
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+/// Returns the hash of the empty tree.
+pub fn empty_root() -> Hash {
+    Areion512Md::digest([]).into()
+}
+
+/// Returns the hash of a leaf entry.
+pub fn leaf_hash(entry: &[u8]) -> Hash {
+    Areion512Md::new().chain_update([0x00]).chain_update(entry).finalize().into()
+}
+
+/// Returns the hash of an interior node with the given children.
+pub fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let (h0, h1) = crate::areion512_dm(
+        load(&left[..16]),
+        load(&left[16..]),
+        load(&NODE_IV[0]),
+        load(&NODE_IV[1]),
+    );
+    let (h0, h1) = crate::areion512_dm(load(&right[..16]), load(&right[16..]), h0, h1);
+    let mut out = [0u8; HASH_LEN];
+    store(&mut out[..16], h0);
+    store(&mut out[16..], h1);
+    out
+}
+
+/// Returns the largest power of two less than `n`, which must be greater than one.
+fn split(n: u64) -> u64 {
+    debug_assert!(n > 1);
+    1 << (n - 1).ilog2()
+}
+
+fn u64_at(b: &[u8], offset: usize) -> u64 {
+    u64::from_be_bytes(b[offset..offset + 8].try_into().expect("should be 8 bytes"))
+}
+
+fn decode_path(b: &[u8]) -> Result<Vec<Hash>, ProofError> {
+    if !b.len().is_multiple_of(HASH_LEN) || b.len() / HASH_LEN > MAX_PROOF_LEN {
+        return Err(ProofError::Malformed);
+    }
+    Ok(b.chunks_exact(HASH_LEN).map(|h| h.try_into().expect("should be a hash")).collect())
+}
+
+fn encode(a: u64, b: u64, path: &[Hash]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(16 + path.len() * HASH_LEN);
+    out.extend_from_slice(&a.to_be_bytes());
+    out.extend_from_slice(&b.to_be_bytes());
+    for h in path {
+        out.extend_from_slice(h);
+    }
+    out
+}
+
+/// The size and root hash of a log at some point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeHead {
+    /// The number of leaves in the tree.
+    pub size: u64,
+    /// The root hash of the tree.
+    pub root: Hash,
+}
+
+impl TreeHead {
+    /// The number of bytes in an encoded tree head.
+    pub const LEN: usize = 8 + HASH_LEN;
+
+    /// Decodes a tree head from its size, as a big-endian 64-bit integer, and root hash.
+    pub fn from_bytes(bytes: &[u8]) -> Result<TreeHead, ProofError> {
+        if bytes.len() != Self::LEN {
+            return Err(ProofError::Malformed);
+        }
+        Ok(TreeHead {
+            size: u64_at(bytes, 0),
+            root: bytes[8..].try_into().expect("should be a hash"),
+        })
+    }
+
+    /// Encodes the tree head as its size, as a big-endian 64-bit integer, and root hash.
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut out = [0u8; Self::LEN];
+        out[..8].copy_from_slice(&self.size.to_be_bytes());
+        out[8..].copy_from_slice(&self.root);
+        out
+    }
+}
+
+/// A proof that a leaf is included in a tree of a given size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof {
+    /// The index of the leaf.
+    pub leaf_index: u64,
+    /// The number of leaves in the tree.
+    pub tree_size: u64,
+    /// The sibling hashes from the leaf up to the root.
+    pub path: Vec<Hash>,
+}
+
+impl InclusionProof {
+    /// Decodes a proof from its compact encoding, `leaf_index || tree_size || path`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<InclusionProof, ProofError> {
+        if bytes.len() < 16 {
+            return Err(ProofError::Malformed);
+        }
+        Ok(InclusionProof {
+            leaf_index: u64_at(bytes, 0),
+            tree_size: u64_at(bytes, 8),
+            path: decode_path(&bytes[16..])?,
+        })
+    }
+
+    /// Encodes the proof as `leaf_index || tree_size || path`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode(self.leaf_index, self.tree_size, &self.path)
+    }
+
+    /// Verifies that the leaf with the given hash is included in the tree with the given head, as
+    /// in Section 2.1.3.2 of RFC 9162.
+    pub fn verify(&self, leaf: &Hash, head: &TreeHead) -> Result<(), ProofError> {
+        if self.tree_size != head.size || self.leaf_index >= self.tree_size {
+            return Err(ProofError::Invalid);
+        }
+
+        let (mut f, mut s) = (self.leaf_index, self.tree_size - 1);
+        let mut r = *leaf;
+        for p in &self.path {
+            if s == 0 {
+                return Err(ProofError::Invalid);
+            }
+            if f & 1 == 1 || f == s {
+                r = node_hash(p, &r);
+                while f & 1 == 0 && f != 0 {
+                    f >>= 1;
+                    s >>= 1;
+                }
+            } else {
+                r = node_hash(&r, p);
+            }
+            f >>= 1;
+            s >>= 1;
+        }
+
+        if s == 0 && r == head.root {
+            Ok(())
+        } else {
+            Err(ProofError::Invalid)
+        }
+    }
+}
+
+/// A proof that a tree is an append-only extension of an earlier tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyProof {
+    /// The number of leaves in the earlier tree.
+    pub old_size: u64,
+    /// The number of leaves in the later tree.
+    pub new_size: u64,
+    /// The subtree hashes needed to compute both roots.
+    pub path: Vec<Hash>,
+}
+
+impl ConsistencyProof {
+    /// Decodes a proof from its compact encoding, `old_size || new_size || path`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<ConsistencyProof, ProofError> {
+        if bytes.len() < 16 {
+            return Err(ProofError::Malformed);
+        }
+        Ok(ConsistencyProof {
+            old_size: u64_at(bytes, 0),
+            new_size: u64_at(bytes, 8),
+            path: decode_path(&bytes[16..])?,
+        })
+    }
+
+    /// Encodes the proof as `old_size || new_size || path`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode(self.old_size, self.new_size, &self.path)
+    }
+
+    /// Verifies that the tree with the `new` head extends the tree with the `old` head, as in
+    /// Section 2.1.4.2 of RFC 9162.
+    ///
+    /// Every tree extends the empty tree and itself, with an empty proof.
+    pub fn verify(&self, old: &TreeHead, new: &TreeHead) -> Result<(), ProofError> {
+        if self.old_size != old.size || self.new_size != new.size || old.size > new.size {
+            return Err(ProofError::Invalid);
+        }
+        if old.size == 0 || old.size == new.size {
+            let trivial = self.path.is_empty()
+                && (old.size != 0 || old.root == empty_root())
+                && (old.size == 0 || old.root == new.root);
+            return if trivial { Ok(()) } else { Err(ProofError::Invalid) };
+        }
+
+        let mut path = self.path.iter();
+        let first = if old.size.is_power_of_two() { Some(&old.root) } else { path.next() };
+        let Some(first) = first else { return Err(ProofError::Invalid) };
+
+        let (mut f, mut s) = (old.size - 1, new.size - 1);
+        while f & 1 == 1 {
+            f >>= 1;
+            s >>= 1;
+        }
+        let (mut fr, mut sr) = (*first, *first);
+        for c in path {
+            if s == 0 {
+                return Err(ProofError::Invalid);
+            }
+            if f & 1 == 1 || f == s {
+                fr = node_hash(c, &fr);
+                sr = node_hash(c, &sr);
+                while f & 1 == 0 && f != 0 {
+                    f >>= 1;
+                    s >>= 1;
+                }
+            } else {
+                sr = node_hash(&sr, c);
+            }
+            f >>= 1;
+            s >>= 1;
+        }
+
+        if s == 0 && fr == old.root && sr == new.root {
+            Ok(())
+        } else {
+            Err(ProofError::Invalid)
+        }
+    }
+}
+
+/// An append-only Merkle log which keeps every complete subtree hash in memory.
+///
+/// A log of `n` leaves stores fewer than `2n` hashes, and computes any root, inclusion proof, or
+/// consistency proof with `O(log n)` node compressions.
+#[derive(Debug, Clone)]
+pub struct MerkleLog {
+    /// The hashes of complete subtrees of `2^k` leaves, indexed by `k` and then position.
+    levels: Vec<Vec<Hash>>,
+}
+
+impl Default for MerkleLog {
+    fn default() -> Self {
+        MerkleLog::new()
+    }
+}
+
+impl MerkleLog {
+    /// Creates an empty log.
+    pub fn new() -> MerkleLog {
+        MerkleLog { levels: vec![Vec::new()] }
+    }
+
+    /// Returns the number of leaves in the log.
+    pub fn len(&self) -> u64 {
+        self.levels[0].len() as u64
+    }
+
+    /// Returns `true` if the log has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// Appends an entry to the log, returning its leaf index.
+    pub fn append(&mut self, entry: &[u8]) -> u64 {
+        self.append_leaf_hash(leaf_hash(entry))
+    }
+
+    /// Appends a leaf hash, as returned by [`leaf_hash`], to the log, returning its leaf index.
+    pub fn append_leaf_hash(&mut self, leaf: Hash) -> u64 {
+        let index = self.len();
+        self.levels[0].push(leaf);
+
+        // Complete each subtree which this leaf finishes.
+        let (mut i, mut k) = (index, 0);
+        while i & 1 == 1 {
+            let level = &self.levels[k];
+            let node = node_hash(&level[level.len() - 2], &level[level.len() - 1]);
+            k += 1;
+            i >>= 1;
+            if self.levels.len() == k {
+                self.levels.push(Vec::new());
+            }
+            self.levels[k].push(node);
+        }
+        index
+    }
+
+    /// Returns the hash of the leaf with the given index.
+    pub fn leaf(&self, index: u64) -> Option<Hash> {
+        self.levels[0].get(usize::try_from(index).ok()?).copied()
+    }
+
+    /// Returns the current tree head.
+    pub fn tree_head(&self) -> TreeHead {
+        TreeHead { size: self.len(), root: self.subtree(0, self.len()) }
+    }
+
+    /// Returns the tree head of the log when it had `size` leaves.
+    pub fn tree_head_at(&self, size: u64) -> Result<TreeHead, ProofError> {
+        if size > self.len() {
+            return Err(ProofError::OutOfRange);
+        }
+        Ok(TreeHead { size, root: self.subtree(0, size) })
+    }
+
+    /// Returns a proof that the leaf with the given index is included in the tree of `tree_size`
+    /// leaves, as in Section 2.1.3.1 of RFC 9162.
+    pub fn inclusion_proof(
+        &self,
+        leaf_index: u64,
+        tree_size: u64,
+    ) -> Result<InclusionProof, ProofError> {
+        if leaf_index >= tree_size || tree_size > self.len() {
+            return Err(ProofError::OutOfRange);
+        }
+
+        let mut path = Vec::new();
+        let (mut start, mut end, mut m) = (0, tree_size, leaf_index);
+        while end - start > 1 {
+            let k = split(end - start);
+            if m < k {
+                path.push(self.subtree(start + k, end));
+                end = start + k;
+            } else {
+                path.push(self.subtree(start, start + k));
+                start += k;
+                m -= k;
+            }
+        }
+        path.reverse();
+        Ok(InclusionProof { leaf_index, tree_size, path })
+    }
+
+    /// Returns a proof that the tree of `new_size` leaves extends the tree of `old_size` leaves, as
+    /// in Section 2.1.4.1 of RFC 9162.
+    pub fn consistency_proof(
+        &self,
+        old_size: u64,
+        new_size: u64,
+    ) -> Result<ConsistencyProof, ProofError> {
+        if old_size > new_size || new_size > self.len() {
+            return Err(ProofError::OutOfRange);
+        }
+
+        let mut path = Vec::new();
+        if old_size != 0 {
+            let (mut start, mut end, mut m, mut complete) = (0, new_size, old_size, true);
+            while m != end - start {
+                let k = split(end - start);
+                if m <= k {
+                    path.push(self.subtree(start + k, end));
+                    end = start + k;
+                } else {
+                    path.push(self.subtree(start, start + k));
+                    start += k;
+                    m -= k;
+                    complete = false;
+                }
+            }
+            if !complete {
+                path.push(self.subtree(start, end));
+            }
+            path.reverse();
+        }
+        Ok(ConsistencyProof { old_size, new_size, path })
+    }
+
+    /// Returns the hash of the leaves in `start..end`, where either the range is the whole tree or
+    /// `start` is a multiple of the largest power of two not greater than `end - start`.
+    fn subtree(&self, start: u64, end: u64) -> Hash {
+        let n = end - start;
+        if n == 0 {
+            return empty_root();
+        }
+        if n.is_power_of_two() && start.is_multiple_of(n) {
+            let k = n.trailing_zeros() as usize;
+            return self.levels[k][(start >> k) as usize];
+        }
+        let k = split(n);
+        node_hash(&self.subtree(start, start + k), &self.subtree(start + k, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    /// The Merkle tree hash of RFC 9162, computed directly from its recursive definition.
+    fn mth(leaves: &[Hash]) -> Hash {
+        match leaves.len() {
+            0 => empty_root(),
+            1 => leaves[0],
+            n => {
+                let k = split(n as u64) as usize;
+                node_hash(&mth(&leaves[..k]), &mth(&leaves[k..]))
+            }
+        }
+    }
+
+    fn log(n: u64) -> (MerkleLog, Vec<Hash>) {
+        let mut log = MerkleLog::new();
+        let leaves = (0..n).map(|i| leaf_hash(&i.to_be_bytes())).collect::<Vec<_>>();
+        for (i, &leaf) in leaves.iter().enumerate() {
+            assert_eq!(log.append_leaf_hash(leaf), i as u64);
+        }
+        (log, leaves)
+    }
+
+    #[test]
+    fn hashes() {
+        expect![[r#"
+            a9 5c 7b 92 4e f1 d6 48 7d 3f 44 05 9b 27 03 ec
+            2c 99 31 9f 31 ea e4 74 13 13 53 e9 f3 94 08 ff"#]]
+        .assert_eq(&hex_fmt(&empty_root()));
+        expect![[r#"
+            a7 b1 bb 88 b4 53 63 fb 36 3c 7e 24 f0 25 6d 9c
+            0e 09 51 43 1b 61 2c 30 69 84 52 e7 43 0f 1d 5c"#]]
+        .assert_eq(&hex_fmt(&leaf_hash(b"")));
+        expect![[r#"
+            86 c3 ef 1a 18 b9 60 dc b9 49 56 7c 64 74 72 d0
+            b3 24 bb 4b b0 e8 e9 86 74 39 78 1c 8b 33 10 71"#]]
+        .assert_eq(&hex_fmt(&node_hash(&[1; 32], &[2; 32])));
+
+        let mut log = MerkleLog::new();
+        for entry in [&b"alpha"[..], b"beta", b"gamma", b"delta", b"epsilon", b"zeta", b"eta"] {
+            log.append(entry);
+        }
+        expect![[r#"
+            6c 44 80 db 87 ec 58 f1 d2 90 42 c5 6d 4a 31 2e
+            46 8c 2c 57 8a 8a b1 c3 d0 06 69 44 ea b6 1b ce"#]]
+        .assert_eq(&hex_fmt(&log.tree_head().root));
+    }
+
+    #[test]
+    fn leaves_and_nodes_are_separated() {
+        assert_ne!(leaf_hash(&[[1; 32], [2; 32]].concat()), node_hash(&[1; 32], &[2; 32]));
+        assert_ne!(node_hash(&[1; 32], &[2; 32]), node_hash(&[2; 32], &[1; 32]));
+        assert_ne!(empty_root(), leaf_hash(b""));
+    }
+
+    #[test]
+    fn nodes_are_not_leaf_compressions() {
+        // The final compression of leaf_hash(e) for a short entry e is DM(b, IV), where b is the
+        // padded block 0x00 || e || 0x80 || 0* || BE64(bit length). With the right child in the
+        // chaining position of a single compression, node_hash(b, IV ^ T) would equal it.
+        let entry = b"entry";
+        let mut block = [0u8; HASH_LEN];
+        block[1..6].copy_from_slice(entry);
+        block[6] = 0x80;
+        block[24..].copy_from_slice(&48u64.to_be_bytes());
+        let iv = hex!("6a09e667bb67ae853c6ef372a54ff53a510e527f9b05688c1f83d9ab5be0cd19");
+        let mut tweaked = iv;
+        tweaked[HASH_LEN - 1] ^= 0x01;
+
+        let leaf = leaf_hash(entry);
+        assert_ne!(node_hash(&block, &tweaked), leaf);
+        assert_ne!(node_hash(&block, &iv), leaf);
+        assert_ne!(node_hash(&iv, &block), leaf);
+    }
+
+    #[test]
+    fn roots_match_definition() {
+        let (log, leaves) = log(70);
+        for size in 0..=70 {
+            let head = log.tree_head_at(size).expect("should be in range");
+            assert_eq!(head.root, mth(&leaves[..size as usize]), "size = {size}");
+        }
+        assert_eq!(log.tree_head(), log.tree_head_at(70).expect("should be in range"));
+        assert_eq!(log.tree_head_at(71), Err(ProofError::OutOfRange));
+        assert_eq!(log.leaf(69), Some(leaves[69]));
+        assert_eq!(log.leaf(70), None);
+    }
+
+    #[test]
+    fn inclusion_proofs() {
+        let (log, leaves) = log(40);
+        for size in 1..=40 {
+            let head = log.tree_head_at(size).expect("should be in range");
+            for index in 0..size {
+                let proof = log.inclusion_proof(index, size).expect("should be in range");
+                assert!(proof.path.len() <= 6);
+                assert_eq!(proof.verify(&leaves[index as usize], &head), Ok(()));
+
+                // The proof doesn't verify for any other leaf or position.
+                let other = leaves[((index + 1) % 40) as usize];
+                assert_eq!(proof.verify(&other, &head), Err(ProofError::Invalid));
+                if size > 1 {
+                    let moved = InclusionProof { leaf_index: (index + 1) % size, ..proof.clone() };
+                    assert_eq!(
+                        moved.verify(&leaves[index as usize], &head),
+                        Err(ProofError::Invalid)
+                    );
+                }
+                let mut extended = proof.clone();
+                extended.path.push(head.root);
+                assert_eq!(
+                    extended.verify(&leaves[index as usize], &head),
+                    Err(ProofError::Invalid)
+                );
+                if let Some(h) = proof.path.first() {
+                    let mut tampered = proof.clone();
+                    tampered.path[0] = node_hash(h, h);
+                    assert_eq!(
+                        tampered.verify(&leaves[index as usize], &head),
+                        Err(ProofError::Invalid)
+                    );
+                }
+            }
+        }
+        assert_eq!(log.inclusion_proof(5, 5), Err(ProofError::OutOfRange));
+        assert_eq!(log.inclusion_proof(0, 41), Err(ProofError::OutOfRange));
+    }
+
+    #[test]
+    fn consistency_proofs() {
+        let (log, _) = log(40);
+        for new_size in 0..=40 {
+            let new = log.tree_head_at(new_size).expect("should be in range");
+            for old_size in 0..=new_size {
+                let old = log.tree_head_at(old_size).expect("should be in range");
+                let proof = log.consistency_proof(old_size, new_size).expect("should be in range");
+                assert_eq!(proof.verify(&old, &new), Ok(()), "{old_size} -> {new_size}");
+                if old_size == 0 || old_size == new_size {
+                    assert!(proof.path.is_empty());
+                }
+
+                // The proof doesn't verify for a different earlier tree.
+                if old_size > 0 && old_size < new_size {
+                    let forked = TreeHead { root: node_hash(&old.root, &old.root), ..old };
+                    assert_eq!(proof.verify(&forked, &new), Err(ProofError::Invalid));
+                    let mut tampered = proof.clone();
+                    tampered.path[0] = node_hash(&proof.path[0], &proof.path[0]);
+                    assert_eq!(tampered.verify(&old, &new), Err(ProofError::Invalid));
+                    let mut truncated = proof.clone();
+                    truncated.path.pop();
+                    assert_eq!(truncated.verify(&old, &new), Err(ProofError::Invalid));
+                }
+            }
+        }
+        assert_eq!(log.consistency_proof(6, 5), Err(ProofError::OutOfRange));
+        assert_eq!(log.consistency_proof(5, 41), Err(ProofError::OutOfRange));
+
+        // A proof can't be reused for trees of other sizes.
+        let proof = log.consistency_proof(5, 9).expect("should be in range");
+        let old = log.tree_head_at(5).expect("should be in range");
+        let new = log.tree_head_at(10).expect("should be in range");
+        assert_eq!(proof.verify(&old, &new), Err(ProofError::Invalid));
+    }
+
+    #[test]
+    fn proof_encoding() {
+        let (log, leaves) = log(11);
+        let head = log.tree_head();
+        let bytes = head.to_bytes();
+        assert_eq!(TreeHead::from_bytes(&bytes), Ok(head));
+        assert_eq!(TreeHead::from_bytes(&bytes[1..]), Err(ProofError::Malformed));
+
+        let proof = log.inclusion_proof(6, 11).expect("should be in range");
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 16 + 4 * HASH_LEN);
+        expect!["00 00 00 00 00 00 00 06 00 00 00 00 00 00 00 0b"]
+            .assert_eq(&hex_fmt(&bytes[..16]));
+        let decoded = InclusionProof::from_bytes(&bytes).expect("should decode");
+        assert_eq!(decoded.verify(&leaves[6], &head), Ok(()));
+        assert_eq!(InclusionProof::from_bytes(&bytes[..15]), Err(ProofError::Malformed));
+        assert_eq!(
+            InclusionProof::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ProofError::Malformed)
+        );
+
+        let proof = log.consistency_proof(3, 11).expect("should be in range");
+        let bytes = proof.to_bytes();
+        let decoded = ConsistencyProof::from_bytes(&bytes).expect("should decode");
+        assert_eq!(decoded, proof);
+        assert_eq!(
+            ConsistencyProof::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ProofError::Malformed)
+        );
+        let long = [&bytes[..16], &[0; (MAX_PROOF_LEN + 1) * HASH_LEN]].concat();
+        assert_eq!(ConsistencyProof::from_bytes(&long), Err(ProofError::Malformed));
+    }
+
+    #[quickcheck]
+    fn different_entries_have_different_roots(a: Vec<Vec<u8>>, b: Vec<Vec<u8>>) -> bool {
+        let root = |entries: &[Vec<u8>]| {
+            let mut log = MerkleLog::new();
+            for entry in entries {
+                log.append(entry);
+            }
+            log.tree_head().root
+        };
+        (a != b) == (root(&a) != root(&b))
+    }
+}
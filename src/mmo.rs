@@ -52,29 +52,41 @@ impl State {
         }
         *self = Self(h0, h1, h2, h3);
     }
+
+    fn finalize(&mut self) {
+        // Every SEM-MMO compression is a function of H ^ M alone, so no constant XORed into the
+        // final block can separate it from an ordinary one. Instead, use the inverse permutation:
+        // Ω(H) = P^-1(H) ^ H. Continuing the chain from Ω(H) requires knowing P^-1(H), and
+        // therefore H.
+        let Self(h0, h1, h2, h3) = *self;
+        let (x0, x1, x2, x3) = crate::inv_areion512(h0, h1, h2, h3);
+        *self = Self(xor(x0, h0), xor(x1, h1), xor(x2, h2), xor(x3, h3));
+    }
 }
 
+/// The Areion512-MMO core. If `FINAL` is true, the final chaining value is passed through an
+/// output transformation, making the hash resistant to length-extension attacks.
 #[derive(Debug, Default, Clone)]
-pub struct Core {
+pub struct Core<const FINAL: bool> {
     state: State,
     block_len: u128,
 }
 
-impl HashMarker for Core {}
+impl<const FINAL: bool> HashMarker for Core<FINAL> {}
 
-impl BlockSizeUser for Core {
+impl<const FINAL: bool> BlockSizeUser for Core<FINAL> {
     type BlockSize = U64;
 }
 
-impl BufferKindUser for Core {
+impl<const FINAL: bool> BufferKindUser for Core<FINAL> {
     type BufferKind = Eager;
 }
 
-impl OutputSizeUser for Core {
+impl<const FINAL: bool> OutputSizeUser for Core<FINAL> {
     type OutputSize = U64;
 }
 
-impl UpdateCore for Core {
+impl<const FINAL: bool> UpdateCore for Core<FINAL> {
     #[inline]
     fn update_blocks(&mut self, blocks: &[Block<Self>]) {
         self.block_len += blocks.len() as u128;
@@ -82,11 +94,14 @@ impl UpdateCore for Core {
     }
 }
 
-impl FixedOutputCore for Core {
+impl<const FINAL: bool> FixedOutputCore for Core<FINAL> {
     fn finalize_fixed_core(&mut self, buffer: &mut Buffer<Self>, out: &mut Output<Self>) {
         let bs = Self::BlockSize::U64 as u128;
         let bit_len = 8 * (buffer.get_pos() as u128 + bs * self.block_len);
         buffer.len128_padding_be(bit_len, |b| self.state.compress(slice::from_ref(b)));
+        if FINAL {
+            self.state.finalize();
+        }
 
         store(&mut out[..16], self.state.0);
         store(&mut out[16..32], self.state.1);
@@ -95,27 +110,31 @@ impl FixedOutputCore for Core {
     }
 }
 
-impl Reset for Core {
+impl<const FINAL: bool> Reset for Core<FINAL> {
     #[inline]
     fn reset(&mut self) {
         *self = Default::default();
     }
 }
 
-impl AlgorithmName for Core {
+impl<const FINAL: bool> AlgorithmName for Core<FINAL> {
     #[inline]
     fn write_alg_name(f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("Areion512-MMO")
+        f.write_str(if FINAL { "Areion512-MMO-F" } else { "Areion512-MMO" })
     }
 }
 
-pub type Areion512Mmo = CoreWrapper<Core>;
+pub type Areion512Mmo = CoreWrapper<Core<false>>;
+pub type Areion512MmoF = CoreWrapper<Core<true>>;
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::tests::hex_fmt;
+
     use digest::Digest;
+    use expect_test::expect;
     use quickcheck_macros::quickcheck;
 
     #[quickcheck]
@@ -125,6 +144,70 @@ mod tests {
         aa == bb || a != b
     }
 
+    #[quickcheck]
+    fn finalized_different_inputs_yield_different_digests(a: Vec<u8>, b: Vec<u8>) -> bool {
+        let aa = Areion512MmoF::new().chain_update(&a).finalize();
+        let bb = Areion512MmoF::new().chain_update(&b).finalize();
+        aa == bb || a != b
+    }
+
+    /// Resumes hashing from a digest of `m`, assuming it's the chaining value after `blocks`
+    /// padded blocks.
+    fn extend<const FINAL: bool>(digest: &[u8], blocks: u128, ext: &[u8]) -> Vec<u8> {
+        let core = Core::<FINAL> {
+            state: State(
+                load(&digest[..16]),
+                load(&digest[16..32]),
+                load(&digest[32..48]),
+                load(&digest[48..]),
+            ),
+            block_len: blocks,
+        };
+        CoreWrapper::from_core(core).chain_update(ext).finalize().to_vec()
+    }
+
+    #[test]
+    fn length_extension() {
+        let m = [0xaa; 40];
+        let mut padded = m.to_vec();
+        padded.push(0x80);
+        padded.resize(48, 0);
+        padded.extend_from_slice(&(8 * m.len() as u128).to_be_bytes());
+        padded.extend_from_slice(b"extension");
+
+        let digest = Areion512Mmo::new().chain_update(m).finalize();
+        assert_eq!(
+            extend::<false>(&digest, 1, b"extension"),
+            Areion512Mmo::new().chain_update(&padded).finalize().to_vec(),
+            "Areion512-MMO should be vulnerable to length extension"
+        );
+
+        let digest = Areion512MmoF::new().chain_update(m).finalize();
+        assert_ne!(
+            extend::<true>(&digest, 1, b"extension"),
+            Areion512MmoF::new().chain_update(&padded).finalize().to_vec(),
+            "Areion512-MMO-F should not be vulnerable to length extension"
+        );
+    }
+
+    #[test]
+    fn areion512_mmo_f_test_vector() {
+        let data = hex!(
+            "
+            00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f
+            10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f
+            20 21 22 23 24 25 26 27 28 29 2a 2b 2c 2d 2e 2f
+            30 31 32 33 34 35 36 37 38 39 3a 3b 3c 3d 3e 3f"
+        );
+
+        expect![[r#"
+            3c 92 0d e2 6f 17 26 2a 5c 23 35 53 be 71 16 1d
+            76 14 69 55 47 e1 d2 26 dc 3c 86 63 75 62 34 93
+            d2 c9 04 67 a9 a3 b8 96 37 1c 96 a9 93 e0 24 56
+            b1 36 c0 11 01 4e 3a ac c4 62 18 3a b7 66 ca 2e"#]]
+        .assert_eq(&hex_fmt(&Areion512MmoF::default().chain_update(data).finalize()));
+    }
+
     #[test]
     fn fuzz() {
         bolero::check!().with_type::<Vec<u8>>().for_each(|input| {
@@ -0,0 +1,348 @@
+use core::marker::PhantomData;
+
+use crate::intrinsics::*;
+use crate::mask::Mask512 as Mask;
+
+use aead::consts::{U0, U16, U32};
+use aead::generic_array::{ArrayLength, GenericArray};
+use aead::{AeadCore, AeadInPlace, Key, KeyInit, KeySizeUser, Nonce, Tag};
+use subtle::ConstantTimeEq;
+
+const BLOCK_LEN: usize = 64;
+
+/// Returns the tweakable Even-Mansour encryption of `block` with the mask `delta`:
+/// `E(δ, X) = P(X ^ δ) ^ δ`.
+fn encrypt_block(delta: Mask, block: &mut [u8; BLOCK_LEN]) {
+    let (d0, d1, d2, d3) = (delta.block(0), delta.block(1), delta.block(2), delta.block(3));
+    let (x0, x1, x2, x3) = crate::areion512(
+        xor(load(&block[..16]), d0),
+        xor(load(&block[16..32]), d1),
+        xor(load(&block[32..48]), d2),
+        xor(load(&block[48..]), d3),
+    );
+    store(&mut block[..16], xor(x0, d0));
+    store(&mut block[16..32], xor(x1, d1));
+    store(&mut block[32..48], xor(x2, d2));
+    store(&mut block[48..], xor(x3, d3));
+}
+
+/// The inverse of [`encrypt_block`]: `D(δ, Y) = P^-1(Y ^ δ) ^ δ`.
+fn decrypt_block(delta: Mask, block: &mut [u8; BLOCK_LEN]) {
+    let (d0, d1, d2, d3) = (delta.block(0), delta.block(1), delta.block(2), delta.block(3));
+    let (x0, x1, x2, x3) = crate::inv_areion512(
+        xor(load(&block[..16]), d0),
+        xor(load(&block[16..32]), d1),
+        xor(load(&block[32..48]), d2),
+        xor(load(&block[48..]), d3),
+    );
+    store(&mut block[..16], xor(x0, d0));
+    store(&mut block[16..32], xor(x1, d1));
+    store(&mut block[32..48], xor(x2, d2));
+    store(&mut block[48..], xor(x3, d3));
+}
+
+fn xor_in(acc: &mut [u8], block: &[u8]) {
+    for (a, b) in acc.iter_mut().zip(block) {
+        *a ^= b;
+    }
+}
+
+/// Pads a partial block with a single set bit followed by zeros.
+fn pad(partial: &[u8]) -> [u8; BLOCK_LEN] {
+    let mut block = [0u8; BLOCK_LEN];
+    block[..partial.len()].copy_from_slice(partial);
+    block[partial.len()] = 0x80;
+    block
+}
+
+/// A key or nonce size supported by [`Areion512Opp`]: either 128 or 256 bits.
+pub trait OppSize: ArrayLength<u8> + private::Sealed {}
+
+impl OppSize for U16 {}
+impl OppSize for U32 {}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for super::U16 {}
+    impl Sealed for super::U32 {}
+}
+
+/// Areion512-OPP, an authenticated cipher using the Offset Public Permutation mode with the
+/// Areion512 permutation, with 128- or 256-bit keys and nonces and 128-bit tags.
+#[derive(Clone)]
+pub struct Areion512Opp<KeySize: OppSize = U32, NonceSize: OppSize = U16> {
+    key: GenericArray<u8, KeySize>,
+    _nonce_size: PhantomData<NonceSize>,
+}
+
+impl<K: OppSize, N: OppSize> Areion512Opp<K, N> {
+    /// Returns the initial mask `L = P(N || 0* || K)`.
+    fn init(&self, nonce: &Nonce<Self>) -> Mask {
+        let mut block = [0u8; BLOCK_LEN];
+        block[..N::USIZE].copy_from_slice(nonce);
+        block[BLOCK_LEN - K::USIZE..].copy_from_slice(&self.key);
+        let (x0, x1, x2, x3) = crate::areion512(
+            load(&block[..16]),
+            load(&block[16..32]),
+            load(&block[32..48]),
+            load(&block[48..]),
+        );
+        store(&mut block[..16], x0);
+        store(&mut block[16..32], x1);
+        store(&mut block[32..48], x2);
+        store(&mut block[48..], x3);
+        Mask::from_bytes(&block)
+    }
+
+    /// Absorbs the associated data, using the masks `δ(i, 0, 0)` for full blocks and `δ(a, 0, 1)`
+    /// for a final partial block.
+    fn absorb(l: Mask, ad: &[u8]) -> [u8; BLOCK_LEN] {
+        let mut acc = [0u8; BLOCK_LEN];
+        let mut delta = l;
+        let mut blocks = ad.chunks_exact(BLOCK_LEN);
+        for block in &mut blocks {
+            let mut block: [u8; BLOCK_LEN] = block.try_into().expect("should be a full block");
+            encrypt_block(delta, &mut block);
+            xor_in(&mut acc, &block);
+            delta = delta.phi();
+        }
+
+        let partial = blocks.remainder();
+        if !partial.is_empty() {
+            let mut block = pad(partial);
+            encrypt_block(delta.phi_2(), &mut block);
+            xor_in(&mut acc, &block);
+        }
+        acc
+    }
+
+    /// Encrypts or decrypts the message in place, using the masks `δ(i, 1, 0)` for full blocks and
+    /// `δ(m, 1, 1)` to generate a keystream for a final partial block. Returns the checksum of the
+    /// plaintext and the mask for the tag.
+    fn crypt(l: Mask, buffer: &mut [u8], encrypt: bool) -> ([u8; BLOCK_LEN], Mask) {
+        let mut sigma = [0u8; BLOCK_LEN];
+        let mut delta = l.phi_1();
+        let mut blocks = buffer.chunks_exact_mut(BLOCK_LEN);
+        for block in &mut blocks {
+            let block: &mut [u8; BLOCK_LEN] = block.try_into().expect("should be a full block");
+            if encrypt {
+                xor_in(&mut sigma, block);
+                encrypt_block(delta, block);
+            } else {
+                decrypt_block(delta, block);
+                xor_in(&mut sigma, block);
+            }
+            delta = delta.phi();
+        }
+
+        let partial = blocks.into_remainder();
+        if partial.is_empty() {
+            return (sigma, delta.phi_1());
+        }
+
+        let mut keystream = [0u8; BLOCK_LEN];
+        encrypt_block(delta.phi_2(), &mut keystream);
+        if encrypt {
+            xor_in(&mut sigma, &pad(partial));
+        }
+        xor_in(partial, &keystream);
+        if !encrypt {
+            xor_in(&mut sigma, &pad(partial));
+        }
+        (sigma, delta.phi_1().phi_2())
+    }
+
+    /// Calculates the tag `T = E(δ(m, 2, l), Σ) ^ A`.
+    fn tag(mut sigma: [u8; BLOCK_LEN], delta: Mask, ad: [u8; BLOCK_LEN]) -> Tag<Self> {
+        encrypt_block(delta, &mut sigma);
+        xor_in(&mut sigma, &ad);
+        GenericArray::clone_from_slice(&sigma[..16])
+    }
+}
+
+impl<K: OppSize, N: OppSize> KeySizeUser for Areion512Opp<K, N> {
+    type KeySize = K;
+}
+
+impl<K: OppSize, N: OppSize> KeyInit for Areion512Opp<K, N> {
+    fn new(key: &Key<Self>) -> Self {
+        Areion512Opp { key: key.clone(), _nonce_size: PhantomData }
+    }
+}
+
+impl<K: OppSize, N: OppSize> AeadCore for Areion512Opp<K, N> {
+    type NonceSize = N;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+impl<K: OppSize, N: OppSize> AeadInPlace for Areion512Opp<K, N> {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> aead::Result<Tag<Self>> {
+        let l = self.init(nonce);
+        let ad = Self::absorb(l, associated_data);
+        let (sigma, delta) = Self::crypt(l, buffer, true);
+        Ok(Self::tag(sigma, delta, ad))
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> aead::Result<()> {
+        let l = self.init(nonce);
+        let ad = Self::absorb(l, associated_data);
+        let (sigma, delta) = Self::crypt(l, buffer, false);
+        if Self::tag(sigma, delta, ad).ct_eq(tag).into() {
+            Ok(())
+        } else {
+            // Don't release unverified plaintext.
+            buffer.fill(0);
+            Err(aead::Error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    fn seal<K: OppSize, N: OppSize>(
+        key: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        pt: &[u8],
+    ) -> (Vec<u8>, Vec<u8>) {
+        let cipher = Areion512Opp::<K, N>::new_from_slice(key).expect("should be a valid key");
+        let mut ct = pt.to_vec();
+        let tag = cipher
+            .encrypt_in_place_detached(GenericArray::from_slice(nonce), ad, &mut ct)
+            .expect("should encrypt");
+        (ct, tag.to_vec())
+    }
+
+    fn open<K: OppSize, N: OppSize>(
+        key: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        ct: &[u8],
+        tag: &[u8],
+    ) -> Option<Vec<u8>> {
+        let cipher = Areion512Opp::<K, N>::new_from_slice(key).expect("should be a valid key");
+        let mut pt = ct.to_vec();
+        cipher
+            .decrypt_in_place_detached(
+                GenericArray::from_slice(nonce),
+                ad,
+                &mut pt,
+                GenericArray::from_slice(tag),
+            )
+            .ok()
+            .map(|_| pt)
+    }
+
+    #[test]
+    fn test_vector() {
+        let key = (0..32).collect::<Vec<u8>>();
+        let nonce = (32..48).collect::<Vec<u8>>();
+        let ad = b"header";
+        let pt = (0..100).collect::<Vec<u8>>();
+        let (ct, tag) = seal::<U32, U16>(&key, &nonce, ad, &pt);
+        expect![[r#"
+            73 fb fe 5f a7 98 92 38 23 a7 25 56 69 84 87 70
+            ca da ee 93 96 f1 f8 6f 13 43 86 e7 28 2f 42 19
+            dc 24 bc 6c 82 c0 b1 7b 64 27 3f 99 df 7a d0 3b
+            12 d9 0f 71 dd 00 45 fd 08 04 f1 06 8c 1f a9 18
+            22 8f be 32 41 aa 51 53 22 60 47 6a 6b 9a d5 49
+            7d 90 5e 35 a6 f3 bb f8 c4 80 7b c3 44 75 37 ea
+            be a0 cd c6"#]]
+        .assert_eq(&hex_fmt(&ct));
+        expect!["b6 61 bb cd 62 f6 47 23 e9 e0 d3 a1 2d be 2d 27"].assert_eq(&hex_fmt(&tag));
+    }
+
+    #[test]
+    fn test_vector_128() {
+        let key = (0..16).collect::<Vec<u8>>();
+        let nonce = (16..32).collect::<Vec<u8>>();
+        let (ct, tag) = seal::<U16, U16>(&key, &nonce, b"", &[0u8; 64]);
+        expect![[r#"
+            85 a8 98 cc 9e aa 9e cd 34 8f b6 24 8b c6 35 a9
+            52 0a 52 01 b7 7c e5 d5 ea f3 21 d6 0b cd b1 76
+            ae 4e dd 36 ec f9 ec 0f a2 2d f3 26 6a 1a ee 92
+            eb 07 71 51 c7 25 42 1f 69 ee 6d 81 40 0a 15 41"#]]
+        .assert_eq(&hex_fmt(&ct));
+        expect!["a2 15 fe e0 da bd 5e 74 1f 3d d0 a2 a7 5b 33 03"].assert_eq(&hex_fmt(&tag));
+    }
+
+    #[test]
+    fn test_vector_256() {
+        let key = (0..32).collect::<Vec<u8>>();
+        let nonce = (32..64).collect::<Vec<u8>>();
+        let (ct, tag) = seal::<U32, U32>(&key, &nonce, &[0u8; 65], b"");
+        assert!(ct.is_empty());
+        expect!["95 cd fc 56 fe 6e 41 01 e5 b9 e3 1b 08 e6 c8 99"].assert_eq(&hex_fmt(&tag));
+    }
+
+    #[test]
+    fn tampering() {
+        let (key, nonce) = ([7u8; 32], [9u8; 16]);
+        for len in [0, 1, 63, 64, 65, 200] {
+            let pt = vec![0xcc; len];
+            let (ct, tag) = seal::<U32, U16>(&key, &nonce, b"ad", &pt);
+            assert_eq!(open::<U32, U16>(&key, &nonce, b"ad", &ct, &tag), Some(pt));
+
+            assert_eq!(open::<U32, U16>(&key, &nonce, b"AD", &ct, &tag), None, "ad, len={len}");
+            assert_eq!(open::<U32, U16>(&key, &[8; 16], b"ad", &ct, &tag), None, "nonce");
+            assert_eq!(open::<U32, U16>(&[6; 32], &nonce, b"ad", &ct, &tag), None, "key");
+            for i in 0..ct.len() {
+                let mut ct = ct.clone();
+                ct[i] ^= 1;
+                assert_eq!(open::<U32, U16>(&key, &nonce, b"ad", &ct, &tag), None, "ct[{i}]");
+            }
+            for i in 0..tag.len() {
+                let mut tag = tag.clone();
+                tag[i] ^= 1;
+                assert_eq!(open::<U32, U16>(&key, &nonce, b"ad", &ct, &tag), None, "tag[{i}]");
+            }
+        }
+    }
+
+    #[test]
+    fn failed_decryption_clears_buffer() {
+        let cipher = Areion512Opp::<U32, U16>::new(&[7u8; 32].into());
+        let nonce = [9u8; 16].into();
+        let mut buf = vec![0xcc; 100];
+        let tag = cipher.encrypt_in_place_detached(&nonce, b"", &mut buf).expect("should encrypt");
+        assert!(cipher.decrypt_in_place_detached(&nonce, b"x", &mut buf, &tag).is_err());
+        assert_eq!(buf, vec![0; 100]);
+    }
+
+    #[quickcheck]
+    fn round_trip(key: Vec<u8>, nonce: Vec<u8>, ad: Vec<u8>, pt: Vec<u8>) -> bool {
+        let mut key = key;
+        key.resize(32, 0);
+        let mut nonce = nonce;
+        nonce.resize(16, 0);
+        let (ct, tag) = seal::<U32, U16>(&key, &nonce, &ad, &pt);
+        open::<U32, U16>(&key, &nonce, &ad, &ct, &tag) == Some(pt)
+    }
+
+    #[test]
+    fn fuzz() {
+        bolero::check!().with_type::<(Vec<u8>, Vec<u8>, [u8; 16])>().for_each(|(ad, ct, tag)| {
+            assert_eq!(open::<U32, U16>(&[0; 32], &[0; 16], ad, ct, tag), None);
+        });
+    }
+}
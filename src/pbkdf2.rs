@@ -0,0 +1,463 @@
+//! [PBKDF2](https://www.rfc-editor.org/rfc/rfc8018#section-5.2) with HMAC over Areion512-MD or
+//! Areion512-MMO.
+//!
+//! [`pbkdf2_hmac_areion512md`] and [`pbkdf2_hmac_areion512mmo`] derive keys of arbitrary length.
+//! [`Pbkdf2`] implements [`PasswordHasher`] and
+//! [`PasswordVerifier`](password_hash::PasswordVerifier), using the
+//! [PHC string format](https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md) with
+//! the `pbkdf2-areion512md` and `pbkdf2-areion512mmo` algorithm identifiers and the iteration count
+//! `i` and output length `l` as parameters:
+//!
+//! ```text
+//! $pbkdf2-areion512md$i=600000,l=32$<salt>$<hash>
+//! ```
+
+use core::fmt;
+
+use crate::{Areion512Md, Areion512Mmo};
+
+use digest::core_api::BlockSizeUser;
+use digest::typenum::Unsigned;
+use digest::Digest;
+use password_hash::errors::InvalidValue;
+use password_hash::{
+    Decimal, Error, Ident, Output, ParamsString, PasswordHash, PasswordHasher, Result, Salt,
+};
+
+/// The PHC algorithm identifier for PBKDF2 with HMAC-Areion512-MD.
+pub const PBKDF2_AREION512MD: Ident<'static> = Ident::new_unwrap("pbkdf2-areion512md");
+
+/// The PHC algorithm identifier for PBKDF2 with HMAC-Areion512-MMO.
+pub const PBKDF2_AREION512MMO: Ident<'static> = Ident::new_unwrap("pbkdf2-areion512mmo");
+
+/// Fills `out` with a key derived from the password and salt with PBKDF2-HMAC-Areion512-MD.
+pub fn pbkdf2_hmac_areion512md(password: &[u8], salt: &[u8], rounds: u32, out: &mut [u8]) {
+    pbkdf2::<Areion512Md>(password, salt, rounds, out);
+}
+
+/// Fills `out` with a key derived from the password and salt with PBKDF2-HMAC-Areion512-MMO.
+pub fn pbkdf2_hmac_areion512mmo(password: &[u8], salt: &[u8], rounds: u32, out: &mut [u8]) {
+    pbkdf2::<Areion512Mmo>(password, salt, rounds, out);
+}
+
+fn pbkdf2<D: Digest + BlockSizeUser + Clone>(
+    password: &[u8],
+    salt: &[u8],
+    rounds: u32,
+    out: &mut [u8],
+) {
+    let prf = Hmac::<D>::new(password);
+    for (i, chunk) in out.chunks_mut(<D as Digest>::output_size()).enumerate() {
+        // U_1 = PRF(P, S || INT(i))
+        let mut u = prf.mac(&[salt, &(i as u32 + 1).to_be_bytes()]);
+        chunk.copy_from_slice(&u[..chunk.len()]);
+
+        // U_j = PRF(P, U_{j-1})
+        for _ in 1..rounds {
+            u = prf.mac(&[&u]);
+            chunk.iter_mut().zip(u.iter()).for_each(|(c, u)| *c ^= u);
+        }
+    }
+}
+
+/// HMAC with the inner and outer states precomputed from the key.
+pub(crate) struct Hmac<D> {
+    inner: D,
+    outer: D,
+}
+
+impl<D: Digest + BlockSizeUser + Clone> Hmac<D> {
+    pub(crate) fn new(key: &[u8]) -> Self {
+        let mut k = vec![0u8; D::BlockSize::USIZE];
+        if key.len() > k.len() {
+            let h = D::digest(key);
+            k[..h.len()].copy_from_slice(&h);
+        } else {
+            k[..key.len()].copy_from_slice(key);
+        }
+
+        let (mut inner, mut outer) = (D::new(), D::new());
+        inner.update(k.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+        outer.update(k.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>());
+        Hmac { inner, outer }
+    }
+
+    pub(crate) fn mac(&self, message: &[&[u8]]) -> digest::Output<D> {
+        let mut inner = self.inner.clone();
+        for m in message {
+            inner.update(m);
+        }
+        let mut outer = self.outer.clone();
+        outer.update(inner.finalize());
+        outer.finalize()
+    }
+}
+
+/// The hash function underlying a [`Pbkdf2`] password hasher.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// PBKDF2-HMAC-Areion512-MD.
+    #[default]
+    Areion512Md,
+    /// PBKDF2-HMAC-Areion512-MMO.
+    Areion512Mmo,
+}
+
+impl Algorithm {
+    /// Returns the PHC algorithm identifier.
+    pub fn ident(&self) -> Ident<'static> {
+        match self {
+            Algorithm::Areion512Md => PBKDF2_AREION512MD,
+            Algorithm::Areion512Mmo => PBKDF2_AREION512MMO,
+        }
+    }
+}
+
+impl<'a> TryFrom<Ident<'a>> for Algorithm {
+    type Error = Error;
+
+    fn try_from(ident: Ident<'a>) -> Result<Self> {
+        match ident {
+            PBKDF2_AREION512MD => Ok(Algorithm::Areion512Md),
+            PBKDF2_AREION512MMO => Ok(Algorithm::Areion512Mmo),
+            _ => Err(Error::Algorithm),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.ident().as_str())
+    }
+}
+
+/// The parameters for [`Pbkdf2`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Params {
+    /// The number of iterations.
+    pub rounds: u32,
+    /// The output length, in bytes.
+    pub output_len: usize,
+}
+
+impl Params {
+    /// The default number of iterations.
+    pub const DEFAULT_ROUNDS: u32 = 600_000;
+
+    /// The default output length, in bytes.
+    pub const DEFAULT_OUTPUT_LEN: usize = 32;
+
+    fn validate(&self) -> Result<()> {
+        if self.rounds < 1 {
+            return Err(InvalidValue::TooShort.param_error());
+        }
+        if self.output_len < Output::MIN_LENGTH {
+            return Err(Error::OutputSize {
+                provided: core::cmp::Ordering::Less,
+                expected: Output::MIN_LENGTH,
+            });
+        }
+        if self.output_len > Output::MAX_LENGTH {
+            return Err(Error::OutputSize {
+                provided: core::cmp::Ordering::Greater,
+                expected: Output::MAX_LENGTH,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params { rounds: Self::DEFAULT_ROUNDS, output_len: Self::DEFAULT_OUTPUT_LEN }
+    }
+}
+
+impl<'a> TryFrom<&'a PasswordHash<'a>> for Params {
+    type Error = Error;
+
+    fn try_from(hash: &'a PasswordHash<'a>) -> Result<Self> {
+        let mut params = Params::default();
+        for (ident, value) in hash.params.iter() {
+            match ident.as_str() {
+                "i" => params.rounds = value.decimal()?,
+                "l" => params.output_len = value.decimal()? as usize,
+                _ => return Err(Error::ParamNameInvalid),
+            }
+        }
+        if let Some(hash) = &hash.hash {
+            if hash.len() != params.output_len {
+                return Err(Error::OutputSize {
+                    provided: hash.len().cmp(&params.output_len),
+                    expected: params.output_len,
+                });
+            }
+        }
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+impl TryFrom<Params> for ParamsString {
+    type Error = Error;
+
+    fn try_from(params: Params) -> Result<Self> {
+        let mut s = ParamsString::new();
+        s.add_decimal("i", params.rounds)?;
+        s.add_decimal("l", params.output_len as Decimal)?;
+        Ok(s)
+    }
+}
+
+/// A PBKDF2 password hasher with HMAC over Areion512-MD or Areion512-MMO.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Pbkdf2 {
+    algorithm: Algorithm,
+    params: Params,
+}
+
+impl Pbkdf2 {
+    /// Creates a password hasher with the given algorithm and parameters.
+    pub fn new(algorithm: Algorithm, params: Params) -> Pbkdf2 {
+        Pbkdf2 { algorithm, params }
+    }
+}
+
+impl PasswordHasher for Pbkdf2 {
+    type Params = Params;
+
+    /// Hashes the password with the hasher's algorithm and parameters, rather than the defaults.
+    fn hash_password<'a>(
+        &self,
+        password: &[u8],
+        salt: impl Into<Salt<'a>>,
+    ) -> Result<PasswordHash<'a>> {
+        self.hash_password_customized(
+            password,
+            Some(self.algorithm.ident()),
+            None,
+            self.params,
+            salt,
+        )
+    }
+
+    fn hash_password_customized<'a>(
+        &self,
+        password: &[u8],
+        algorithm: Option<Ident<'a>>,
+        version: Option<Decimal>,
+        params: Params,
+        salt: impl Into<Salt<'a>>,
+    ) -> Result<PasswordHash<'a>> {
+        let algorithm = algorithm.map_or(Ok(self.algorithm), Algorithm::try_from)?;
+        if version.is_some() {
+            return Err(Error::Version);
+        }
+        params.validate()?;
+
+        let salt = salt.into();
+        let mut salt_buf = [0u8; Salt::MAX_LENGTH];
+        let salt_bytes = salt.decode_b64(&mut salt_buf)?;
+
+        let hash = Output::init_with(params.output_len, |out| {
+            match algorithm {
+                Algorithm::Areion512Md => {
+                    pbkdf2_hmac_areion512md(password, salt_bytes, params.rounds, out)
+                }
+                Algorithm::Areion512Mmo => {
+                    pbkdf2_hmac_areion512mmo(password, salt_bytes, params.rounds, out)
+                }
+            }
+            Ok(())
+        })?;
+
+        Ok(PasswordHash {
+            algorithm: algorithm.ident(),
+            version: None,
+            params: params.try_into()?,
+            salt: Some(salt),
+            hash: Some(hash),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+    use password_hash::{PasswordVerifier, SaltString};
+
+    fn md(password: &[u8], salt: &[u8], rounds: u32, len: usize) -> String {
+        let mut out = vec![0u8; len];
+        pbkdf2_hmac_areion512md(password, salt, rounds, &mut out);
+        hex_fmt(&out)
+    }
+
+    fn mmo(password: &[u8], salt: &[u8], rounds: u32, len: usize) -> String {
+        let mut out = vec![0u8; len];
+        pbkdf2_hmac_areion512mmo(password, salt, rounds, &mut out);
+        hex_fmt(&out)
+    }
+
+    #[test]
+    fn areion512md_test_vectors() {
+        // The inputs from RFC 6070.
+        expect![[r#"
+            ce 18 73 1e 6e 04 77 f7 af cf f4 ec 0f 28 43 b2
+            29 f7 c4 b1 0d ad 52 71 6f 7e eb e4 b9 5e 20 63"#]]
+        .assert_eq(&md(b"password", b"salt", 1, 32));
+        expect![[r#"
+            84 19 75 62 37 7c 7f 0a 7f 3c 55 4a 43 18 66 e0
+            91 26 73 d3 4a b0 06 2a 9b 57 57 11 9e a4 96 1a"#]]
+        .assert_eq(&md(b"password", b"salt", 2, 32));
+        expect![[r#"
+            c2 e5 af a1 07 4c cd 89 df 4b 39 11 dd 7b 5e 7c
+            29 a0 f9 be 19 27 31 79 a2 60 e0 2b d0 23 7a 2d"#]]
+        .assert_eq(&md(b"password", b"salt", 4096, 32));
+        expect![[r#"
+            8a 08 1e 02 ef 52 fa 85 49 4c b7 e0 ae c4 25 e6
+            b0 2c 6c 70 6f ea f8 28 db 2e c8 e8 8c c5 d2 5e
+            b0 bc 06 e4 28 18 3d c3"#]]
+        .assert_eq(&md(
+            b"passwordPASSWORDpassword",
+            b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+            4096,
+            40,
+        ));
+        expect!["f7 f3 89 61 08 6b 59 cf 4c 57 a3 74 a7 55 a5 bc"].assert_eq(&md(
+            b"pass\0word",
+            b"sa\0lt",
+            4096,
+            16,
+        ));
+    }
+
+    #[test]
+    fn areion512mmo_test_vectors() {
+        // The inputs from RFC 6070.
+        expect![[r#"
+            ea 28 a1 e6 c9 14 ef cc d1 cc de 64 8c d9 66 60
+            4b 7b b5 94 6e 33 77 b5 97 f9 d1 81 83 2b d1 06
+            0c 8b 1b d6 22 b0 2e 18 57 28 0d 52 26 6d 5c 73
+            9a 0b c4 fc 86 ee bc 50 ac 78 cc df b0 1e dd 36"#]]
+        .assert_eq(&mmo(b"password", b"salt", 1, 64));
+        expect![[r#"
+            98 08 2e 7a e5 51 62 91 70 6d 9f aa 15 c1 a1 9a
+            ab 71 bc 21 e6 0e 6e d4 b2 b2 60 60 11 2d 84 c9
+            de 39 5e 06 fc 3e 2e 52 15 86 40 bb 64 d4 03 50
+            38 1f 15 07 65 ea 83 58 ab 4a 63 af 9d 23 62 2c"#]]
+        .assert_eq(&mmo(b"password", b"salt", 2, 64));
+        expect![[r#"
+            9a 65 03 ff 91 c1 36 c8 a8 d4 2a 20 12 b4 e1 96
+            ac 34 0a b3 7b df 16 85 14 6d 78 41 d5 84 4c 4a
+            26 52 13 bb 0f 3d 73 27 07 45 8d b1 a7 c6 44 f2
+            24 ce ef 27 44 a4 df 8d 5d e5 be 2e ab 6b 26 08"#]]
+        .assert_eq(&mmo(b"password", b"salt", 4096, 64));
+        expect![[r#"
+            ab 73 63 21 67 09 0c 6b 2c 85 79 2e 85 09 95 bd
+            04 af ed cc 3b c1 fc cc 05 de 0b c7 85 45 59 31
+            1f 87 72 11 5f a4 e3 bc 61 b5 f1 b6 29 ee bd 1a
+            a8 a6 17 9f 45 6b 59 8a 98 0c 31 ca 96 2c ee dd
+            e2 cf dd 96 08 bb e7 12 3d 00 08 60 03 28 2a 9b"#]]
+        .assert_eq(&mmo(
+            b"passwordPASSWORDpassword",
+            b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+            4096,
+            80,
+        ));
+        expect!["c3 12 ac b1 13 59 b6 88 ff 3d c8 ac 48 c5 b6 c0"].assert_eq(&mmo(
+            b"pass\0word",
+            b"sa\0lt",
+            4096,
+            16,
+        ));
+    }
+
+    #[test]
+    fn sha256_test_vectors() {
+        // The generic construction matches the published PBKDF2-HMAC-SHA256 outputs for the RFC
+        // 6070 inputs.
+        let mut out = [0u8; 32];
+        pbkdf2::<sha2::Sha256>(b"password", b"salt", 1, &mut out);
+        assert_eq!(
+            hex::encode(out),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+        pbkdf2::<sha2::Sha256>(b"password", b"salt", 4096, &mut out);
+        assert_eq!(
+            hex::encode(out),
+            "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"
+        );
+    }
+
+    #[test]
+    fn outputs_are_prefixes() {
+        assert!(md(b"password", b"salt", 2, 100).starts_with(&md(b"password", b"salt", 2, 40)));
+        assert!(mmo(b"password", b"salt", 2, 100).starts_with(&mmo(b"password", b"salt", 2, 40)));
+    }
+
+    #[test]
+    fn long_passwords_are_hashed() {
+        // Passwords longer than the block size are replaced with their digest.
+        let password = [0x61; 33];
+        let digest = Areion512Md::digest(password);
+        assert_eq!(md(&password, b"salt", 2, 32), md(&digest, b"salt", 2, 32));
+    }
+
+    #[test]
+    fn phc_strings() {
+        let salt = SaltString::encode_b64(b"somesalt").expect("should be a valid salt");
+        for algorithm in [Algorithm::Areion512Md, Algorithm::Areion512Mmo] {
+            let hasher = Pbkdf2::new(algorithm, Params { rounds: 1000, output_len: 32 });
+            let hash = hasher.hash_password(b"password", &salt).expect("should hash").to_string();
+            let hash = PasswordHash::new(&hash).expect("should parse");
+            assert_eq!(hash.algorithm, algorithm.ident());
+
+            // The encoded algorithm and parameters are used for verification.
+            assert!(Pbkdf2::default().verify_password(b"password", &hash).is_ok());
+            assert_eq!(Pbkdf2::default().verify_password(b"passwore", &hash), Err(Error::Password));
+        }
+
+        let hasher = Pbkdf2::new(Algorithm::Areion512Md, Params { rounds: 1000, output_len: 32 });
+        let hash = hasher.hash_password(b"password", &salt).expect("should hash").to_string();
+        let (prefix, output) = hash.rsplit_once('$').expect("should have an output");
+        expect!["$pbkdf2-areion512md$i=1000,l=32$c29tZXNhbHQ"].assert_eq(prefix);
+        expect!["NVKOxgB3P1UPFAjS+5t4GttFAHS7r/+aQC8vsI6qJoc"].assert_eq(output);
+    }
+
+    #[test]
+    fn migrated_hashes() {
+        // A raw derived key stored with its salt and iteration count can be converted to a PHC
+        // string and verified.
+        let mut key = [0u8; 20];
+        pbkdf2_hmac_areion512mmo(b"password", b"legacysalt", 4096, &mut key);
+        let salt = SaltString::encode_b64(b"legacysalt").expect("should be a valid salt");
+        let hash = format!(
+            "$pbkdf2-areion512mmo$i=4096,l=20${}${}",
+            salt.as_str(),
+            Output::new(&key).expect("should be a valid output")
+        );
+        let hash = PasswordHash::new(&hash).expect("should parse");
+        assert!(Pbkdf2::default().verify_password(b"password", &hash).is_ok());
+    }
+
+    #[test]
+    fn invalid_phc_strings() {
+        let hasher = Pbkdf2::default();
+        let salt_and_hash = "$c29tZXNhbHQ$qOqIvWo3rl9c1ohwhWLPWIA23FK0pZnnzdBswjFDA3M";
+        for prefix in [
+            "$pbkdf2-sha256$i=1000,l=32",
+            "$pbkdf2-areion512md$v=1$i=1000,l=32",
+            "$pbkdf2-areion512md$i=0,l=32",
+            "$pbkdf2-areion512md$i=1000,l=16",
+            "$pbkdf2-areion512md$i=1000,x=1",
+        ] {
+            let s = format!("{prefix}{salt_and_hash}");
+            let hash = PasswordHash::new(&s).expect("should parse");
+            assert!(hasher.verify_password(b"password", &hash).is_err(), "{s}");
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use core::fmt;
+
+use crate::intrinsics::*;
+use crate::mask::Mask512;
+use crate::Areion512Em;
+
+use cipher::BlockEncrypt;
+use digest::block_buffer::Lazy;
+use digest::core_api::{
+    Block, BlockSizeUser, Buffer, BufferKindUser, CoreWrapper, FixedOutputCore, UpdateCore,
+};
+use digest::crypto_common::{AlgorithmName, Key, KeyInit, KeySizeUser};
+use digest::typenum::U64;
+use digest::{MacMarker, Output, OutputSizeUser, Reset};
+
+const LANES: usize = 4;
+
+/// The core of [`Areion512Pmac`].
+#[derive(Clone)]
+pub struct Areion512PmacCore {
+    em: Areion512Em,
+    l: Mask512,
+    delta: Mask512,
+    sigma: AesBlock4,
+}
+
+impl Areion512PmacCore {
+    #[inline]
+    fn e(&self, x: AesBlock4) -> AesBlock4 {
+        self.em.encrypt_words(x.0, x.1, x.2, x.3)
+    }
+}
+
+impl MacMarker for Areion512PmacCore {}
+
+impl BlockSizeUser for Areion512PmacCore {
+    type BlockSize = U64;
+}
+
+impl BufferKindUser for Areion512PmacCore {
+    type BufferKind = Lazy;
+}
+
+impl OutputSizeUser for Areion512PmacCore {
+    type OutputSize = U64;
+}
+
+impl KeySizeUser for Areion512PmacCore {
+    type KeySize = U64;
+}
+
+impl KeyInit for Areion512PmacCore {
+    fn new(key: &Key<Self>) -> Self {
+        let em = Areion512Em::new(key);
+        let mut l = Block::<Self>::default();
+        em.encrypt_block(&mut l);
+        let l = Mask512::from_bytes(&l);
+        Areion512PmacCore { em, l, delta: l, sigma: (zero(), zero(), zero(), zero()) }
+    }
+}
+
+impl UpdateCore for Areion512PmacCore {
+    #[inline]
+    fn update_blocks(&mut self, blocks: &[Block<Self>]) {
+        // The block encryptions are independent, allowing their AES instructions to be interleaved.
+        let mut lanes = blocks.chunks_exact(LANES);
+        for lanes in lanes.by_ref() {
+            let d0 = self.delta;
+            let d1 = d0.phi();
+            let d2 = d1.phi();
+            let d3 = d2.phi();
+            let y0 = self.e(xor4(load4(&lanes[0]), d0.blocks()));
+            let y1 = self.e(xor4(load4(&lanes[1]), d1.blocks()));
+            let y2 = self.e(xor4(load4(&lanes[2]), d2.blocks()));
+            let y3 = self.e(xor4(load4(&lanes[3]), d3.blocks()));
+            self.sigma = xor4(self.sigma, xor4(xor4(y0, y1), xor4(y2, y3)));
+            self.delta = d3.phi();
+        }
+
+        for block in lanes.remainder() {
+            self.sigma = xor4(self.sigma, self.e(xor4(load4(block), self.delta.blocks())));
+            self.delta = self.delta.phi();
+        }
+    }
+}
+
+impl FixedOutputCore for Areion512PmacCore {
+    fn finalize_fixed_core(&mut self, buffer: &mut Buffer<Self>, out: &mut Output<Self>) {
+        // The final block is XORed into the checksum unencrypted. A full final block is masked with
+        // (φ ^ 1)(L), and a padded final block with (φ^2 ^ φ ^ 1)(L).
+        let pos = buffer.get_pos();
+        let mut block = *buffer.pad_with_zeros();
+        let mask = if pos == block.len() {
+            self.l.phi_1()
+        } else {
+            block[pos] = 0x80;
+            self.l.phi_2()
+        };
+
+        let tag = self.e(xor4(xor4(self.sigma, load4(&block)), mask.blocks()));
+        store4(out, tag);
+    }
+}
+
+impl Reset for Areion512PmacCore {
+    #[inline]
+    fn reset(&mut self) {
+        self.delta = self.l;
+        self.sigma = (zero(), zero(), zero(), zero());
+    }
+}
+
+impl AlgorithmName for Areion512PmacCore {
+    fn write_alg_name(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-PMAC")
+    }
+}
+
+impl fmt::Debug for Areion512PmacCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-PMAC { ... }")
+    }
+}
+
+/// Areion512-PMAC, a parallelizable MAC in the style of
+/// [PMAC1](https://www.cs.ucdavis.edu/~rogaway/papers/pmac.pdf) over the Areion512-EM block cipher,
+/// with 512-bit keys and tags.
+///
+/// With `L = E_K(0)` and offsets `δ_i = φ^i(L)`, every block but the last is encrypted
+/// independently and the results are summed with the final block `M_m`:
+///
+/// ```text
+/// Σ = E_K(M_0 ^ δ_0) ^ ... ^ E_K(M_{m-1} ^ δ_{m-1}) ^ M_m
+/// T = E_K(Σ ^ (φ ^ 1)(L))              if |M_m| = 512
+/// T = E_K(Σ ^ 10* ^ (φ^2 ^ φ ^ 1)(L))  otherwise
+/// ```
+///
+/// Blocks are encrypted four at a time to take advantage of the pipelined AES instructions.
+pub type Areion512Pmac = CoreWrapper<Areion512PmacCore>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use digest::Mac;
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    fn pmac(key: &[u8], message: &[u8]) -> Vec<u8> {
+        let mut mac = <Areion512Pmac as Mac>::new_from_slice(key).expect("should be a valid key");
+        mac.update(message);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_vector() {
+        let key = (0..64).collect::<Vec<u8>>();
+        let message = (0..300).map(|i| i as u8).collect::<Vec<u8>>();
+        expect![[r#"
+            e5 74 75 8c 89 3d 1f d6 ca 2f ec 71 47 f8 18 e9
+            f3 a7 4e 88 1c 9e 2f 41 f8 ac 74 08 f7 3b e5 3c
+            aa e3 03 09 72 25 2e ab 29 10 0b a1 96 fc ec 44
+            1c 35 2c 4f 89 82 21 2d cd f9 1f a9 13 e4 f7 f7"#]]
+        .assert_eq(&hex_fmt(&pmac(&key, &message)));
+    }
+
+    #[test]
+    fn padding() {
+        let key = [7u8; 64];
+        let mut padded = vec![0xcc; 63];
+        padded.push(0x80);
+        let outputs = [
+            pmac(&key, b""),
+            pmac(&key, &[0x80]),
+            pmac(&key, &[0xcc; 63]),
+            pmac(&key, &padded),
+            pmac(&key, &[0xcc; 64]),
+            pmac(&key, &[0xcc; 128]),
+            pmac(&key, &[0; 64]),
+        ];
+        for (i, a) in outputs.iter().enumerate() {
+            assert!(!outputs[i + 1..].contains(a), "{i}");
+        }
+    }
+
+    #[test]
+    fn block_order() {
+        let key = [7u8; 64];
+        let (a, b) = ([0xaa; 64], [0xbb; 64]);
+        let ab = pmac(&key, &[a, b, [0; 64]].concat());
+        let ba = pmac(&key, &[b, a, [0; 64]].concat());
+        assert_ne!(ab, ba);
+    }
+
+    #[test]
+    fn verifies() {
+        let mut mac = <Areion512Pmac as Mac>::new_from_slice(&[7; 64]).expect("should be valid");
+        mac.update(b"message");
+        let tag = mac.clone().finalize().into_bytes();
+        assert!(mac.clone().verify_slice(&tag).is_ok());
+
+        Mac::reset(&mut mac);
+        mac.update(b"messagf");
+        assert!(mac.verify_slice(&tag).is_err());
+    }
+
+    #[test]
+    fn batched_blocks_match_single_blocks() {
+        let message = (0..64 * 11 + 5).map(|i| i as u8).collect::<Vec<u8>>();
+        let mut mac = <Areion512Pmac as Mac>::new_from_slice(&[7; 64]).expect("should be valid");
+        for block in message.chunks(64) {
+            mac.update(block);
+        }
+        assert_eq!(mac.finalize().into_bytes().to_vec(), pmac(&[7; 64], &message));
+    }
+
+    #[quickcheck]
+    fn incremental_updates_match_one_shot(message: Vec<u8>, splits: Vec<u16>) -> bool {
+        let mut mac = <Areion512Pmac as Mac>::new_from_slice(&[7; 64]).expect("should be valid");
+        let mut rest = message.as_slice();
+        for split in splits {
+            let (chunk, tail) = rest.split_at((split as usize).min(rest.len()));
+            mac.update(chunk);
+            rest = tail;
+        }
+        mac.update(rest);
+        mac.finalize().into_bytes().to_vec() == pmac(&[7; 64], &message)
+    }
+
+    #[quickcheck]
+    fn different_messages_yield_different_tags(a: Vec<u8>, b: Vec<u8>) -> bool {
+        (pmac(&[7; 64], &a) != pmac(&[7; 64], &b)) == (a != b)
+    }
+}
@@ -0,0 +1,335 @@
+//! A [Hashcash](http://www.hashcash.org/papers/hashcash.pdf)-style proof of work over Areion512-DM.
+//!
+//! A server issues a [`Challenge`] consisting of a 256-bit seed and a difficulty in bits. The
+//! client searches for a 128-bit nonce such that
+//!
+//! ```text
+//! DM(seed || T || nonce)
+//! ```
+//!
+//! begins with at least that many zero bits, where `DM` is Areion512-DM, `T` is the 16-byte string
+//! `"areion-pow-v1"` padded with zeros and ending in the difficulty, and the nonce is a
+//! little-endian integer. Finding a solution takes `2^bits` evaluations on average, and checking
+//! one takes a single permutation call. The solver permutes four nonces at a time, advancing them
+//! a round at a time so that their AES instructions overlap; see the `pow` group of the crate's
+//! benchmarks.
+//!
+//! Solutions are exchanged as [`Stamp`] strings of the form `areion1:<bits>:<seed>:<nonce>`, with
+//! the seed and nonce in lowercase hex and the nonce without leading zeros, so that each solution
+//! has exactly one encoding.
+//!
+//! A stamp proves that work was done for its challenge, not that it's fresh. Servers should derive
+//! seeds which expire (e.g. by MACing a client identifier and timestamp) and reject stamps which
+//! have already been redeemed.
+
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
+
+use crate::intrinsics::*;
+
+use rand_core::CryptoRngCore;
+
+/// The domain separation string, followed by the difficulty in its last byte.
+const DOMAIN: &[u8; 15] = b"areion-pow-v1\0\0";
+
+/// The stamp format version.
+const VERSION: &str = "areion1";
+
+/// The number of nonces evaluated at once.
+const LANES: usize = 4;
+
+/// An error returned when parsing or verifying a stamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowError {
+    /// The stamp string isn't in the `areion1:<bits>:<seed>:<nonce>` format.
+    Malformed,
+    /// The stamp is for a different challenge.
+    WrongChallenge,
+    /// The stamp's hash doesn't have enough leading zero bits.
+    InsufficientWork,
+}
+
+impl fmt::Display for PowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PowError::Malformed => "malformed stamp",
+            PowError::WrongChallenge => "stamp is for a different challenge",
+            PowError::InsufficientWork => "insufficient work",
+        })
+    }
+}
+
+impl std::error::Error for PowError {}
+
+/// A proof-of-work challenge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Challenge {
+    bits: u8,
+    seed: [u8; 32],
+}
+
+impl Challenge {
+    /// Creates a challenge requiring `bits` leading zero bits, with the given seed.
+    pub fn new(bits: u8, seed: [u8; 32]) -> Challenge {
+        Challenge { bits, seed }
+    }
+
+    /// Creates a challenge requiring `bits` leading zero bits, with a random seed.
+    pub fn generate(bits: u8, mut rng: impl CryptoRngCore) -> Challenge {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        Challenge { bits, seed }
+    }
+
+    /// Returns the number of leading zero bits required.
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// Returns the challenge's seed.
+    pub fn seed(&self) -> &[u8; 32] {
+        &self.seed
+    }
+
+    /// Finds the smallest nonce which solves the challenge.
+    ///
+    /// This takes `2^bits` evaluations on average, and effectively never returns for large
+    /// difficulties.
+    pub fn solve(&self) -> Stamp {
+        self.solve_range(0..u128::MAX).expect("should find a solution")
+    }
+
+    /// Finds the smallest nonce in `nonces` which solves the challenge, if any. Disjoint ranges
+    /// can be searched in parallel.
+    pub fn solve_range(&self, nonces: Range<u128>) -> Option<Stamp> {
+        // Candidates are filtered by the first 64 bits of their hashes, which are the high half of
+        // the first word of the permutation's output XORed with the seed.
+        let (s0, s1, t) = self.blocks();
+        let input = |nonce: u128| (s0, s1, t, from_u64(nonce as u64, (nonce >> 64) as u64));
+        let first_u64 = |(p0, ..): AesBlock4| high_u64(xor(p0, s0)).swap_bytes();
+        let check = |nonce: u128, first: u64| {
+            let stamp = Stamp { challenge: *self, nonce };
+            (first.leading_zeros() >= u32::from(self.bits).min(64)
+                && (self.bits <= 64 || stamp.leading_zeros() >= u32::from(self.bits)))
+            .then_some(stamp)
+        };
+
+        let mut nonce = nonces.start;
+        while nonces.end.saturating_sub(nonce) >= LANES as u128 {
+            let x: [AesBlock4; LANES] = core::array::from_fn(|i| input(nonce + i as u128));
+            let firsts = crate::areion512_par(x).map(first_u64);
+            let found =
+                firsts.into_iter().enumerate().find_map(|(i, f)| check(nonce + i as u128, f));
+            if let Some(stamp) = found {
+                return Some(stamp);
+            }
+            nonce += LANES as u128;
+        }
+        (nonce..nonces.end).find_map(|nonce| {
+            let (x0, x1, x2, x3) = input(nonce);
+            check(nonce, first_u64(crate::areion512(x0, x1, x2, x3)))
+        })
+    }
+
+    /// Verifies that the stamp solves this challenge.
+    pub fn verify(&self, stamp: &Stamp) -> Result<(), PowError> {
+        if stamp.challenge != *self {
+            return Err(PowError::WrongChallenge);
+        }
+        if stamp.leading_zeros() < u32::from(self.bits) {
+            return Err(PowError::InsufficientWork);
+        }
+        Ok(())
+    }
+
+    fn blocks(&self) -> (AesBlock, AesBlock, AesBlock) {
+        let mut t = [0u8; 16];
+        t[..15].copy_from_slice(DOMAIN);
+        t[15] = self.bits;
+        (load(&self.seed[..16]), load(&self.seed[16..]), load(&t))
+    }
+}
+
+fn leading_zeros((h0, h1): (AesBlock, AesBlock)) -> u32 {
+    let mut h = [0u8; 32];
+    store(&mut h[..16], h0);
+    store(&mut h[16..], h1);
+    match h.iter().position(|&b| b != 0) {
+        Some(i) => i as u32 * 8 + h[i].leading_zeros(),
+        None => 256,
+    }
+}
+
+/// A solution to a [`Challenge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stamp {
+    challenge: Challenge,
+    nonce: u128,
+}
+
+impl Stamp {
+    /// Creates a stamp for the given challenge and nonce, without checking it.
+    pub fn new(challenge: Challenge, nonce: u128) -> Stamp {
+        Stamp { challenge, nonce }
+    }
+
+    /// Returns the challenge the stamp claims to solve.
+    pub fn challenge(&self) -> &Challenge {
+        &self.challenge
+    }
+
+    /// Returns the stamp's nonce.
+    pub fn nonce(&self) -> u128 {
+        self.nonce
+    }
+
+    /// Returns the number of leading zero bits in the stamp's hash.
+    pub fn leading_zeros(&self) -> u32 {
+        let (s0, s1, t) = self.challenge.blocks();
+        let nonce = from_u64(self.nonce as u64, (self.nonce >> 64) as u64);
+        leading_zeros(crate::areion512_dm(s0, s1, t, nonce))
+    }
+}
+
+impl fmt::Display for Stamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{VERSION}:{}:", self.challenge.bits)?;
+        for b in self.challenge.seed {
+            write!(f, "{b:02x}")?;
+        }
+        write!(f, ":{:x}", self.nonce)
+    }
+}
+
+impl FromStr for Stamp {
+    type Err = PowError;
+
+    /// Parses a stamp, accepting only the canonical encoding.
+    fn from_str(s: &str) -> Result<Stamp, PowError> {
+        let mut parts = s.split(':');
+        let (Some(VERSION), Some(bits), Some(seed), Some(nonce), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(PowError::Malformed);
+        };
+
+        let is_hex = |s: &str| s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+        let bits = bits.parse::<u8>().map_err(|_| PowError::Malformed)?;
+        if seed.len() != 64 || !is_hex(seed) || !is_hex(nonce) {
+            return Err(PowError::Malformed);
+        }
+        let mut seed_bytes = [0u8; 32];
+        for (b, hex) in seed_bytes.iter_mut().zip(seed.as_bytes().chunks_exact(2)) {
+            let hex = core::str::from_utf8(hex).map_err(|_| PowError::Malformed)?;
+            *b = u8::from_str_radix(hex, 16).map_err(|_| PowError::Malformed)?;
+        }
+        let nonce = u128::from_str_radix(nonce, 16).map_err(|_| PowError::Malformed)?;
+
+        let stamp = Stamp { challenge: Challenge::new(bits, seed_bytes), nonce };
+        // Reject leading zeros and other non-canonical encodings, e.g. of the bits.
+        if stamp.to_string() != s {
+            return Err(PowError::Malformed);
+        }
+        Ok(stamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::AreionRng;
+
+    use expect_test::expect;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn solve_and_verify() {
+        let challenge = Challenge::generate(12, AreionRng::from_seed([7; 32]));
+        let stamp = challenge.solve();
+        assert!(stamp.leading_zeros() >= 12);
+        assert_eq!(challenge.verify(&stamp), Ok(()));
+        assert_eq!(*stamp.challenge(), challenge);
+
+        // Every smaller nonce fails, so the lanes find the first solution.
+        for nonce in 0..stamp.nonce() {
+            assert_eq!(
+                challenge.verify(&Stamp::new(challenge, nonce)),
+                Err(PowError::InsufficientWork)
+            );
+        }
+    }
+
+    #[test]
+    fn solve_range() {
+        let challenge = Challenge::new(8, [1; 32]);
+        let first = challenge.solve();
+        assert_eq!(challenge.solve_range(0..first.nonce()), None);
+        assert_eq!(challenge.solve_range(first.nonce()..first.nonce() + 1), Some(first));
+        assert_eq!(challenge.solve_range(first.nonce() - 2..first.nonce() + 5), Some(first));
+
+        let next = challenge.solve_range(first.nonce() + 1..u128::MAX).expect("should solve");
+        assert!(next.nonce() > first.nonce());
+        assert_eq!(challenge.verify(&next), Ok(()));
+
+        // The search doesn't overflow at the end of the nonce space.
+        if let Some(stamp) = challenge.solve_range(u128::MAX - 9..u128::MAX) {
+            assert_eq!(challenge.verify(&stamp), Ok(()));
+        }
+    }
+
+    #[test]
+    fn zero_difficulty() {
+        let challenge = Challenge::new(0, [0; 32]);
+        assert_eq!(challenge.solve().nonce(), 0);
+    }
+
+    #[test]
+    fn difficulty_is_bound() {
+        let challenge = Challenge::new(10, [2; 32]);
+        let stamp = challenge.solve();
+
+        // A stamp for an easier challenge with the same seed doesn't count.
+        let easier = Challenge::new(9, [2; 32]);
+        assert_eq!(easier.verify(&stamp), Err(PowError::WrongChallenge));
+        let other = Challenge::new(10, [3; 32]);
+        assert_eq!(other.verify(&stamp), Err(PowError::WrongChallenge));
+        assert_eq!(
+            challenge.verify(&Stamp::new(challenge, stamp.nonce() + 1)),
+            Err(PowError::InsufficientWork)
+        );
+    }
+
+    #[test]
+    fn stamp_encoding() {
+        let challenge = Challenge::new(16, [0xab; 32]);
+        let stamp = challenge.solve();
+        expect!["areion1:16:abababababababababababababababababababababababababababababababab:d374"]
+            .assert_eq(&stamp.to_string());
+        assert_eq!(stamp.to_string().parse(), Ok(stamp));
+
+        let s = Stamp::new(Challenge::new(255, [0; 32]), u128::MAX).to_string();
+        assert_eq!(s.parse::<Stamp>().map(|s| s.nonce()), Ok(u128::MAX));
+
+        let seed = "ab".repeat(32);
+        for malformed in [
+            "".to_string(),
+            format!("areion2:16:{seed}:ab"),
+            format!("areion1:16:{seed}"),
+            format!("areion1:16:{seed}:ab:"),
+            format!("areion1:016:{seed}:ab"),
+            format!("areion1:256:{seed}:ab"),
+            format!("areion1:-1:{seed}:ab"),
+            format!("areion1:16:{}:ab", &seed[2..]),
+            format!("areion1:16:{}:ab", seed.to_uppercase()),
+            format!("areion1:16:{seed}:0ab"),
+            format!("areion1:16:{seed}:AB"),
+            format!("areion1:16:{seed}:"),
+            format!("areion1:16:{seed}:+ab"),
+            format!("areion1:16:{seed}:{}", "f".repeat(33)),
+        ] {
+            assert_eq!(malformed.parse::<Stamp>(), Err(PowError::Malformed), "{malformed}");
+        }
+    }
+}
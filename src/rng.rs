@@ -0,0 +1,184 @@
+use core::fmt;
+
+use crate::intrinsics::*;
+
+use rand_core::{impls, CryptoRng, Error, RngCore, SeedableRng};
+
+const BLOCK_LEN: usize = 64;
+
+/// The number of blocks generated per refill.
+const BLOCKS: usize = 16;
+
+const BUF_LEN: usize = BLOCKS * BLOCK_LEN;
+
+const KEY_LEN: usize = 32;
+
+/// AreionRng, a cryptographically secure pseudorandom number generator using
+/// [fast key erasure](https://blog.cr.yp.to/20170723-random.html) with 256-bit seeds.
+///
+/// Output is generated a kilobyte at a time by running Areion512 in counter mode under the current
+/// key:
+///
+/// ```text
+/// B_i = P(K || LE64(i) || 0^192) ^ (K || 0^256)
+/// ```
+///
+/// The first 32 bytes of each batch immediately replace the key and the remainder is buffered as
+/// output. Buffered bytes are overwritten with zeros as they're returned, so compromising the
+/// generator's state reveals nothing about previous outputs.
+pub struct AreionRng {
+    key: [u8; KEY_LEN],
+    buf: [u8; BUF_LEN],
+    pos: usize,
+}
+
+impl AreionRng {
+    fn refill(&mut self) {
+        let (k0, k1) = (load(&self.key[..16]), load(&self.key[16..]));
+        let x: [AesBlock4; BLOCKS] =
+            core::array::from_fn(|i| (k0, k1, from_u64(i as u64, 0), zero()));
+        for (block, (x0, x1, x2, x3)) in
+            self.buf.chunks_exact_mut(BLOCK_LEN).zip(crate::areion512_par(x))
+        {
+            store(&mut block[..16], xor(x0, k0));
+            store(&mut block[16..32], xor(x1, k1));
+            store(&mut block[32..48], x2);
+            store(&mut block[48..], x3);
+        }
+
+        // Erase the old key by replacing it with the first part of the output.
+        self.key.copy_from_slice(&self.buf[..KEY_LEN]);
+        self.buf[..KEY_LEN].fill(0);
+        self.pos = KEY_LEN;
+    }
+}
+
+impl RngCore for AreionRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut dest = dest;
+        while !dest.is_empty() {
+            if self.pos == BUF_LEN {
+                self.refill();
+            }
+            let n = dest.len().min(BUF_LEN - self.pos);
+            let out = &mut self.buf[self.pos..self.pos + n];
+            dest[..n].copy_from_slice(out);
+            out.fill(0);
+            self.pos += n;
+            dest = &mut dest[n..];
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for AreionRng {}
+
+impl SeedableRng for AreionRng {
+    type Seed = [u8; KEY_LEN];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        AreionRng { key: seed, buf: [0; BUF_LEN], pos: BUF_LEN }
+    }
+}
+
+impl fmt::Debug for AreionRng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AreionRng { ... }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    fn rng() -> AreionRng {
+        AreionRng::from_seed(core::array::from_fn(|i| i as u8))
+    }
+
+    #[test]
+    fn test_vector() {
+        let mut rng = rng();
+        let mut out = [0u8; 100];
+        rng.fill_bytes(&mut out);
+        expect![[r#"
+            e8 7e 0d fd 94 22 bc dd bc c2 96 38 f9 46 f3 3f
+            ad b8 29 64 19 21 33 2a a2 c2 a2 0e 15 12 3a f8
+            70 22 ae 8a 14 28 32 75 bc 57 e0 3d e0 ac bc cd
+            f9 35 3b 8d 8b 5b 52 cd d5 40 2b 3d 8b 12 6c 5d
+            06 a2 5d 93 ee 9f 1d f6 74 64 99 bf 75 1a dd 2c
+            69 1c a1 70 d0 b3 e6 01 04 f5 6a ba e6 0c a0 01
+            7d 53 0c b4"#]]
+        .assert_eq(&hex_fmt(&out));
+
+        // The generator rekeys after every batch.
+        let mut out = [0u8; 32];
+        rng.fill_bytes(&mut [0u8; BUF_LEN - KEY_LEN - 100]);
+        rng.fill_bytes(&mut out);
+        expect![[r#"
+            7f de d2 0d 17 27 50 54 13 ff f1 d7 0f 69 32 0a
+            24 82 40 84 cd ef ac ff 4d 2c 43 bb 76 fe c5 df"#]]
+        .assert_eq(&hex_fmt(&out));
+    }
+
+    #[test]
+    fn integers() {
+        let mut rng = rng();
+        expect!["fd0d7ee8"].assert_eq(&format!("{:08x}", rng.next_u32()));
+        expect!["3896c2bcddbc2294"].assert_eq(&format!("{:016x}", rng.next_u64()));
+    }
+
+    #[test]
+    fn key_erasure() {
+        let mut rng = rng();
+        let seed = rng.key;
+        let mut out = [0u8; 10];
+        rng.fill_bytes(&mut out);
+
+        // The seed has been replaced and the returned output is gone from the buffer.
+        assert_ne!(rng.key, seed);
+        assert!(rng.buf[..KEY_LEN + out.len()].iter().all(|&b| b == 0));
+
+        // The next batch is under a new key.
+        let key = rng.key;
+        rng.fill_bytes(&mut [0u8; BUF_LEN]);
+        assert_ne!(rng.key, key);
+    }
+
+    #[quickcheck]
+    fn fills_are_contiguous(a: u16, b: u16) -> bool {
+        let (a, b) = (a as usize % 3000, b as usize % 3000);
+        let mut all = vec![0u8; a + b];
+        rng().fill_bytes(&mut all);
+
+        let mut rng = rng();
+        let (mut x, mut y) = (vec![0u8; a], vec![0u8; b]);
+        rng.fill_bytes(&mut x);
+        rng.fill_bytes(&mut y);
+        [x, y].concat() == all
+    }
+
+    #[quickcheck]
+    fn different_seeds_yield_different_outputs(a: u64, b: u64) -> bool {
+        let (mut x, mut y) = ([0u8; 32], [0u8; 32]);
+        AreionRng::seed_from_u64(a).fill_bytes(&mut x);
+        AreionRng::seed_from_u64(b).fill_bytes(&mut y);
+        (x != y) == (a != b)
+    }
+}
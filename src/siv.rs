@@ -0,0 +1,222 @@
+use crate::{Areion512Ctr, AreionHaifaMac};
+
+use aead::consts::{U0, U16, U64};
+use aead::generic_array::GenericArray;
+use aead::{AeadCore, AeadInPlace, Key, KeyInit, KeySizeUser, Nonce, Tag};
+use cipher::{KeyIvInit, StreamCipher};
+use digest::Mac;
+use subtle::ConstantTimeEq;
+
+/// Areion512-SIV, a nonce-misuse-resistant authenticated cipher in the style of
+/// [SIV](https://www.rfc-editor.org/rfc/rfc5297) with 512-bit keys, 128-bit nonces, and 128-bit
+/// tags.
+///
+/// The key is split into a 256-bit MAC key `K_1` and a 256-bit encryption key `K_2`. The synthetic
+/// IV is derived from the nonce, associated data, and plaintext with the keyed Areion512-HAIFA PRF,
+/// then used as both the tag and the nonce for Areion512-CTR:
+///
+/// ```text
+/// V = HAIFA-MAC(K_1, N || A || P || LE64(|A|) || LE64(|P|))[..16]
+/// C = P ^ CTR(K_2, V)
+/// ```
+///
+/// If a nonce is repeated, the only information leaked is whether the associated data and
+/// plaintext were also repeated. The nonce may be fixed for deterministic encryption.
+#[derive(Clone)]
+pub struct Areion512Siv {
+    mac: AreionHaifaMac,
+    key: [u8; 32],
+}
+
+impl Areion512Siv {
+    fn siv(&self, nonce: &Nonce<Self>, ad: &[u8], pt: &[u8]) -> Tag<Self> {
+        let mut mac = self.mac.clone();
+        mac.update(nonce);
+        mac.update(ad);
+        mac.update(pt);
+        mac.update(&(ad.len() as u64).to_le_bytes());
+        mac.update(&(pt.len() as u64).to_le_bytes());
+        GenericArray::clone_from_slice(&mac.finalize().into_bytes()[..16])
+    }
+
+    fn ctr(&self, siv: &Tag<Self>, buffer: &mut [u8]) {
+        Areion512Ctr::new(&self.key.into(), siv).apply_keystream(buffer);
+    }
+}
+
+impl KeySizeUser for Areion512Siv {
+    type KeySize = U64;
+}
+
+impl KeyInit for Areion512Siv {
+    fn new(key: &Key<Self>) -> Self {
+        let mac =
+            <AreionHaifaMac as KeyInit>::new_from_slice(&key[..32]).expect("should be a valid key");
+        Areion512Siv { mac, key: key[32..].try_into().expect("should be 32 bytes") }
+    }
+}
+
+impl AeadCore for Areion512Siv {
+    type NonceSize = U16;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+impl AeadInPlace for Areion512Siv {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> aead::Result<Tag<Self>> {
+        let siv = self.siv(nonce, associated_data, buffer);
+        self.ctr(&siv, buffer);
+        Ok(siv)
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> aead::Result<()> {
+        self.ctr(tag, buffer);
+        if self.siv(nonce, associated_data, buffer).ct_eq(tag).into() {
+            Ok(())
+        } else {
+            // Don't release unverified plaintext.
+            buffer.fill(0);
+            Err(aead::Error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+
+    fn seal(key: &[u8], nonce: &[u8], ad: &[u8], pt: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let cipher = Areion512Siv::new_from_slice(key).expect("should be a valid key");
+        let mut ct = pt.to_vec();
+        let tag = cipher
+            .encrypt_in_place_detached(GenericArray::from_slice(nonce), ad, &mut ct)
+            .expect("should encrypt");
+        (ct, tag.to_vec())
+    }
+
+    fn open(key: &[u8], nonce: &[u8], ad: &[u8], ct: &[u8], tag: &[u8]) -> Option<Vec<u8>> {
+        let cipher = Areion512Siv::new_from_slice(key).expect("should be a valid key");
+        let mut pt = ct.to_vec();
+        cipher
+            .decrypt_in_place_detached(
+                GenericArray::from_slice(nonce),
+                ad,
+                &mut pt,
+                GenericArray::from_slice(tag),
+            )
+            .ok()
+            .map(|_| pt)
+    }
+
+    #[test]
+    fn test_vector() {
+        let key = (0..64).collect::<Vec<u8>>();
+        let nonce = (64..80).collect::<Vec<u8>>();
+        let pt = (0..100).collect::<Vec<u8>>();
+        let (ct, tag) = seal(&key, &nonce, b"header", &pt);
+        expect![[r#"
+            bb 8b 8b d9 f1 67 51 87 af 3a 9c cd ce a6 11 31
+            ac fb c9 5e a7 25 ec 05 1b 86 84 05 ce ef b6 a4
+            99 84 29 a0 01 1b 5d 0a 83 e8 b2 4a 45 d7 e8 8d
+            ca 20 f8 07 aa 66 8f 00 c8 86 00 09 23 3d 0b 6a
+            f7 21 3c 90 8f 85 b3 44 28 1b fa 1a 4d f2 1c b2
+            dd 65 ee cc 68 81 60 88 d4 e1 2b 68 26 d9 1a e1
+            d1 97 f6 c3"#]]
+        .assert_eq(&hex_fmt(&ct));
+        expect!["43 74 00 69 c6 f6 1c 79 fb bb fe 9d 25 3d 8b ab"].assert_eq(&hex_fmt(&tag));
+    }
+
+    #[test]
+    fn deterministic() {
+        let (key, nonce) = ([7u8; 64], [9u8; 16]);
+        let (ct_a, tag_a) = seal(&key, &nonce, b"ad", b"message");
+        let (ct_b, tag_b) = seal(&key, &nonce, b"ad", b"message");
+        assert_eq!((&ct_a, &tag_a), (&ct_b, &tag_b));
+
+        // Reusing a nonce with a different message produces an unrelated ciphertext.
+        let (ct_c, tag_c) = seal(&key, &nonce, b"ad", b"messagf");
+        assert_ne!(tag_a, tag_c);
+        assert_ne!(ct_a[..6], ct_c[..6]);
+    }
+
+    #[test]
+    fn tag_tampering() {
+        let (key, nonce) = ([7u8; 64], [9u8; 16]);
+        for len in [0, 1, 63, 64, 65, 200] {
+            let pt = vec![0xcc; len];
+            let (ct, tag) = seal(&key, &nonce, b"ad", &pt);
+            assert_eq!(open(&key, &nonce, b"ad", &ct, &tag), Some(pt));
+
+            assert_eq!(open(&key, &[8; 16], b"ad", &ct, &tag), None, "nonce, len={len}");
+            assert_eq!(open(&[6; 64], &nonce, b"ad", &ct, &tag), None, "key, len={len}");
+            for i in 0..ct.len() {
+                let mut ct = ct.clone();
+                ct[i] ^= 1;
+                assert_eq!(open(&key, &nonce, b"ad", &ct, &tag), None, "ct[{i}]");
+            }
+            for i in 0..tag.len() {
+                let mut tag = tag.clone();
+                tag[i] ^= 1;
+                assert_eq!(open(&key, &nonce, b"ad", &ct, &tag), None, "tag[{i}]");
+            }
+        }
+    }
+
+    #[test]
+    fn associated_data_substitution() {
+        let (key, nonce) = ([7u8; 64], [9u8; 16]);
+        let (ct_a, tag_a) = seal(&key, &nonce, b"user=alice", b"balance=100");
+        let (ct_b, tag_b) = seal(&key, &nonce, b"user=bob", b"balance=100");
+
+        // Swapping associated data between messages fails.
+        assert_eq!(open(&key, &nonce, b"user=bob", &ct_a, &tag_a), None);
+        assert_eq!(open(&key, &nonce, b"user=alice", &ct_b, &tag_b), None);
+
+        // Moving bytes between the associated data and the plaintext fails.
+        let (ct, tag) = seal(&key, &nonce, b"ab", b"c");
+        assert_ne!(seal(&key, &nonce, b"a", b"bc").1, tag);
+        assert_eq!(open(&key, &nonce, b"", &ct, &tag), None);
+    }
+
+    #[test]
+    fn failed_decryption_clears_buffer() {
+        let cipher = Areion512Siv::new(&[7u8; 64].into());
+        let nonce = [9u8; 16].into();
+        let mut buf = vec![0xcc; 100];
+        let tag = cipher.encrypt_in_place_detached(&nonce, b"", &mut buf).expect("should encrypt");
+        assert!(cipher.decrypt_in_place_detached(&nonce, b"x", &mut buf, &tag).is_err());
+        assert_eq!(buf, vec![0; 100]);
+    }
+
+    #[quickcheck]
+    fn round_trip(key: Vec<u8>, nonce: Vec<u8>, ad: Vec<u8>, pt: Vec<u8>) -> bool {
+        let (mut key, mut nonce) = (key, nonce);
+        key.resize(64, 0);
+        nonce.resize(16, 0);
+        let (ct, tag) = seal(&key, &nonce, &ad, &pt);
+        open(&key, &nonce, &ad, &ct, &tag) == Some(pt)
+    }
+
+    #[test]
+    fn fuzz() {
+        bolero::check!().with_type::<(Vec<u8>, Vec<u8>, [u8; 16])>().for_each(|(ad, ct, tag)| {
+            assert_eq!(open(&[0; 64], &[0; 16], ad, ct, tag), None);
+        });
+    }
+}
@@ -0,0 +1,693 @@
+//! A sparse Merkle tree for authenticated key-value maps with 256-bit keys.
+//!
+//! The tree has a leaf for every possible key, almost all of which are empty. Empty leaves hash to
+//! zero, and the hash of an empty subtree of each height is precomputed, so the root of a tree with
+//! `n` keys is computed without ever visiting empty subtrees. In memory, paths which lead to a
+//! single key are compressed, so the tree stores `O(n)` nodes.
+//!
+//! * Leaves are hashed as `DM(Areion512-MD(value), DM(key, IV_leaf))`.
+//! * The interior node at depth `d` is hashed as `DM(right, DM(left, IV_node(d)))`.
+//!
+//! `DM(m, h)` is an Areion512-DM compression of the 256-bit block `m` with the chaining value `h`.
+//! The chaining values are the last 256 bits of the SHA2-512 IV, distinct from the Areion512-MD IV
+//! and the [Merkle log](crate::merkle)'s interior node IV, with a 128-bit tweak XORed into their
+//! last word. The tweak's second-to-last byte is `0x01` for leaves and `0x02` for interior nodes,
+//! and its last byte is the node's depth, from zero at the root to 255 just above the leaves.
+//!
+//! Every hash is a two-block chain from a chaining value which no caller-chosen input affects, so
+//! leaves, interior nodes at each depth, and Areion512-MD digests are domain separated: a hash of
+//! one kind equal to a hash of another requires a collision between chains from different IVs.
+//! This also makes the hashes of the same children differ by position, so that, e.g., the empty
+//! subtree of each height has a distinct hash.
+//!
+//! A [`Proof`] of a key contains the 256 sibling hashes along the key's path, and proves either
+//! that the key has a particular value or that it's absent. Proofs are encoded as a 256-bit bitmap
+//! of the siblings which aren't empty subtrees, followed by those siblings' hashes.
+
+use std::mem;
+use std::sync::OnceLock;
+
+use crate::intrinsics::*;
+use crate::merkle::ProofError;
+use crate::Areion512Md;
+
+use digest::Digest;
+use hex_literal::hex;
+
+/// The number of bytes in a hash.
+pub const HASH_LEN: usize = 32;
+
+/// A leaf, subtree, or root hash.
+pub type Hash = [u8; HASH_LEN];
+
+/// A key in the tree.
+pub type Key = [u8; 32];
+
+/// The number of levels of interior nodes.
+const DEPTH: usize = 256;
+
+/// The chaining value which the leaf and node tweaks are XORed into: the last 256 bits of the
+/// SHA2-512 IV.
+const IV: [[u8; 16]; 2] =
+    [hex!("510e527fade682d19b05688c2b3e6c1f"), hex!("1f83d9abfb41bd6b5be0cd19137e2179")];
+
+const LEAF: u8 = 0x01;
+const NODE: u8 = 0x02;
+
+/// Hashes two blocks with Areion512-DM, starting from the chaining value for `flag` and `depth`.
+fn dm(left: &[u8; 32], right: &[u8; 32], flag: u8, depth: u8) -> Hash {
+    let mut t = [0u8; 16];
+    t[14] = flag;
+    t[15] = depth;
+    let (h0, h1) = crate::areion512_dm(
+        load(&left[..16]),
+        load(&left[16..]),
+        load(&IV[0]),
+        xor(load(&IV[1]), load(&t)),
+    );
+    let (h0, h1) = crate::areion512_dm(load(&right[..16]), load(&right[16..]), h0, h1);
+    let mut out = [0u8; HASH_LEN];
+    store(&mut out[..16], h0);
+    store(&mut out[16..], h1);
+    out
+}
+
+fn leaf_hash(key: &Key, value: &[u8]) -> Hash {
+    dm(key, &Areion512Md::digest(value).into(), LEAF, 0)
+}
+
+fn node_hash(depth: usize, left: &Hash, right: &Hash) -> Hash {
+    dm(left, right, NODE, depth as u8)
+}
+
+/// Returns the hash of an empty subtree whose root is at the given depth.
+fn empty(depth: usize) -> &'static Hash {
+    static EMPTY: OnceLock<Vec<Hash>> = OnceLock::new();
+    &EMPTY.get_or_init(|| {
+        let mut empty = vec![[0u8; HASH_LEN]; DEPTH + 1];
+        for d in (0..DEPTH).rev() {
+            empty[d] = node_hash(d, &empty[d + 1], &empty[d + 1]);
+        }
+        empty
+    })[depth]
+}
+
+/// Returns the hash of an empty tree.
+pub fn empty_root() -> Hash {
+    *empty(0)
+}
+
+/// Returns the bit of `key` which selects the child of a node at `depth`.
+fn bit(key: &Key, depth: usize) -> usize {
+    usize::from(key[depth / 8] >> (7 - depth % 8) & 1)
+}
+
+/// Returns the number of leading bits which `a` and `b` have in common.
+fn common_prefix(a: &Key, b: &Key) -> usize {
+    a.iter()
+        .zip(b)
+        .position(|(a, b)| a != b)
+        .map_or(DEPTH, |i| i * 8 + (a[i] ^ b[i]).leading_zeros() as usize)
+}
+
+/// Hashes the subtree at depth `from` containing `key` up to depth `to`, through empty siblings.
+fn lift(mut h: Hash, from: usize, to: usize, key: &Key) -> Hash {
+    for d in (to..from).rev() {
+        h = if bit(key, d) == 0 {
+            node_hash(d, &h, empty(d + 1))
+        } else {
+            node_hash(d, empty(d + 1), &h)
+        };
+    }
+    h
+}
+
+/// A node of the path-compressed tree.
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf {
+        key: Key,
+        value: Vec<u8>,
+        hash: Hash,
+    },
+    /// A node at `depth` with two non-empty subtrees, which may be deeper than `depth + 1`.
+    Branch {
+        depth: u8,
+        /// Any key in the subtree, whose first `depth` bits are the node's position.
+        key: Key,
+        children: [Box<Node>; 2],
+        /// The hashes of the children lifted to `depth + 1`, or `None` if they've changed.
+        up: [Option<Hash>; 2],
+    },
+}
+
+impl Node {
+    fn key(&self) -> &Key {
+        match self {
+            Node::Leaf { key, .. } | Node::Branch { key, .. } => key,
+        }
+    }
+
+    fn depth(&self) -> usize {
+        match self {
+            Node::Leaf { .. } => DEPTH,
+            Node::Branch { depth, .. } => usize::from(*depth),
+        }
+    }
+
+    /// Returns `true` if `key` is in this node's subtree.
+    fn covers(&self, key: &Key) -> bool {
+        common_prefix(self.key(), key) >= self.depth()
+    }
+
+    /// Recomputes any changed hashes in the subtree, returning the hash at the node's depth.
+    fn rehash(&mut self) -> Hash {
+        match self {
+            Node::Leaf { hash, .. } => *hash,
+            Node::Branch { depth, children, up, .. } => {
+                let depth = usize::from(*depth);
+                for (child, up) in children.iter_mut().zip(up.iter_mut()) {
+                    if up.is_none() {
+                        let h = child.rehash();
+                        *up = Some(lift(h, child.depth(), depth + 1, child.key()));
+                    }
+                }
+                let [Some(left), Some(right)] = up else { unreachable!("children were rehashed") };
+                node_hash(depth, left, right)
+            }
+        }
+    }
+
+    /// Inserts a leaf, returning the key's previous value.
+    fn insert(node: &mut Box<Node>, leaf: Node) -> Option<Vec<u8>> {
+        let c = common_prefix(node.key(), leaf.key());
+        match &mut **node {
+            Node::Leaf { value, hash, .. } if c == DEPTH => {
+                let Node::Leaf { value: new_value, hash: new_hash, .. } = leaf else {
+                    unreachable!("should be a leaf")
+                };
+                *hash = new_hash;
+                return Some(mem::replace(value, new_value));
+            }
+            Node::Branch { depth, children, up, .. } if c >= usize::from(*depth) => {
+                let b = bit(leaf.key(), usize::from(*depth));
+                up[b] = None;
+                return Node::insert(&mut children[b], leaf);
+            }
+            _ => {}
+        }
+
+        // The new key leaves this subtree's path at depth c, so it needs a new branch there.
+        let key = *leaf.key();
+        let placeholder = Node::Leaf { key, value: Vec::new(), hash: [0; HASH_LEN] };
+        let old = Box::new(mem::replace(&mut **node, placeholder));
+        let children =
+            if bit(&key, c) == 0 { [Box::new(leaf), old] } else { [old, Box::new(leaf)] };
+        **node = Node::Branch { depth: c as u8, key, children, up: [None, None] };
+        None
+    }
+
+    /// Removes a key, returning the remaining subtree and the key's value, if it was present.
+    fn remove(node: Box<Node>, key: &Key) -> (Option<Box<Node>>, Option<Vec<u8>>) {
+        if !node.covers(key) {
+            return (Some(node), None);
+        }
+        match *node {
+            Node::Leaf { value, .. } => (None, Some(value)),
+            Node::Branch { depth, key: branch_key, children: [left, right], mut up } => {
+                let b = bit(key, usize::from(depth));
+                let (child, other) = if b == 0 { (left, right) } else { (right, left) };
+                let (child, removed) = Node::remove(child, key);
+                let Some(child) = child else {
+                    // A branch with a single subtree collapses into it.
+                    return (Some(other), removed);
+                };
+                if removed.is_some() {
+                    up[b] = None;
+                }
+                let children = if b == 0 { [child, other] } else { [other, child] };
+                (Some(Box::new(Node::Branch { depth, key: branch_key, children, up })), removed)
+            }
+        }
+    }
+}
+
+/// A sparse Merkle tree mapping 256-bit keys to values.
+#[derive(Debug, Clone)]
+pub struct SparseMerkleTree {
+    top: Option<Box<Node>>,
+    len: usize,
+    root: Hash,
+}
+
+impl Default for SparseMerkleTree {
+    fn default() -> Self {
+        SparseMerkleTree::new()
+    }
+}
+
+impl SparseMerkleTree {
+    /// Creates an empty tree.
+    pub fn new() -> SparseMerkleTree {
+        SparseMerkleTree { top: None, len: 0, root: empty_root() }
+    }
+
+    /// Returns the number of keys in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the tree has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the root hash of the tree.
+    pub fn root(&self) -> Hash {
+        self.root
+    }
+
+    /// Returns the value of a key.
+    pub fn get(&self, key: &Key) -> Option<&[u8]> {
+        let mut node = self.top.as_deref()?;
+        loop {
+            match node {
+                Node::Leaf { key: k, value, .. } => return (k == key).then_some(value),
+                Node::Branch { depth, children, .. } => {
+                    if !node.covers(key) {
+                        return None;
+                    }
+                    node = &children[bit(key, usize::from(*depth))];
+                }
+            }
+        }
+    }
+
+    /// Sets the value of a key, returning its previous value.
+    pub fn insert(&mut self, key: Key, value: impl Into<Vec<u8>>) -> Option<Vec<u8>> {
+        let old = self.set(key, value.into());
+        self.rehash();
+        old
+    }
+
+    /// Removes a key, returning its value.
+    pub fn remove(&mut self, key: &Key) -> Option<Vec<u8>> {
+        let old = self.unset(key);
+        self.rehash();
+        old
+    }
+
+    /// Applies a batch of changes, setting each key to `Some` value or removing it if `None`. If a
+    /// key appears more than once, its last change wins.
+    ///
+    /// Each changed node is rehashed once, no matter how many changed keys are below it.
+    pub fn update(&mut self, changes: impl IntoIterator<Item = (Key, Option<Vec<u8>>)>) {
+        for (key, value) in changes {
+            match value {
+                Some(value) => self.set(key, value),
+                None => self.unset(&key),
+            };
+        }
+        self.rehash();
+    }
+
+    /// Returns a proof of the key's value, or of its absence.
+    pub fn prove(&self, key: &Key) -> Proof {
+        let mut siblings = vec![None; DEPTH];
+        let mut node = self.top.as_deref();
+        while let Some(n) = node {
+            let c = common_prefix(n.key(), key);
+            if c < n.depth() {
+                // The key leaves this subtree's path at depth c, so everything else below is empty.
+                let h = match n {
+                    Node::Leaf { hash, .. } => *hash,
+                    Node::Branch { depth, up: [Some(left), Some(right)], .. } => {
+                        node_hash(usize::from(*depth), left, right)
+                    }
+                    Node::Branch { .. } => unreachable!("tree should be rehashed"),
+                };
+                siblings[c] = Some(lift(h, n.depth(), c + 1, n.key()));
+                break;
+            }
+            node = match n {
+                Node::Leaf { .. } => None,
+                Node::Branch { depth, children, up, .. } => {
+                    let d = usize::from(*depth);
+                    let b = bit(key, d);
+                    siblings[d] = up[1 - b];
+                    Some(&children[b])
+                }
+            };
+        }
+        Proof { siblings }
+    }
+
+    fn set(&mut self, key: Key, value: Vec<u8>) -> Option<Vec<u8>> {
+        let leaf = Node::Leaf { key, hash: leaf_hash(&key, &value), value };
+        let old = match &mut self.top {
+            None => {
+                self.top = Some(Box::new(leaf));
+                None
+            }
+            Some(top) => Node::insert(top, leaf),
+        };
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    fn unset(&mut self, key: &Key) -> Option<Vec<u8>> {
+        let (top, old) = match self.top.take() {
+            None => (None, None),
+            Some(top) => Node::remove(top, key),
+        };
+        self.top = top;
+        if old.is_some() {
+            self.len -= 1;
+        }
+        old
+    }
+
+    fn rehash(&mut self) {
+        self.root = match &mut self.top {
+            None => empty_root(),
+            Some(top) => {
+                let h = top.rehash();
+                lift(h, top.depth(), 0, top.key())
+            }
+        };
+    }
+}
+
+/// A proof of a key's value, or of its absence, in a [`SparseMerkleTree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    /// The sibling hashes along the key's path, indexed by the depth of their parent, or `None` for
+    /// empty subtrees.
+    siblings: Vec<Option<Hash>>,
+}
+
+impl Proof {
+    /// Decodes a proof from its bitmap of non-empty siblings and their hashes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Proof, ProofError> {
+        if bytes.len() < DEPTH / 8 {
+            return Err(ProofError::Malformed);
+        }
+        let (bitmap, mut hashes) = bytes.split_at(DEPTH / 8);
+        let bitmap: &Key = bitmap.try_into().expect("should be a bitmap");
+        let mut siblings = vec![None; DEPTH];
+        for (d, sibling) in siblings.iter_mut().enumerate() {
+            if bit(bitmap, d) == 1 {
+                let (h, rest) = hashes.split_first_chunk().ok_or(ProofError::Malformed)?;
+                *sibling = Some(*h);
+                hashes = rest;
+            }
+        }
+        if !hashes.is_empty() {
+            return Err(ProofError::Malformed);
+        }
+        Ok(Proof { siblings })
+    }
+
+    /// Encodes the proof as a bitmap of non-empty siblings, most significant bit first, followed by
+    /// their hashes in order of increasing depth.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bitmap = [0u8; DEPTH / 8];
+        let mut hashes = Vec::new();
+        for (d, sibling) in self.siblings.iter().enumerate() {
+            if let Some(h) = sibling {
+                bitmap[d / 8] |= 0x80 >> (d % 8);
+                hashes.extend_from_slice(h);
+            }
+        }
+        [bitmap.as_slice(), &hashes].concat()
+    }
+
+    /// Verifies that the tree with the given root maps `key` to `value`, or that `key` is absent if
+    /// `value` is `None`.
+    pub fn verify(&self, root: &Hash, key: &Key, value: Option<&[u8]>) -> Result<(), ProofError> {
+        let mut h = value.map_or(*empty(DEPTH), |value| leaf_hash(key, value));
+        for (d, sibling) in self.siblings.iter().enumerate().rev() {
+            let sibling = sibling.as_ref().unwrap_or(empty(d + 1));
+            h = if bit(key, d) == 0 {
+                node_hash(d, &h, sibling)
+            } else {
+                node_hash(d, sibling, &h)
+            };
+        }
+        if h == *root {
+            Ok(())
+        } else {
+            Err(ProofError::Invalid)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    use crate::tests::hex_fmt;
+    use crate::AreionRng;
+
+    use expect_test::expect;
+    use quickcheck_macros::quickcheck;
+    use rand_core::{RngCore, SeedableRng};
+
+    /// The root hash of the full tree, computed directly from its definition.
+    fn reference(leaves: &[(Key, Vec<u8>)], depth: usize) -> Hash {
+        if leaves.is_empty() {
+            return *empty(depth);
+        }
+        if depth == DEPTH {
+            return leaf_hash(&leaves[0].0, &leaves[0].1);
+        }
+        let split = leaves.partition_point(|(k, _)| bit(k, depth) == 0);
+        node_hash(
+            depth,
+            &reference(&leaves[..split], depth + 1),
+            &reference(&leaves[split..], depth + 1),
+        )
+    }
+
+    fn reference_root(map: &BTreeMap<Key, Vec<u8>>) -> Hash {
+        reference(&map.iter().map(|(k, v)| (*k, v.clone())).collect::<Vec<_>>(), 0)
+    }
+
+    /// Returns keys which are random, or which share long prefixes with each other.
+    fn keys(n: usize) -> Vec<Key> {
+        let mut rng = AreionRng::from_seed([7; 32]);
+        (0..n)
+            .map(|i| {
+                let mut key = [0u8; 32];
+                match i % 3 {
+                    0 => rng.fill_bytes(&mut key),
+                    1 => key[31] = i as u8,
+                    _ => key[(i / 3) % 32] = 0x80 >> (i % 8),
+                }
+                key
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hashes() {
+        expect![[r#"
+            31 4e 90 89 9b f8 ce 8d 2c 52 4a 97 1b 95 3a f1
+            71 b8 e6 e8 34 ef 69 8b 38 25 f7 b1 74 f8 2f 64"#]]
+        .assert_eq(&hex_fmt(&empty_root()));
+
+        let mut tree = SparseMerkleTree::new();
+        tree.insert([1; 32], b"one");
+        tree.insert([2; 32], b"two");
+        tree.insert([3; 32], b"three");
+        expect![[r#"
+            db f3 77 13 e2 5e f9 5e 7a 63 f2 a9 b3 ca 12 dd
+            7b d7 76 58 26 d1 2e d8 5f 11 06 ba e9 22 70 b7"#]]
+        .assert_eq(&hex_fmt(&tree.root()));
+    }
+
+    #[test]
+    fn leaves_are_not_nodes() {
+        // A leaf is a node-shaped hash of its key and value digest, so only the chaining value
+        // separates the two.
+        let (key, value) = ([7; 32], b"value");
+        let digest: Hash = Areion512Md::digest(value).into();
+        let leaf = leaf_hash(&key, value);
+        for d in 0..DEPTH {
+            assert_ne!(node_hash(d, &key, &digest), leaf, "depth {d}");
+        }
+        assert_ne!(node_hash(0, &[1; 32], &[2; 32]), node_hash(1, &[1; 32], &[2; 32]));
+    }
+
+    #[test]
+    fn empty_subtrees() {
+        assert_eq!(*empty(DEPTH), [0; HASH_LEN]);
+        assert_eq!(empty_root(), reference(&[], 0));
+        let tree = SparseMerkleTree::new();
+        assert_eq!(tree.root(), empty_root());
+        assert!(tree.is_empty());
+        assert_eq!(tree.get(&[0; 32]), None);
+    }
+
+    #[test]
+    fn roots_match_definition() {
+        let mut tree = SparseMerkleTree::new();
+        let mut map = BTreeMap::new();
+        for (i, key) in keys(60).into_iter().enumerate() {
+            let value = vec![i as u8; i % 5];
+            assert_eq!(tree.insert(key, value.clone()), map.insert(key, value));
+            assert_eq!(tree.root(), reference_root(&map), "i = {i}");
+        }
+        assert_eq!(tree.len(), map.len());
+
+        for (i, key) in keys(60).into_iter().enumerate().step_by(2) {
+            assert_eq!(tree.remove(&key), map.remove(&key));
+            assert_eq!(tree.remove(&key), None);
+            assert_eq!(tree.root(), reference_root(&map), "i = {i}");
+        }
+        assert_eq!(tree.len(), map.len());
+        for (key, value) in &map {
+            assert_eq!(tree.get(key), Some(value.as_slice()));
+        }
+        assert_eq!(tree.get(&keys(60)[0]), None);
+    }
+
+    #[test]
+    fn removing_everything_empties_the_tree() {
+        let mut tree = SparseMerkleTree::new();
+        for key in keys(20) {
+            tree.insert(key, b"value");
+        }
+        for key in keys(20).iter().rev() {
+            assert_eq!(tree.remove(key), Some(b"value".to_vec()));
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.root(), empty_root());
+    }
+
+    #[test]
+    fn batch_updates() {
+        let keys = keys(40);
+        let mut sequential = SparseMerkleTree::new();
+        for key in &keys[..30] {
+            sequential.insert(*key, b"old");
+        }
+        let mut batched = sequential.clone();
+
+        let changes = keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (*key, (i % 3 != 0).then(|| vec![i as u8])))
+            .chain([(keys[1], Some(b"last".to_vec())), (keys[2], None)])
+            .collect::<Vec<_>>();
+        for (key, value) in &changes {
+            match value {
+                Some(value) => sequential.insert(*key, value.clone()),
+                None => sequential.remove(key),
+            };
+        }
+        batched.update(changes);
+
+        assert_eq!(batched.root(), sequential.root());
+        assert_eq!(batched.len(), sequential.len());
+        assert_eq!(batched.get(&keys[1]), Some(&b"last"[..]));
+        assert_eq!(batched.get(&keys[2]), None);
+    }
+
+    #[test]
+    fn membership_proofs() {
+        let mut tree = SparseMerkleTree::new();
+        let keys = keys(30);
+        for key in &keys[..20] {
+            tree.insert(*key, key.to_vec());
+        }
+        let root = tree.root();
+
+        for key in &keys[..20] {
+            let proof = tree.prove(key);
+            assert_eq!(proof.verify(&root, key, Some(key)), Ok(()));
+            assert_eq!(proof.verify(&root, key, Some(b"other")), Err(ProofError::Invalid));
+            assert_eq!(proof.verify(&root, key, None), Err(ProofError::Invalid));
+            assert_eq!(proof.verify(&empty_root(), key, Some(key)), Err(ProofError::Invalid));
+        }
+        for key in &keys[20..] {
+            let proof = tree.prove(key);
+            assert_eq!(proof.verify(&root, key, None), Ok(()));
+            assert_eq!(proof.verify(&root, key, Some(key)), Err(ProofError::Invalid));
+        }
+
+        // A proof for one key doesn't prove anything about another.
+        let proof = tree.prove(&keys[0]);
+        assert_eq!(proof.verify(&root, &keys[1], Some(&keys[0])), Err(ProofError::Invalid));
+        assert_eq!(proof.verify(&root, &keys[1], None), Err(ProofError::Invalid));
+
+        // Every key is absent from the empty tree.
+        let proof = SparseMerkleTree::new().prove(&keys[0]);
+        assert_eq!(proof.verify(&empty_root(), &keys[0], None), Ok(()));
+        assert_eq!(proof.to_bytes(), [0; 32]);
+    }
+
+    #[test]
+    fn proof_encoding() {
+        let mut tree = SparseMerkleTree::new();
+        for key in keys(10) {
+            tree.insert(key, b"value");
+        }
+        let key = keys(10)[4];
+        let proof = tree.prove(&key);
+        let bytes = proof.to_bytes();
+        let siblings = proof.siblings.iter().flatten().count();
+        assert!(siblings > 0);
+        assert_eq!(bytes.len(), 32 + siblings * HASH_LEN);
+        assert_eq!(Proof::from_bytes(&bytes), Ok(proof.clone()));
+
+        assert_eq!(Proof::from_bytes(&bytes[..31]), Err(ProofError::Malformed));
+        assert_eq!(Proof::from_bytes(&bytes[..bytes.len() - 1]), Err(ProofError::Malformed));
+        assert_eq!(
+            Proof::from_bytes(&[bytes.as_slice(), &[0; 32]].concat()),
+            Err(ProofError::Malformed)
+        );
+
+        // Replacing an empty sibling with its hash doesn't change the result.
+        let d =
+            proof.siblings.iter().position(Option::is_none).expect("should have empty siblings");
+        let mut explicit = proof.clone();
+        explicit.siblings[d] = Some(*empty(d + 1));
+        assert_eq!(explicit.verify(&tree.root(), &key, Some(b"value")), Ok(()));
+        explicit.siblings[d] = Some([0; HASH_LEN]);
+        assert_eq!(explicit.verify(&tree.root(), &key, Some(b"value")), Err(ProofError::Invalid));
+    }
+
+    #[quickcheck]
+    fn insertion_order_is_irrelevant(entries: Vec<(u8, u8)>) -> bool {
+        // Spread the keys across different depths of the tree.
+        let key = |k: u8| {
+            let mut key = [0u8; 32];
+            key[usize::from(k) % 32] = k;
+            key
+        };
+        let mut tree = SparseMerkleTree::new();
+        let mut map = BTreeMap::new();
+        for &(k, v) in &entries {
+            tree.insert(key(k), [v]);
+            map.insert(key(k), vec![v]);
+        }
+        let mut reversed = SparseMerkleTree::new();
+        reversed.update(map.into_iter().rev().map(|(k, v)| (k, Some(v))));
+        tree.root() == reversed.root()
+    }
+
+    #[quickcheck]
+    fn different_maps_have_different_roots(a: BTreeMap<u8, u8>, b: BTreeMap<u8, u8>) -> bool {
+        let root = |map: &BTreeMap<u8, u8>| {
+            let mut tree = SparseMerkleTree::new();
+            tree.update(map.iter().map(|(&k, &v)| ([k; 32], Some(vec![v]))));
+            tree.root()
+        };
+        (a != b) == (root(&a) != root(&b))
+    }
+}
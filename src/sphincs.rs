@@ -0,0 +1,882 @@
+//! Stateless hash-based signatures in the style of
+//! [SLH-DSA](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.205.pdf) (SPHINCS+), instantiated
+//! with Areion.
+//!
+//! The hypertree, WOTS+, and FORS algorithms follow FIPS 205 exactly, with `n = 16` and `lg_w = 4`,
+//! for the [`Sphincs128s`] and [`Sphincs128f`] parameter sets. Only the tweakable hash functions
+//! differ. As with SPHINCS+-Haraka, the public seed keys the permutation: a 512-bit mask
+//! `K = K_0 || K_1 || K_2 || K_3` is derived once per key pair with Areion512-DM, and the
+//! fixed-length functions are Davies-Meyer compressions of the masked input, truncated to `n`
+//! bytes. Addresses are compressed to 16 bytes
+//! (`layer || tree || type || key pair || height/chain || index/hash`, with 1, 8, 1, 2, 2, and 2
+//! bytes), and the variable-length functions use Areion512-MD:
+//!
+//! ```text
+//! K_2i || K_2i+1          = DM-512(PK.seed || 0^383 || i)
+//! F(PK.seed, ADRS, M)     = DM-256((ADRS || M) ^ (K_0 || K_1))[..n]
+//! H(PK.seed, ADRS, L, R)  = DM-512((ADRS || L || R || 0^128) ^ K)[..n]
+//! PRF(PK.seed, SK.seed, ADRS) = F(PK.seed, ADRS, SK.seed)
+//! T_l(PK.seed, ADRS, M)   = MD(PK.seed || ADRS || M)[..n]
+//! PRF_msg(SK.prf, R', M)  = HMAC-MD(SK.prf, R' || M)[..n]
+//! H_msg(R, PK.seed, PK.root, M) = MGF1-MD(R || PK.seed || MD(R || PK.seed || PK.root || M), m)
+//! ```
+//!
+//! `PRF` and `F` share a construction but are separated by the address type. Messages are signed
+//! directly, as with `slh_sign_internal`, without a context string or pre-hashing.
+//!
+//! [`SigningKey`], [`VerifyingKey`], and [`Signature`] implement the RustCrypto [`signature`]
+//! traits. [`Signer`] signs deterministically, using `PK.seed` for the per-signature randomness,
+//! and [`RandomizedSigner`] draws it from an RNG.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use crate::intrinsics::*;
+use crate::pbkdf2::Hmac;
+use crate::Areion512Md;
+
+use digest::Digest;
+use rand_core::CryptoRngCore;
+use signature::{Error, Keypair, RandomizedSigner, SignatureEncoding, Signer, Verifier};
+
+/// The security parameter, in bytes.
+const N: usize = 16;
+
+/// The Winternitz parameter.
+const LG_W: usize = 4;
+const W: usize = 1 << LG_W;
+
+/// The number of WOTS+ chains for the message and checksum.
+const LEN1: usize = 8 * N / LG_W;
+const LEN2: usize = 3;
+const LEN: usize = LEN1 + LEN2;
+
+/// The address types.
+const WOTS_HASH: u8 = 0;
+const WOTS_PK: u8 = 1;
+const TREE: u8 = 2;
+const FORS_TREE: u8 = 3;
+const FORS_ROOTS: u8 = 4;
+const WOTS_PRF: u8 = 5;
+const FORS_PRF: u8 = 6;
+
+type Node = [u8; N];
+
+/// An SLH-DSA parameter set with `n = 16`.
+pub trait ParameterSet: Copy + fmt::Debug + Eq + private::Sealed {
+    /// The name of the parameter set.
+    const NAME: &'static str;
+
+    /// The total height of the hypertree.
+    const H: usize;
+
+    /// The number of hypertree layers.
+    const D: usize;
+
+    /// The height of each XMSS tree.
+    const HP: usize;
+
+    /// The height of each FORS tree.
+    const A: usize;
+
+    /// The number of FORS trees.
+    const K: usize;
+
+    /// The length of the message digest, in bytes.
+    const M: usize;
+
+    /// The length of a signature, in bytes.
+    const SIG_LEN: usize = N + Self::K * (1 + Self::A) * N + (Self::H + Self::D * LEN) * N;
+}
+
+/// The SLH-DSA-128s parameter set, with small signatures and slow signing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sphincs128s;
+
+impl ParameterSet for Sphincs128s {
+    const NAME: &'static str = "SPHINCS+-Areion-128s";
+    const H: usize = 63;
+    const D: usize = 7;
+    const HP: usize = 9;
+    const A: usize = 12;
+    const K: usize = 14;
+    const M: usize = 30;
+}
+
+/// The SLH-DSA-128f parameter set, with fast signing and large signatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sphincs128f;
+
+impl ParameterSet for Sphincs128f {
+    const NAME: &'static str = "SPHINCS+-Areion-128f";
+    const H: usize = 66;
+    const D: usize = 22;
+    const HP: usize = 3;
+    const A: usize = 6;
+    const K: usize = 33;
+    const M: usize = 34;
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for super::Sphincs128s {}
+    impl Sealed for super::Sphincs128f {}
+}
+
+/// An SLH-DSA signing key.
+#[derive(Clone)]
+pub struct SigningKey<P: ParameterSet> {
+    sk_seed: Node,
+    sk_prf: Node,
+    vk: VerifyingKey<P>,
+}
+
+impl<P: ParameterSet> SigningKey<P> {
+    /// The length of an encoded signing key, in bytes.
+    pub const LEN: usize = 4 * N;
+
+    /// Generates a signing key with seeds from the given RNG.
+    pub fn generate(mut rng: impl CryptoRngCore) -> SigningKey<P> {
+        let mut seeds = [[0u8; N]; 3];
+        for seed in &mut seeds {
+            rng.fill_bytes(seed);
+        }
+        SigningKey::from_seeds(&seeds[0], &seeds[1], &seeds[2])
+    }
+
+    /// Derives a signing key from the given secret seed, PRF key, and public seed.
+    pub fn from_seeds(sk_seed: &[u8; N], sk_prf: &[u8; N], pk_seed: &[u8; N]) -> SigningKey<P> {
+        let ctx = Ctx::new(pk_seed);
+        let mut adrs = Adrs { layer: P::D as u8 - 1, ..Adrs::default() };
+        let pk_root = xmss_node(&ctx, sk_seed, 0, P::HP as u32, &mut adrs);
+        SigningKey {
+            sk_seed: *sk_seed,
+            sk_prf: *sk_prf,
+            vk: VerifyingKey { pk_seed: *pk_seed, pk_root, _params: PhantomData },
+        }
+    }
+
+    /// Decodes a signing key from `SK.seed || SK.prf || PK.seed || PK.root`.
+    pub fn from_bytes(bytes: &[u8; 4 * N]) -> SigningKey<P> {
+        let (sk, pk) = bytes.split_at(2 * N);
+        SigningKey {
+            sk_seed: node(&sk[..N]),
+            sk_prf: node(&sk[N..]),
+            vk: VerifyingKey::from_bytes(&pk.try_into().expect("should be 32 bytes")),
+        }
+    }
+
+    /// Encodes the signing key as `SK.seed || SK.prf || PK.seed || PK.root`.
+    pub fn to_bytes(&self) -> [u8; 4 * N] {
+        let mut bytes = [0u8; 4 * N];
+        bytes[..N].copy_from_slice(&self.sk_seed);
+        bytes[N..2 * N].copy_from_slice(&self.sk_prf);
+        bytes[2 * N..].copy_from_slice(&self.vk.to_bytes());
+        bytes
+    }
+
+    fn sign_internal(&self, message: &[u8], addrnd: &[u8]) -> Signature<P> {
+        let VerifyingKey { pk_seed, pk_root, .. } = &self.vk;
+        let ctx = Ctx::new(pk_seed);
+        let mut sig = vec![0u8; P::SIG_LEN];
+        let (r, rest) = sig.split_at_mut(N);
+        let (sig_fors, sig_ht) = rest.split_at_mut(P::K * (1 + P::A) * N);
+
+        r.copy_from_slice(&prf_msg(&self.sk_prf, addrnd, message));
+        let digest = h_msg::<P>(r, pk_seed, pk_root, message);
+        let (md, idx_tree, idx_leaf) = split_digest::<P>(&digest);
+
+        let mut adrs = Adrs { tree: idx_tree, ..Adrs::default() };
+        adrs.set_type_and_clear(FORS_TREE);
+        adrs.keypair = idx_leaf as u16;
+        fors_sign::<P>(&ctx, md, &self.sk_seed, &adrs, sig_fors);
+        let pk_fors = fors_pk_from_sig::<P>(&ctx, sig_fors, md, &adrs);
+        ht_sign::<P>(&ctx, &pk_fors, &self.sk_seed, idx_tree, idx_leaf, sig_ht);
+
+        Signature { bytes: sig, _params: PhantomData }
+    }
+}
+
+impl<P: ParameterSet> Signer<Signature<P>> for SigningKey<P> {
+    fn try_sign(&self, msg: &[u8]) -> Result<Signature<P>, Error> {
+        Ok(self.sign_internal(msg, &self.vk.pk_seed))
+    }
+}
+
+impl<P: ParameterSet> RandomizedSigner<Signature<P>> for SigningKey<P> {
+    fn try_sign_with_rng(
+        &self,
+        rng: &mut impl CryptoRngCore,
+        msg: &[u8],
+    ) -> Result<Signature<P>, Error> {
+        let mut addrnd = [0u8; N];
+        rng.try_fill_bytes(&mut addrnd).map_err(|_| Error::new())?;
+        Ok(self.sign_internal(msg, &addrnd))
+    }
+}
+
+impl<P: ParameterSet> Keypair for SigningKey<P> {
+    type VerifyingKey = VerifyingKey<P>;
+
+    fn verifying_key(&self) -> VerifyingKey<P> {
+        self.vk.clone()
+    }
+}
+
+impl<P: ParameterSet> fmt::Debug for SigningKey<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SigningKey { ... }")
+    }
+}
+
+/// An SLH-DSA verifying key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyingKey<P: ParameterSet> {
+    pk_seed: Node,
+    pk_root: Node,
+    _params: PhantomData<P>,
+}
+
+impl<P: ParameterSet> VerifyingKey<P> {
+    /// The length of an encoded verifying key, in bytes.
+    pub const LEN: usize = 2 * N;
+
+    /// Decodes a verifying key from `PK.seed || PK.root`.
+    pub fn from_bytes(bytes: &[u8; 2 * N]) -> VerifyingKey<P> {
+        VerifyingKey {
+            pk_seed: node(&bytes[..N]),
+            pk_root: node(&bytes[N..]),
+            _params: PhantomData,
+        }
+    }
+
+    /// Encodes the verifying key as `PK.seed || PK.root`.
+    pub fn to_bytes(&self) -> [u8; 2 * N] {
+        let mut bytes = [0u8; 2 * N];
+        bytes[..N].copy_from_slice(&self.pk_seed);
+        bytes[N..].copy_from_slice(&self.pk_root);
+        bytes
+    }
+}
+
+impl<P: ParameterSet> Verifier<Signature<P>> for VerifyingKey<P> {
+    fn verify(&self, msg: &[u8], signature: &Signature<P>) -> Result<(), Error> {
+        let ctx = Ctx::new(&self.pk_seed);
+        let (r, rest) = signature.bytes.split_at(N);
+        let (sig_fors, sig_ht) = rest.split_at(P::K * (1 + P::A) * N);
+
+        let digest = h_msg::<P>(r, &self.pk_seed, &self.pk_root, msg);
+        let (md, idx_tree, idx_leaf) = split_digest::<P>(&digest);
+
+        let mut adrs = Adrs { tree: idx_tree, ..Adrs::default() };
+        adrs.set_type_and_clear(FORS_TREE);
+        adrs.keypair = idx_leaf as u16;
+        let pk_fors = fors_pk_from_sig::<P>(&ctx, sig_fors, md, &adrs);
+        if ht_verify::<P>(&ctx, &pk_fors, sig_ht, idx_tree, idx_leaf, &self.pk_root) {
+            Ok(())
+        } else {
+            Err(Error::new())
+        }
+    }
+}
+
+/// An SLH-DSA signature of [`ParameterSet::SIG_LEN`] bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature<P: ParameterSet> {
+    bytes: Vec<u8>,
+    _params: PhantomData<P>,
+}
+
+impl<P: ParameterSet> AsRef<[u8]> for Signature<P> {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl<P: ParameterSet> TryFrom<&[u8]> for Signature<P> {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != P::SIG_LEN {
+            return Err(Error::new());
+        }
+        Ok(Signature { bytes: bytes.to_vec(), _params: PhantomData })
+    }
+}
+
+impl<P: ParameterSet> From<Signature<P>> for Vec<u8> {
+    fn from(signature: Signature<P>) -> Vec<u8> {
+        signature.bytes
+    }
+}
+
+impl<P: ParameterSet> SignatureEncoding for Signature<P> {
+    type Repr = Vec<u8>;
+
+    fn encoded_len(&self) -> usize {
+        P::SIG_LEN
+    }
+}
+
+/// A hypertree address, stored in its compressed form.
+#[derive(Debug, Default, Clone, Copy)]
+struct Adrs {
+    layer: u8,
+    tree: u64,
+    typ: u8,
+    keypair: u16,
+    /// The chain address or tree height.
+    height: u16,
+    /// The hash address or tree index.
+    index: u16,
+}
+
+impl Adrs {
+    fn set_type_and_clear(&mut self, typ: u8) {
+        *self = Adrs { layer: self.layer, tree: self.tree, typ, ..Adrs::default() };
+    }
+
+    fn to_bytes(self) -> [u8; 16] {
+        let mut b = [0u8; 16];
+        b[0] = self.layer;
+        b[1..9].copy_from_slice(&self.tree.to_be_bytes());
+        b[9] = self.typ;
+        b[10..12].copy_from_slice(&self.keypair.to_be_bytes());
+        b[12..14].copy_from_slice(&self.height.to_be_bytes());
+        b[14..].copy_from_slice(&self.index.to_be_bytes());
+        b
+    }
+}
+
+/// The tweakable hash functions, keyed by the public seed.
+struct Ctx<'a> {
+    pk_seed: &'a Node,
+    k: AesBlock4,
+}
+
+impl<'a> Ctx<'a> {
+    fn new(pk_seed: &'a Node) -> Ctx<'a> {
+        let mut i = [0u8; 16];
+        let seed = load(pk_seed);
+        let (k0, k1) = crate::areion512_dm(seed, zero(), zero(), load(&i));
+        i[15] = 1;
+        let (k2, k3) = crate::areion512_dm(seed, zero(), zero(), load(&i));
+        Ctx { pk_seed, k: (k0, k1, k2, k3) }
+    }
+
+    #[inline]
+    fn f(&self, adrs: &Adrs, m: &Node) -> Node {
+        let (y0, _) =
+            crate::areion256_dm(xor(load(&adrs.to_bytes()), self.k.0), xor(load(m), self.k.1));
+        let mut out = [0u8; N];
+        store(&mut out, y0);
+        out
+    }
+
+    #[inline]
+    fn h(&self, adrs: &Adrs, l: &Node, r: &Node) -> Node {
+        let (y0, _) = crate::areion512_dm(
+            xor(load(&adrs.to_bytes()), self.k.0),
+            xor(load(l), self.k.1),
+            xor(load(r), self.k.2),
+            self.k.3,
+        );
+        let mut out = [0u8; N];
+        store(&mut out, y0);
+        out
+    }
+
+    #[inline]
+    fn prf(&self, adrs: &Adrs, sk_seed: &Node) -> Node {
+        debug_assert!(adrs.typ == WOTS_PRF || adrs.typ == FORS_PRF);
+        self.f(adrs, sk_seed)
+    }
+
+    fn t(&self, adrs: &Adrs, nodes: &[Node]) -> Node {
+        let mut md = Areion512Md::new().chain_update(self.pk_seed).chain_update(adrs.to_bytes());
+        for node in nodes {
+            md.update(node);
+        }
+        node(&md.finalize()[..N])
+    }
+}
+
+fn prf_msg(sk_prf: &Node, opt_rand: &[u8], message: &[u8]) -> Node {
+    node(&Hmac::<Areion512Md>::new(sk_prf).mac(&[opt_rand, message])[..N])
+}
+
+fn h_msg<P: ParameterSet>(r: &[u8], pk_seed: &Node, pk_root: &Node, message: &[u8]) -> Vec<u8> {
+    let seed = Areion512Md::new()
+        .chain_update(r)
+        .chain_update(pk_seed)
+        .chain_update(pk_root)
+        .chain_update(message)
+        .finalize();
+    let mut digest = Vec::with_capacity(P::M.next_multiple_of(32));
+    for i in 0..P::M.div_ceil(32) as u32 {
+        digest.extend_from_slice(
+            &Areion512Md::new()
+                .chain_update(r)
+                .chain_update(pk_seed)
+                .chain_update(seed)
+                .chain_update(i.to_be_bytes())
+                .finalize(),
+        );
+    }
+    digest.truncate(P::M);
+    digest
+}
+
+/// Splits a message digest into the FORS message and the tree and leaf indexes.
+fn split_digest<P: ParameterSet>(digest: &[u8]) -> (&[u8], u64, u32) {
+    let (md, rest) = digest.split_at((P::K * P::A).div_ceil(8));
+    let (tmp_idx_tree, tmp_idx_leaf) = rest.split_at((P::H - P::HP).div_ceil(8));
+    let to_int = |b: &[u8]| b.iter().fold(0u64, |x, &b| x << 8 | u64::from(b));
+    let idx_tree = to_int(tmp_idx_tree) & (u64::MAX >> (64 - (P::H - P::HP)));
+    let idx_leaf = to_int(tmp_idx_leaf) & ((1 << P::HP) - 1);
+    (md, idx_tree, idx_leaf as u32)
+}
+
+/// Splits `x` into `out.len()` big-endian integers of `b` bits each.
+fn base_2b(x: &[u8], b: usize, out: &mut [u32]) {
+    let (mut bytes, mut total, mut bits) = (x.iter(), 0u64, 0);
+    for o in out {
+        while bits < b {
+            total = total << 8 | u64::from(*bytes.next().expect("should have enough bytes"));
+            bits += 8;
+        }
+        bits -= b;
+        *o = ((total >> bits) & ((1 << b) - 1)) as u32;
+        total &= (1 << bits) - 1;
+    }
+}
+
+fn node(b: &[u8]) -> Node {
+    b.try_into().expect("should be a node")
+}
+
+fn chain(ctx: &Ctx, mut x: Node, i: usize, s: usize, adrs: &mut Adrs) -> Node {
+    for j in i..i + s {
+        adrs.index = j as u16;
+        x = ctx.f(adrs, &x);
+    }
+    x
+}
+
+/// Returns the WOTS+ chain lengths for the message and its checksum.
+fn wots_digits(m: &[u8]) -> [u32; LEN] {
+    let mut d = [0u32; LEN];
+    base_2b(m, LG_W, &mut d[..LEN1]);
+    let csum = d[..LEN1].iter().map(|&x| W as u32 - 1 - x).sum::<u32>() << 4;
+    base_2b(&csum.to_be_bytes()[2..], LG_W, &mut d[LEN1..]);
+    d
+}
+
+fn wots_pk_gen(ctx: &Ctx, sk_seed: &Node, adrs: &mut Adrs) -> Node {
+    let mut sk_adrs = *adrs;
+    sk_adrs.set_type_and_clear(WOTS_PRF);
+    sk_adrs.keypair = adrs.keypair;
+
+    let mut tmp = [[0u8; N]; LEN];
+    for (i, tmp) in tmp.iter_mut().enumerate() {
+        sk_adrs.height = i as u16;
+        let sk = ctx.prf(&sk_adrs, sk_seed);
+        adrs.height = i as u16;
+        *tmp = chain(ctx, sk, 0, W - 1, adrs);
+    }
+
+    let mut pk_adrs = *adrs;
+    pk_adrs.set_type_and_clear(WOTS_PK);
+    pk_adrs.keypair = adrs.keypair;
+    ctx.t(&pk_adrs, &tmp)
+}
+
+fn wots_sign(ctx: &Ctx, m: &[u8], sk_seed: &Node, adrs: &mut Adrs, sig: &mut [u8]) {
+    let mut sk_adrs = *adrs;
+    sk_adrs.set_type_and_clear(WOTS_PRF);
+    sk_adrs.keypair = adrs.keypair;
+
+    for (i, (&d, sig)) in wots_digits(m).iter().zip(sig.chunks_exact_mut(N)).enumerate() {
+        sk_adrs.height = i as u16;
+        let sk = ctx.prf(&sk_adrs, sk_seed);
+        adrs.height = i as u16;
+        sig.copy_from_slice(&chain(ctx, sk, 0, d as usize, adrs));
+    }
+}
+
+fn wots_pk_from_sig(ctx: &Ctx, sig: &[u8], m: &[u8], adrs: &mut Adrs) -> Node {
+    let mut tmp = [[0u8; N]; LEN];
+    for (i, ((&d, sig), tmp)) in
+        wots_digits(m).iter().zip(sig.chunks_exact(N)).zip(&mut tmp).enumerate()
+    {
+        adrs.height = i as u16;
+        *tmp = chain(ctx, node(sig), d as usize, W - 1 - d as usize, adrs);
+    }
+
+    let mut pk_adrs = *adrs;
+    pk_adrs.set_type_and_clear(WOTS_PK);
+    pk_adrs.keypair = adrs.keypair;
+    ctx.t(&pk_adrs, &tmp)
+}
+
+/// Computes the node at height `z` and index `i` of an XMSS tree.
+fn xmss_node(ctx: &Ctx, sk_seed: &Node, i: u32, z: u32, adrs: &mut Adrs) -> Node {
+    if z == 0 {
+        adrs.set_type_and_clear(WOTS_HASH);
+        adrs.keypair = i as u16;
+        wots_pk_gen(ctx, sk_seed, adrs)
+    } else {
+        let l = xmss_node(ctx, sk_seed, 2 * i, z - 1, adrs);
+        let r = xmss_node(ctx, sk_seed, 2 * i + 1, z - 1, adrs);
+        adrs.set_type_and_clear(TREE);
+        adrs.height = z as u16;
+        adrs.index = i as u16;
+        ctx.h(adrs, &l, &r)
+    }
+}
+
+fn xmss_sign(ctx: &Ctx, m: &[u8], sk_seed: &Node, idx: u32, adrs: &mut Adrs, sig: &mut [u8]) {
+    let (sig_wots, auth) = sig.split_at_mut(LEN * N);
+    for (j, auth) in auth.chunks_exact_mut(N).enumerate() {
+        let k = (idx >> j) ^ 1;
+        auth.copy_from_slice(&xmss_node(ctx, sk_seed, k, j as u32, adrs));
+    }
+
+    adrs.set_type_and_clear(WOTS_HASH);
+    adrs.keypair = idx as u16;
+    wots_sign(ctx, m, sk_seed, adrs, sig_wots);
+}
+
+fn xmss_pk_from_sig(ctx: &Ctx, idx: u32, sig: &[u8], m: &[u8], adrs: &mut Adrs) -> Node {
+    let (sig_wots, auth) = sig.split_at(LEN * N);
+    adrs.set_type_and_clear(WOTS_HASH);
+    adrs.keypair = idx as u16;
+    let mut node = wots_pk_from_sig(ctx, sig_wots, m, adrs);
+
+    adrs.set_type_and_clear(TREE);
+    adrs.index = idx as u16;
+    for (k, auth) in auth.chunks_exact(N).enumerate() {
+        adrs.height = k as u16 + 1;
+        adrs.index >>= 1;
+        node = if (idx >> k) & 1 == 0 {
+            ctx.h(adrs, &node, &self::node(auth))
+        } else {
+            ctx.h(adrs, &self::node(auth), &node)
+        };
+    }
+    node
+}
+
+fn ht_sign<P: ParameterSet>(
+    ctx: &Ctx,
+    m: &Node,
+    sk_seed: &Node,
+    mut idx_tree: u64,
+    mut idx_leaf: u32,
+    sig: &mut [u8],
+) {
+    let mut root = *m;
+    for (j, sig) in sig.chunks_exact_mut((P::HP + LEN) * N).enumerate() {
+        if j > 0 {
+            idx_leaf = (idx_tree & ((1 << P::HP) - 1)) as u32;
+            idx_tree >>= P::HP;
+        }
+        let mut adrs = Adrs { layer: j as u8, tree: idx_tree, ..Adrs::default() };
+        xmss_sign(ctx, &root, sk_seed, idx_leaf, &mut adrs, sig);
+        if j < P::D - 1 {
+            root = xmss_pk_from_sig(ctx, idx_leaf, sig, &root, &mut adrs);
+        }
+    }
+}
+
+fn ht_verify<P: ParameterSet>(
+    ctx: &Ctx,
+    m: &Node,
+    sig: &[u8],
+    mut idx_tree: u64,
+    mut idx_leaf: u32,
+    pk_root: &Node,
+) -> bool {
+    let mut node = *m;
+    for (j, sig) in sig.chunks_exact((P::HP + LEN) * N).enumerate() {
+        if j > 0 {
+            idx_leaf = (idx_tree & ((1 << P::HP) - 1)) as u32;
+            idx_tree >>= P::HP;
+        }
+        let mut adrs = Adrs { layer: j as u8, tree: idx_tree, ..Adrs::default() };
+        node = xmss_pk_from_sig(ctx, idx_leaf, sig, &node, &mut adrs);
+    }
+    node == *pk_root
+}
+
+fn fors_sk_gen(ctx: &Ctx, sk_seed: &Node, adrs: &Adrs, idx: u32) -> Node {
+    let mut sk_adrs = *adrs;
+    sk_adrs.set_type_and_clear(FORS_PRF);
+    sk_adrs.keypair = adrs.keypair;
+    sk_adrs.index = idx as u16;
+    ctx.prf(&sk_adrs, sk_seed)
+}
+
+/// Computes the node at height `z` and index `i` of the FORS trees.
+fn fors_node(ctx: &Ctx, sk_seed: &Node, i: u32, z: u32, adrs: &mut Adrs) -> Node {
+    if z == 0 {
+        let sk = fors_sk_gen(ctx, sk_seed, adrs, i);
+        adrs.height = 0;
+        adrs.index = i as u16;
+        ctx.f(adrs, &sk)
+    } else {
+        let l = fors_node(ctx, sk_seed, 2 * i, z - 1, adrs);
+        let r = fors_node(ctx, sk_seed, 2 * i + 1, z - 1, adrs);
+        adrs.height = z as u16;
+        adrs.index = i as u16;
+        ctx.h(adrs, &l, &r)
+    }
+}
+
+fn fors_indices<P: ParameterSet>(md: &[u8]) -> Vec<u32> {
+    let mut indices = vec![0u32; P::K];
+    base_2b(md, P::A, &mut indices);
+    indices
+}
+
+fn fors_sign<P: ParameterSet>(ctx: &Ctx, md: &[u8], sk_seed: &Node, adrs: &Adrs, sig: &mut [u8]) {
+    let mut adrs = *adrs;
+    for (i, (&idx, sig)) in
+        fors_indices::<P>(md).iter().zip(sig.chunks_exact_mut((1 + P::A) * N)).enumerate()
+    {
+        let i = i as u32;
+        let (sk, auth) = sig.split_at_mut(N);
+        sk.copy_from_slice(&fors_sk_gen(ctx, sk_seed, &adrs, (i << P::A) + idx));
+        for (j, auth) in auth.chunks_exact_mut(N).enumerate() {
+            let s = (idx >> j) ^ 1;
+            let node = fors_node(ctx, sk_seed, (i << (P::A - j)) + s, j as u32, &mut adrs);
+            auth.copy_from_slice(&node);
+        }
+    }
+}
+
+fn fors_pk_from_sig<P: ParameterSet>(ctx: &Ctx, sig: &[u8], md: &[u8], adrs: &Adrs) -> Node {
+    let mut adrs = *adrs;
+    let mut roots = vec![[0u8; N]; P::K];
+    for (i, ((&idx, sig), root)) in fors_indices::<P>(md)
+        .iter()
+        .zip(sig.chunks_exact((1 + P::A) * N))
+        .zip(&mut roots)
+        .enumerate()
+    {
+        let (sk, auth) = sig.split_at(N);
+        adrs.height = 0;
+        adrs.index = ((i << P::A) + idx as usize) as u16;
+        let mut node = ctx.f(&adrs, &self::node(sk));
+        for (j, auth) in auth.chunks_exact(N).enumerate() {
+            adrs.height = j as u16 + 1;
+            adrs.index >>= 1;
+            node = if (idx >> j) & 1 == 0 {
+                ctx.h(&adrs, &node, &self::node(auth))
+            } else {
+                ctx.h(&adrs, &self::node(auth), &node)
+            };
+        }
+        *root = node;
+    }
+
+    let mut pk_adrs = adrs;
+    pk_adrs.set_type_and_clear(FORS_ROOTS);
+    pk_adrs.keypair = adrs.keypair;
+    ctx.t(&pk_adrs, &roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+    use crate::AreionRng;
+
+    use expect_test::expect;
+    use rand_core::SeedableRng;
+
+    /// A known-answer test parsed from a `.rsp` file.
+    #[derive(Debug, Default)]
+    struct Kat {
+        count: usize,
+        seed: Vec<u8>,
+        msg: Vec<u8>,
+        pk: Vec<u8>,
+        sk: Vec<u8>,
+        sm: Vec<u8>,
+    }
+
+    impl Kat {
+        fn check<P: ParameterSet>(&self) {
+            let sk = SigningKey::<P>::from_seeds(
+                &self.seed[..N].try_into().expect("should be a seed"),
+                &self.seed[N..2 * N].try_into().expect("should be a seed"),
+                &self.seed[2 * N..].try_into().expect("should be a seed"),
+            );
+            let vk = sk.verifying_key();
+            assert_eq!(hex_fmt(&vk.to_bytes()), hex_fmt(&self.pk), "count = {}", self.count);
+            assert_eq!(hex_fmt(&sk.to_bytes()), hex_fmt(&self.sk), "count = {}", self.count);
+
+            let sig = sk.sign(&self.msg);
+            let sm = [sig.as_ref(), &self.msg].concat();
+            assert_eq!(hex_fmt(&sm), hex_fmt(&self.sm), "count = {}", self.count);
+
+            let (sig, msg) = self.sm.split_at(P::SIG_LEN);
+            let sig = Signature::<P>::try_from(sig).expect("should be a valid signature length");
+            assert!(vk.verify(msg, &sig).is_ok(), "count = {}", self.count);
+        }
+    }
+
+    fn parse_rsp(rsp: &str) -> Vec<Kat> {
+        let mut kats = Vec::new();
+        let mut kat = Kat::default();
+        for line in rsp.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').expect("should be a key-value pair");
+            let (key, value) = (key.trim(), value.trim());
+            let bytes = || hex::decode(value).expect("should be hex");
+            match key {
+                "count" => {
+                    kat =
+                        Kat { count: value.parse().expect("should be a number"), ..Kat::default() }
+                }
+                "seed" => kat.seed = bytes(),
+                "msg" => kat.msg = bytes(),
+                "pk" => kat.pk = bytes(),
+                "sk" => kat.sk = bytes(),
+                "mlen" | "smlen" => {}
+                "sm" => {
+                    kat.sm = bytes();
+                    kats.push(core::mem::take(&mut kat));
+                }
+                _ => panic!("unknown key: {key}"),
+            }
+        }
+        kats
+    }
+
+    #[test]
+    fn known_answers_128s() {
+        let kats = parse_rsp(include_str!("../testdata/sphincs_areion_128s.rsp"));
+        assert_eq!(kats.len(), 3);
+        for kat in kats {
+            kat.check::<Sphincs128s>();
+        }
+    }
+
+    #[test]
+    fn known_answers_128f() {
+        let kats = parse_rsp(include_str!("../testdata/sphincs_areion_128f.rsp"));
+        assert_eq!(kats.len(), 3);
+        for kat in kats {
+            kat.check::<Sphincs128f>();
+        }
+    }
+
+    #[test]
+    fn tweakable_hashes() {
+        let pk_seed = [0x01; N];
+        let ctx = Ctx::new(&pk_seed);
+        let adrs = Adrs { layer: 2, tree: 3, typ: TREE, keypair: 4, height: 5, index: 6 };
+        expect!["18 31 b0 50 65 dc 99 4c 9b f6 d9 a0 08 64 74 51"]
+            .assert_eq(&hex_fmt(&ctx.f(&adrs, &[0x02; N])));
+        expect!["6e 65 d5 4b e5 01 1e 36 dd b2 27 fe 7c 77 89 27"]
+            .assert_eq(&hex_fmt(&ctx.h(&adrs, &[0x02; N], &[0x03; N])));
+        expect!["46 e3 33 e8 7e 95 0a b6 a9 fc 53 fb ed 25 6a bc"]
+            .assert_eq(&hex_fmt(&ctx.t(&adrs, &[[0x02; N], [0x03; N], [0x04; N]])));
+        expect!["a5 31 06 9f ae 5a 12 30 f4 75 8e 28 0f 5a e2 26"]
+            .assert_eq(&hex_fmt(&prf_msg(&[0x05; N], &[0x06; N], b"message")));
+        expect![[r#"
+            54 92 f1 87 d7 97 cd ed 85 84 dd 7f 0e 0a 04 7a
+            c3 2f e0 68 8b 63 b0 c2 4b ba 3e 4f f6 f8 a7 9e
+            63 d0"#]]
+        .assert_eq(&hex_fmt(&h_msg::<Sphincs128f>(&[0x07; N], &pk_seed, &[0x08; N], b"message")));
+    }
+
+    #[test]
+    fn address_types_separate_hashes() {
+        let ctx = Ctx::new(&[0x01; N]);
+        let mut adrs = Adrs::default();
+        let hash = ctx.f(&adrs, &[0x02; N]);
+        adrs.set_type_and_clear(WOTS_PRF);
+        assert_ne!(hash, ctx.prf(&adrs, &[0x02; N]));
+        assert_ne!(hash, Ctx::new(&[0x03; N]).f(&Adrs::default(), &[0x02; N]));
+    }
+
+    #[test]
+    fn base_2b_splits_bits() {
+        let mut out = [0u32; 4];
+        base_2b(&[0x12, 0x34, 0x56], 6, &mut out);
+        assert_eq!(out, [0x04, 0x23, 0x11, 0x16]);
+
+        // The checksum is appended with the message digits.
+        let digits = wots_digits(&[0xff; N]);
+        assert_eq!(digits[..LEN1], [15; LEN1]);
+        assert_eq!(digits[LEN1..], [0, 0, 0]);
+    }
+
+    #[test]
+    fn signature_lengths() {
+        assert_eq!(Sphincs128s::SIG_LEN, 7856);
+        assert_eq!(Sphincs128f::SIG_LEN, 17088);
+        assert_eq!(SigningKey::<Sphincs128f>::LEN, 64);
+        assert_eq!(VerifyingKey::<Sphincs128f>::LEN, 32);
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let sk = SigningKey::<Sphincs128f>::generate(AreionRng::from_seed([7; 32]));
+        let vk = sk.verifying_key();
+        let sig = sk.sign(b"this is a message");
+        assert_eq!(sig.encoded_len(), Sphincs128f::SIG_LEN);
+        assert!(vk.verify(b"this is a message", &sig).is_ok());
+        assert!(vk.verify(b"this is a massage", &sig).is_err());
+
+        // Every part of the signature is checked.
+        for i in [0, N, N + Sphincs128f::K * (1 + Sphincs128f::A) * N, Sphincs128f::SIG_LEN - 1] {
+            let mut bad = sig.as_ref().to_vec();
+            bad[i] ^= 1;
+            let bad = Signature::try_from(bad.as_slice()).expect("should be a valid length");
+            assert!(vk.verify(b"this is a message", &bad).is_err(), "{i}");
+        }
+
+        let other = SigningKey::<Sphincs128f>::generate(AreionRng::from_seed([8; 32]));
+        assert!(other.verifying_key().verify(b"this is a message", &sig).is_err());
+    }
+
+    #[test]
+    fn randomized_signatures() {
+        let sk = SigningKey::<Sphincs128f>::generate(AreionRng::from_seed([7; 32]));
+        let mut rng = AreionRng::from_seed([9; 32]);
+        let (a, b) =
+            (sk.sign_with_rng(&mut rng, b"message"), sk.sign_with_rng(&mut rng, b"message"));
+        assert_ne!(a, b);
+        assert_ne!(a, sk.sign(b"message"));
+        assert!(sk.verifying_key().verify(b"message", &a).is_ok());
+        assert!(sk.verifying_key().verify(b"message", &b).is_ok());
+    }
+
+    #[test]
+    fn key_encoding() {
+        let sk = SigningKey::<Sphincs128f>::generate(AreionRng::from_seed([7; 32]));
+        let decoded = SigningKey::<Sphincs128f>::from_bytes(&sk.to_bytes());
+        assert_eq!(decoded.to_bytes(), sk.to_bytes());
+        assert_eq!(decoded.sign(b"message"), sk.sign(b"message"));
+
+        let vk = sk.verifying_key();
+        assert_eq!(VerifyingKey::from_bytes(&vk.to_bytes()), vk);
+        assert_eq!(format!("{sk:?}"), "SigningKey { ... }");
+    }
+
+    #[test]
+    fn invalid_signature_lengths() {
+        assert!(Signature::<Sphincs128f>::try_from(&[0u8; 17087][..]).is_err());
+        assert!(Signature::<Sphincs128s>::try_from(&[0u8; 17088][..]).is_err());
+        assert!(Signature::<Sphincs128s>::try_from(&[0u8; 7856][..]).is_ok());
+    }
+}
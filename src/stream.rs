@@ -0,0 +1,347 @@
+//! Online authenticated encryption of large messages with the
+//! [STREAM](https://eprint.iacr.org/2015/189.pdf) construction over [`Areion512Opp`].
+//!
+//! A message is split into segments, each of which is encrypted and authenticated separately with
+//! a nonce containing a fixed 11-byte prefix, a 32-bit big-endian segment counter, and a flag
+//! marking the last segment. Segments can't be reordered, dropped, or truncated without failing
+//! decryption, and the stream can be processed without buffering more than a single segment.
+//!
+//! [`EncryptorBE32`] and [`DecryptorBE32`] operate on segments directly. With the `std` feature,
+//! [`EncryptWriter`] and [`DecryptReader`] adapt them to [`std::io::Write`] and [`std::io::Read`],
+//! using [`SEGMENT_LEN`]-byte plaintext segments.
+
+use crate::Areion512Opp;
+
+/// The STREAM construction with 32-bit big-endian counters over [`Areion512Opp`].
+pub type StreamBE32 = aead::stream::StreamBE32<Areion512Opp>;
+
+/// An online encryptor for segmented messages.
+pub type EncryptorBE32 = aead::stream::EncryptorBE32<Areion512Opp>;
+
+/// An online decryptor for segmented messages.
+pub type DecryptorBE32 = aead::stream::DecryptorBE32<Areion512Opp>;
+
+/// The nonce prefix for [`StreamBE32`].
+pub type Nonce = aead::stream::Nonce<Areion512Opp, StreamBE32>;
+
+/// The number of bytes in a plaintext segment used by [`EncryptWriter`] and [`DecryptReader`].
+pub const SEGMENT_LEN: usize = 64 * 1024;
+
+/// The number of bytes in an authentication tag.
+pub const TAG_LEN: usize = 16;
+
+#[cfg(feature = "std")]
+pub use self::io::{DecryptReader, EncryptWriter};
+
+#[cfg(feature = "std")]
+mod io {
+    use std::io::{self, Read, Write};
+
+    use super::*;
+
+    use aead::Key;
+
+    /// A [`Write`] adapter which encrypts data in [`SEGMENT_LEN`]-byte segments.
+    ///
+    /// [`EncryptWriter::finish`] must be called to encrypt the final segment; otherwise, the stream
+    /// will fail to decrypt as truncated. If a segment can't be written to the inner writer, the
+    /// ciphertext is incomplete and every later call returns an error.
+    pub struct EncryptWriter<W: Write> {
+        inner: W,
+        encryptor: EncryptorBE32,
+        buf: Vec<u8>,
+        failed: bool,
+    }
+
+    impl<W: Write> EncryptWriter<W> {
+        /// Creates a new writer which writes the ciphertext to `inner`.
+        pub fn new(key: &Key<Areion512Opp>, nonce: &Nonce, inner: W) -> EncryptWriter<W> {
+            EncryptWriter {
+                inner,
+                encryptor: EncryptorBE32::new(key, nonce),
+                buf: Vec::with_capacity(SEGMENT_LEN + TAG_LEN),
+                failed: false,
+            }
+        }
+
+        /// Encrypts and writes the final segment, returning the inner writer.
+        pub fn finish(mut self) -> io::Result<W> {
+            if self.failed {
+                return Err(write_failed());
+            }
+            self.encryptor.encrypt_last_in_place(b"", &mut self.buf).map_err(too_long)?;
+            self.inner.write_all(&self.buf)?;
+            self.inner.flush()?;
+            Ok(self.inner)
+        }
+    }
+
+    impl<W: Write> Write for EncryptWriter<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.failed {
+                return Err(write_failed());
+            }
+
+            // Only encrypt a full segment once more data is written, so the final segment is never
+            // encrypted as an intermediate one.
+            if self.buf.len() == SEGMENT_LEN && !buf.is_empty() {
+                self.encryptor.encrypt_next_in_place(b"", &mut self.buf).map_err(too_long)?;
+
+                // The encryptor has advanced past the segment and the buffer holds its ciphertext,
+                // so the stream can't be resumed if it isn't written.
+                self.inner.write_all(&self.buf).inspect_err(|_| self.failed = true)?;
+                self.buf.clear();
+            }
+
+            let n = buf.len().min(SEGMENT_LEN - self.buf.len());
+            self.buf.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    fn too_long(_: aead::Error) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, "stream too long")
+    }
+
+    fn write_failed() -> io::Error {
+        io::Error::other("segment failed to write")
+    }
+
+    /// A [`Read`] adapter which decrypts and verifies data in [`SEGMENT_LEN`]-byte segments.
+    ///
+    /// Each segment is verified before any of its plaintext is returned. Verification failures,
+    /// including truncated streams, are returned as [`io::ErrorKind::InvalidData`] errors.
+    pub struct DecryptReader<R: Read> {
+        inner: R,
+        decryptor: Option<DecryptorBE32>,
+        buf: Vec<u8>,
+        plaintext: Vec<u8>,
+        pos: usize,
+        failed: bool,
+    }
+
+    impl<R: Read> DecryptReader<R> {
+        /// Creates a new reader which reads the ciphertext from `inner`.
+        pub fn new(key: &Key<Areion512Opp>, nonce: &Nonce, inner: R) -> DecryptReader<R> {
+            DecryptReader {
+                inner,
+                decryptor: Some(DecryptorBE32::new(key, nonce)),
+                buf: Vec::with_capacity(SEGMENT_LEN + TAG_LEN + 1),
+                plaintext: Vec::with_capacity(SEGMENT_LEN + TAG_LEN),
+                pos: 0,
+                failed: false,
+            }
+        }
+
+        fn next_segment(&mut self) -> io::Result<()> {
+            // Read one byte past the end of a full segment to detect whether it's the last.
+            let seg_len = SEGMENT_LEN + TAG_LEN;
+            while self.buf.len() <= seg_len {
+                let start = self.buf.len();
+                self.buf.resize(seg_len + 1, 0);
+                match self.inner.read(&mut self.buf[start..]) {
+                    Ok(0) => {
+                        self.buf.truncate(start);
+                        break;
+                    }
+                    Ok(n) => self.buf.truncate(start + n),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => self.buf.truncate(start),
+                    Err(e) => {
+                        self.buf.truncate(start);
+                        return Err(e);
+                    }
+                }
+            }
+
+            self.plaintext.clear();
+            self.pos = 0;
+            let res = if self.buf.len() > seg_len {
+                self.plaintext.extend_from_slice(&self.buf[..seg_len]);
+                self.buf.drain(..seg_len);
+                let decryptor = self.decryptor.as_mut().expect("should not be finished");
+                decryptor.decrypt_next_in_place(b"", &mut self.plaintext)
+            } else {
+                self.plaintext.append(&mut self.buf);
+                let decryptor = self.decryptor.take().expect("should not be finished");
+                decryptor.decrypt_last_in_place(b"", &mut self.plaintext)
+            };
+
+            // Once a segment fails to decrypt, the rest of the stream can't be trusted.
+            res.map_err(|_| {
+                self.plaintext.clear();
+                self.failed = true;
+                failed()
+            })
+        }
+    }
+
+    impl<R: Read> Read for DecryptReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            while self.pos == self.plaintext.len() {
+                if self.failed {
+                    return Err(failed());
+                }
+                if self.decryptor.is_none() {
+                    return Ok(0);
+                }
+                self.next_segment()?;
+            }
+
+            let n = buf.len().min(self.plaintext.len() - self.pos);
+            buf[..n].copy_from_slice(&self.plaintext[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    fn failed() -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, "segment failed to decrypt")
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::io::{self, Read, Write};
+
+    use super::*;
+
+    use aead::generic_array::GenericArray;
+    use quickcheck_macros::quickcheck;
+
+    const KEY: [u8; 32] = [7; 32];
+    const NONCE: [u8; 11] = [9; 11];
+
+    fn encrypt(pt: &[u8]) -> Vec<u8> {
+        let mut w = EncryptWriter::new(&KEY.into(), &NONCE.into(), Vec::new());
+        w.write_all(pt).expect("should write");
+        w.finish().expect("should finish")
+    }
+
+    fn decrypt(ct: &[u8]) -> io::Result<Vec<u8>> {
+        let mut pt = Vec::new();
+        DecryptReader::new(&KEY.into(), &NONCE.into(), ct).read_to_end(&mut pt)?;
+        Ok(pt)
+    }
+
+    #[test]
+    fn segments() {
+        let key = GenericArray::from([7u8; 32]);
+        let nonce = GenericArray::from([9u8; 11]);
+
+        let mut encryptor = EncryptorBE32::new(&key, &nonce);
+        let mut a = b"first segment".to_vec();
+        encryptor.encrypt_next_in_place(b"", &mut a).expect("should encrypt");
+        let mut b = b"second segment".to_vec();
+        encryptor.encrypt_last_in_place(b"", &mut b).expect("should encrypt");
+
+        // Segments decrypt in order.
+        let mut decryptor = DecryptorBE32::new(&key, &nonce);
+        let mut pt = a.clone();
+        decryptor.decrypt_next_in_place(b"", &mut pt).expect("should decrypt");
+        assert_eq!(pt, b"first segment");
+        let mut pt = b.clone();
+        decryptor.decrypt_last_in_place(b"", &mut pt).expect("should decrypt");
+        assert_eq!(pt, b"second segment");
+
+        // Segments can't be reordered.
+        let mut decryptor = DecryptorBE32::new(&key, &nonce);
+        assert!(decryptor.decrypt_next_in_place(b"", &mut b.clone()).is_err());
+
+        // The stream can't be truncated.
+        let decryptor = DecryptorBE32::new(&key, &nonce);
+        assert!(decryptor.decrypt_last_in_place(b"", &mut a.clone()).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        for len in [0, 1, SEGMENT_LEN - 1, SEGMENT_LEN, SEGMENT_LEN + 1, 3 * SEGMENT_LEN + 7] {
+            let pt = (0..len).map(|i| i as u8).collect::<Vec<u8>>();
+            let ct = encrypt(&pt);
+            assert_eq!(ct.len(), len + len.div_ceil(SEGMENT_LEN).max(1) * TAG_LEN, "len={len}");
+            assert_eq!(decrypt(&ct).expect("should decrypt"), pt, "len={len}");
+        }
+    }
+
+    #[test]
+    fn truncation() {
+        let ct = encrypt(&vec![0xcc; 2 * SEGMENT_LEN + 100]);
+        for len in [0, 1, SEGMENT_LEN + TAG_LEN, 2 * (SEGMENT_LEN + TAG_LEN), ct.len() - 1] {
+            let err = decrypt(&ct[..len]).expect_err("should fail");
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "len={len}");
+        }
+    }
+
+    #[test]
+    fn reordering() {
+        let ct = encrypt(&vec![0xcc; 3 * SEGMENT_LEN]);
+        let seg = SEGMENT_LEN + TAG_LEN;
+        let mut swapped = ct.clone();
+        swapped[..seg].copy_from_slice(&ct[seg..2 * seg]);
+        swapped[seg..2 * seg].copy_from_slice(&ct[..seg]);
+        let err = decrypt(&swapped).expect_err("should fail");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn unverified_plaintext_is_not_released() {
+        let mut ct = encrypt(&[0xcc; 100]);
+        ct[50] ^= 1;
+        let mut r = DecryptReader::new(&KEY.into(), &NONCE.into(), ct.as_slice());
+        let mut buf = [0u8; 10];
+        assert!(r.read(&mut buf).is_err());
+        assert!(r.read(&mut buf).is_err());
+        assert_eq!(buf, [0; 10]);
+    }
+
+    #[test]
+    fn encrypt_writer_matches_encryptor() {
+        let pt = vec![0xaa; SEGMENT_LEN + 10];
+        let mut encryptor = EncryptorBE32::new(&KEY.into(), &GenericArray::from(NONCE));
+        let mut a = pt[..SEGMENT_LEN].to_vec();
+        encryptor.encrypt_next_in_place(b"", &mut a).expect("should encrypt");
+        let mut b = pt[SEGMENT_LEN..].to_vec();
+        encryptor.encrypt_last_in_place(b"", &mut b).expect("should encrypt");
+        assert_eq!(encrypt(&pt), [a, b].concat());
+    }
+
+    #[test]
+    fn failed_writes() {
+        /// A writer which accepts `n` bytes and then fails.
+        struct Failing(usize);
+
+        impl Write for Failing {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.0 == 0 {
+                    return Err(io::Error::other("full"));
+                }
+                let n = buf.len().min(self.0);
+                self.0 -= n;
+                Ok(n)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut w = EncryptWriter::new(&KEY.into(), &NONCE.into(), Failing(100));
+        w.write_all(&[0xcc; SEGMENT_LEN]).expect("should buffer a segment");
+        assert!(w.write(&[0xcc; 10]).is_err());
+        assert!(w.write(&[0xcc; 10]).is_err());
+        assert!(w.finish().is_err());
+    }
+
+    #[quickcheck]
+    fn small_writes(pt: Vec<u8>, chunk: u8) -> bool {
+        let mut w = EncryptWriter::new(&KEY.into(), &NONCE.into(), Vec::new());
+        for c in pt.chunks(chunk.max(1) as usize) {
+            w.write_all(c).expect("should write");
+        }
+        let ct = w.finish().expect("should finish");
+        ct == encrypt(&pt) && decrypt(&ct).ok() == Some(pt)
+    }
+}
@@ -0,0 +1,297 @@
+use core::fmt;
+
+use crate::intrinsics::*;
+use crate::mask::{Mask256, Mask512};
+
+use cipher::consts::{U16, U32};
+use cipher::{AlgorithmName, Key, KeyInit, KeySizeUser};
+
+/// Areion256-TEM, a tweakable Even-Mansour block cipher with 256-bit blocks, 128-bit keys, and
+/// 128-bit tweaks.
+///
+/// The tweak schedule derives a base mask from the tweak `T` and key `K` with a single permutation
+/// call, `L = P(T || K)`. The `i`th block encrypted under a tweak uses the mask `δ_i = φ^i(L)`,
+/// where `φ(x_0, ..., x_3) = (x_1, x_2, x_3, (x_0 <<< 3) ^ (x_3 >> 5))` over 64-bit words:
+///
+/// ```text
+/// E(K, T, i, M) = P(M ^ δ_i) ^ δ_i
+/// ```
+#[derive(Clone)]
+pub struct Areion256Tem {
+    k: AesBlock,
+}
+
+impl Areion256Tem {
+    fn mask(&self, tweak: &[u8; 16]) -> Mask256 {
+        let (l0, l1) = crate::areion256(load(tweak), self.k);
+        let mut l = [0u8; 32];
+        store(&mut l[..16], l0);
+        store(&mut l[16..], l1);
+        Mask256::from_bytes(&l)
+    }
+
+    /// Encrypts a single block under the given tweak.
+    pub fn encrypt_block(&self, tweak: &[u8; 16], block: &mut [u8; 32]) {
+        self.encrypt_blocks(tweak, core::slice::from_mut(block));
+    }
+
+    /// Decrypts a single block under the given tweak.
+    pub fn decrypt_block(&self, tweak: &[u8; 16], block: &mut [u8; 32]) {
+        self.decrypt_blocks(tweak, core::slice::from_mut(block));
+    }
+
+    /// Encrypts a sequence of blocks under the given tweak, using the block index as an additional
+    /// tweak. Equivalent to encrypting a sector of `32 * blocks.len()` bytes.
+    pub fn encrypt_blocks(&self, tweak: &[u8; 16], blocks: &mut [[u8; 32]]) {
+        let mut delta = self.mask(tweak);
+        for block in blocks {
+            let (d0, d1) = (delta.block(0), delta.block(1));
+            let (x0, x1) =
+                crate::areion256(xor(load(&block[..16]), d0), xor(load(&block[16..]), d1));
+            store(&mut block[..16], xor(x0, d0));
+            store(&mut block[16..], xor(x1, d1));
+            delta = delta.phi();
+        }
+    }
+
+    /// Decrypts a sequence of blocks encrypted with [`Areion256Tem::encrypt_blocks`].
+    pub fn decrypt_blocks(&self, tweak: &[u8; 16], blocks: &mut [[u8; 32]]) {
+        let mut delta = self.mask(tweak);
+        for block in blocks {
+            let (d0, d1) = (delta.block(0), delta.block(1));
+            let (x0, x1) =
+                crate::inv_areion256(xor(load(&block[..16]), d0), xor(load(&block[16..]), d1));
+            store(&mut block[..16], xor(x0, d0));
+            store(&mut block[16..], xor(x1, d1));
+            delta = delta.phi();
+        }
+    }
+}
+
+impl KeySizeUser for Areion256Tem {
+    type KeySize = U16;
+}
+
+impl KeyInit for Areion256Tem {
+    fn new(key: &Key<Self>) -> Self {
+        Areion256Tem { k: load(key) }
+    }
+}
+
+impl AlgorithmName for Areion256Tem {
+    fn write_alg_name(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion256-TEM")
+    }
+}
+
+impl fmt::Debug for Areion256Tem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion256-TEM { ... }")
+    }
+}
+
+/// Areion512-TEM, a tweakable Even-Mansour block cipher with 512-bit blocks, 256-bit keys, and
+/// 256-bit tweaks.
+///
+/// The tweak schedule derives a base mask from the tweak `T` and key `K` with a single permutation
+/// call, `L = P(T || K)`. The `i`th block encrypted under a tweak uses the mask `δ_i = φ^i(L)`,
+/// where `φ(x_0, ..., x_7) = (x_1, ..., x_7, (x_0 <<< 29) ^ (x_1 << 9))` over 64-bit words:
+///
+/// ```text
+/// E(K, T, i, M) = P(M ^ δ_i) ^ δ_i
+/// ```
+#[derive(Clone)]
+pub struct Areion512Tem {
+    k: (AesBlock, AesBlock),
+}
+
+impl Areion512Tem {
+    pub(crate) fn mask(&self, tweak: &[u8; 32]) -> Mask512 {
+        let (k0, k1) = self.k;
+        let (l0, l1, l2, l3) = crate::areion512(load(&tweak[..16]), load(&tweak[16..]), k0, k1);
+        let mut l = [0u8; 64];
+        store(&mut l[..16], l0);
+        store(&mut l[16..32], l1);
+        store(&mut l[32..48], l2);
+        store(&mut l[48..], l3);
+        Mask512::from_bytes(&l)
+    }
+
+    /// Encrypts a single block under the given tweak.
+    pub fn encrypt_block(&self, tweak: &[u8; 32], block: &mut [u8; 64]) {
+        self.encrypt_blocks(tweak, core::slice::from_mut(block));
+    }
+
+    /// Decrypts a single block under the given tweak.
+    pub fn decrypt_block(&self, tweak: &[u8; 32], block: &mut [u8; 64]) {
+        self.decrypt_blocks(tweak, core::slice::from_mut(block));
+    }
+
+    /// Encrypts a sequence of blocks under the given tweak, using the block index as an additional
+    /// tweak. Equivalent to encrypting a sector of `64 * blocks.len()` bytes.
+    pub fn encrypt_blocks(&self, tweak: &[u8; 32], blocks: &mut [[u8; 64]]) {
+        let mut delta = self.mask(tweak);
+        for block in blocks {
+            let (d0, d1, d2, d3) = (delta.block(0), delta.block(1), delta.block(2), delta.block(3));
+            let (x0, x1, x2, x3) = crate::areion512(
+                xor(load(&block[..16]), d0),
+                xor(load(&block[16..32]), d1),
+                xor(load(&block[32..48]), d2),
+                xor(load(&block[48..]), d3),
+            );
+            store(&mut block[..16], xor(x0, d0));
+            store(&mut block[16..32], xor(x1, d1));
+            store(&mut block[32..48], xor(x2, d2));
+            store(&mut block[48..], xor(x3, d3));
+            delta = delta.phi();
+        }
+    }
+
+    /// Decrypts a sequence of blocks encrypted with [`Areion512Tem::encrypt_blocks`].
+    pub fn decrypt_blocks(&self, tweak: &[u8; 32], blocks: &mut [[u8; 64]]) {
+        let mut delta = self.mask(tweak);
+        for block in blocks {
+            let (d0, d1, d2, d3) = (delta.block(0), delta.block(1), delta.block(2), delta.block(3));
+            let (x0, x1, x2, x3) = crate::inv_areion512(
+                xor(load(&block[..16]), d0),
+                xor(load(&block[16..32]), d1),
+                xor(load(&block[32..48]), d2),
+                xor(load(&block[48..]), d3),
+            );
+            store(&mut block[..16], xor(x0, d0));
+            store(&mut block[16..32], xor(x1, d1));
+            store(&mut block[32..48], xor(x2, d2));
+            store(&mut block[48..], xor(x3, d3));
+            delta = delta.phi();
+        }
+    }
+}
+
+impl KeySizeUser for Areion512Tem {
+    type KeySize = U32;
+}
+
+impl KeyInit for Areion512Tem {
+    fn new(key: &Key<Self>) -> Self {
+        Areion512Tem { k: (load(&key[..16]), load(&key[16..])) }
+    }
+}
+
+impl AlgorithmName for Areion512Tem {
+    fn write_alg_name(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-TEM")
+    }
+}
+
+impl fmt::Debug for Areion512Tem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Areion512-TEM { ... }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::hex_fmt;
+
+    use expect_test::expect;
+    use quickcheck::{Arbitrary, Gen};
+    use quickcheck_macros::quickcheck;
+
+    #[derive(Debug, Clone)]
+    struct Bytes<const N: usize>([u8; N]);
+
+    impl<const N: usize> Arbitrary for Bytes<N> {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Bytes(core::array::from_fn(|_| u8::arbitrary(g)))
+        }
+    }
+
+    #[test]
+    fn areion256_tem_test_vector() {
+        let cipher = Areion256Tem::new(&[0x0f; 16].into());
+        let mut blocks = [[0xf0; 32]; 2];
+        cipher.encrypt_blocks(&[0xaa; 16], &mut blocks);
+        expect![[r#"
+            f8 73 53 70 33 bc 89 77 62 15 17 c5 13 01 6b 44
+            7c 0c 6d 2f f6 7d 12 27 59 f3 1c ee 3c 30 92 a6
+            cb b3 41 ff 9b dd c9 ff 8b 70 5c c6 09 ab bf 4e
+            ed b9 19 c2 7d e3 bd bf a1 0b 1f 41 f5 1f 2b 03"#]]
+        .assert_eq(&hex_fmt(blocks.as_flattened()));
+    }
+
+    #[test]
+    fn areion512_tem_test_vector() {
+        let cipher = Areion512Tem::new(&[0x0f; 32].into());
+        let mut blocks = [[0xf0; 64]; 2];
+        cipher.encrypt_blocks(&[0xaa; 32], &mut blocks);
+        expect![[r#"
+            17 53 34 c2 6b 02 f2 08 7b 75 53 6c 89 98 cb 73
+            2b 05 ac eb 6d 2d 22 1d 08 cb 74 78 27 6b a2 60
+            69 61 a4 7e 66 24 2e 7e f1 40 5f a4 a5 ac 61 34
+            f1 2b e5 0a 9b da 4c 44 74 ac 80 b1 b0 aa e6 81
+            8b b8 0e 64 82 01 ba fe 04 4b 69 19 05 3e ef 43
+            36 20 e6 04 65 66 f1 77 92 4b e7 de 59 66 3e 32
+            e6 2e 15 a4 a1 48 36 06 7b 6e 94 a2 37 ec a3 55
+            bb a4 d8 9f 36 3b 13 5e c2 4a 8a f0 2c 4c ea ce"#]]
+        .assert_eq(&hex_fmt(blocks.as_flattened()));
+    }
+
+    #[quickcheck]
+    fn areion256_tem_round_trip(key: Bytes<16>, tweak: Bytes<16>, blocks: Vec<Bytes<32>>) -> bool {
+        let cipher = Areion256Tem::new(&key.0.into());
+        let pt = blocks.iter().map(|b| b.0).collect::<Vec<_>>();
+        let mut ct = pt.clone();
+        cipher.encrypt_blocks(&tweak.0, &mut ct);
+        let encrypted = ct.iter().zip(&pt).all(|(c, p)| c != p);
+        cipher.decrypt_blocks(&tweak.0, &mut ct);
+        encrypted && ct == pt
+    }
+
+    #[quickcheck]
+    fn areion512_tem_round_trip(key: Bytes<32>, tweak: Bytes<32>, blocks: Vec<Bytes<64>>) -> bool {
+        let cipher = Areion512Tem::new(&key.0.into());
+        let pt = blocks.iter().map(|b| b.0).collect::<Vec<_>>();
+        let mut ct = pt.clone();
+        cipher.encrypt_blocks(&tweak.0, &mut ct);
+        let encrypted = ct.iter().zip(&pt).all(|(c, p)| c != p);
+        cipher.decrypt_blocks(&tweak.0, &mut ct);
+        encrypted && ct == pt
+    }
+
+    #[quickcheck]
+    fn areion256_tem_single_block_is_first_block(key: Bytes<16>, tweak: Bytes<16>) -> bool {
+        let cipher = Areion256Tem::new(&key.0.into());
+        let mut blocks = [[0x11; 32], [0x22; 32]];
+        cipher.encrypt_blocks(&tweak.0, &mut blocks);
+        let mut block = [0x11; 32];
+        cipher.encrypt_block(&tweak.0, &mut block);
+        block == blocks[0]
+    }
+
+    #[quickcheck]
+    fn areion512_tem_different_tweaks_yield_different_ciphertexts(
+        key: Bytes<32>,
+        a: Bytes<32>,
+        b: Bytes<32>,
+    ) -> bool {
+        let cipher = Areion512Tem::new(&key.0.into());
+        let (mut x, mut y) = ([0u8; 64], [0u8; 64]);
+        cipher.encrypt_block(&a.0, &mut x);
+        cipher.encrypt_block(&b.0, &mut y);
+        (x != y) == (a.0 != b.0)
+    }
+
+    #[quickcheck]
+    fn areion512_tem_identical_blocks_are_encrypted_differently(
+        key: Bytes<32>,
+        tweak: Bytes<32>,
+        block: Bytes<64>,
+    ) -> bool {
+        let cipher = Areion512Tem::new(&key.0.into());
+        let mut blocks = [block.0; 8];
+        cipher.encrypt_blocks(&tweak.0, &mut blocks);
+        (0..blocks.len()).all(|i| !blocks[i + 1..].contains(&blocks[i]))
+    }
+}